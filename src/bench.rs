@@ -0,0 +1,81 @@
+use crate::html_parser::tokenizer::tokenize;
+use crate::parser::css_parser::parse_stylesheet;
+use crate::parser::html_parse::{HtmlParse, SimpleHtmlParser, SpecHtmlParser};
+use crate::parser::html_parser::walk_tree;
+use std::time::{Duration, Instant};
+
+// one screenful of a typical article, repeated into the fixture
+const HTML_CHUNK: &str = r#"<div class="post" id="post">
+  <h2>Heading <small>with a note</small></h2>
+  <p>Some <b>bold</b>, <i>italic</i> and <a href="/next?page=2&amp;sort=new">linked</a> text,
+  with an entity &copy; and a <br> break.</p>
+  <ul><li>one</li><li>two<li>three</ul>
+  <table><tr><td>cell</td><td colspan=2>wide cell</td></tr></table>
+  <!-- a comment -->
+  <input type=text value=hello><textarea rows=2>typed</textarea>
+</div>
+"#;
+const HTML_REPEAT: usize = 2000;
+
+// only what the parser understands, so errors don't flood the report
+const CSS_CHUNK: &str = "body { margin: 8px; color: black; }
+div.post h2 { font-size: 150%; font-weight: bold; }
+.post p a:visited { color: purple; }
+#post li::before { content: '-'; }
+table td { padding: 2px 4px; border-width: 1px; border-color: gray; }
+p { line-height: 1.5; margin: 0 0 1em; }
+";
+const CSS_REPEAT: usize = 2000;
+
+// the fastest of these runs is reported, the others absorb warm-up and noise
+const ITERATIONS: usize = 5;
+
+/// Time the tokenizer, both HTML parsers and the CSS parser over large fixed inputs and
+/// print their throughput.
+pub fn run() {
+    let html = HTML_CHUNK.repeat(HTML_REPEAT);
+    let css = CSS_CHUNK.repeat(CSS_REPEAT);
+    println!(
+        "HTML fixture: {} bytes, CSS fixture: {} bytes",
+        html.len(),
+        css.len()
+    );
+
+    report("tokenize", "tokens", || tokenize(&html).len());
+
+    let parsers: [(&str, Box<dyn HtmlParse>); 2] = [
+        ("html parser", Box::new(SimpleHtmlParser)),
+        ("spec html parser", Box::new(SpecHtmlParser)),
+    ];
+    for (name, parser) in parsers {
+        report(name, "nodes", || count_nodes(parser.as_ref(), &html));
+    }
+
+    report("css parser", "rules", || parse_stylesheet(&css).len());
+}
+
+fn count_nodes(parser: &dyn HtmlParse, html: &str) -> usize {
+    parser.parse(html).map_or(0, |root| walk_tree(root).count())
+}
+
+/// Run `bench` a few times and print how many `unit`s per second its fastest run made.
+fn report(name: &str, unit: &str, mut bench: impl FnMut() -> usize) {
+    let mut best = Duration::MAX;
+    let mut count = 0;
+
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        count = bench();
+        best = best.min(start.elapsed());
+    }
+
+    println!(
+        "{:<18} {:>8} {} in {:>8.2?}, {:>12.0} {}/sec",
+        name,
+        count,
+        unit,
+        best,
+        count as f64 / best.as_secs_f64(),
+        unit
+    );
+}
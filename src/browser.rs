@@ -1,11 +1,31 @@
-use crate::constant::browser::{DEFAULT_BROWSER_PADDING, HEIGHT, SCROLL_STEP, WIDTH};
-use crate::layout::block_layout::BlockLayoutRef;
+use crate::constant::browser::{
+    CLIPBOARD_COMMANDS, CONNECTION_ERROR_MESSAGE, DEFAULT_BROWSER_PADDING, FIND_BAR_COLOR,
+    FIND_BAR_FONT_SIZE, FIND_BAR_HEIGHT, FIND_HIGHLIGHT_COLOR, FOCUS_RING_COLOR, FOCUS_RING_OFFSET,
+    FOCUS_RING_THICKNESS, HEIGHT, MAX_IMPORT_DEPTH, RESIZE_DEBOUNCE_MS, SCROLL_STEP,
+    SELECTION_COLOR, SMOOTH_SCROLL_EPSILON, SMOOTH_SCROLL_FACTOR, SMOOTH_SCROLL_FRAME_MS,
+    TOOLTIP_BORDER_COLOR, TOOLTIP_COLOR, TOOLTIP_DELAY_MS, TOOLTIP_OFFSET, TOOLTIP_PADDING,
+    WHEEL_LINE_HEIGHT, WIDTH, WINDOW_TITLE,
+};
+use crate::constant::html::{ANCHOR, ATTRIBUTE_KEY_HREF, BODY, HTML, PRE};
+use crate::constant::layout::READER_MAX_WIDTH;
+use crate::constant::net::{
+    CONTENT_TYPE_HTML, CONTENT_TYPE_TEXT_PREFIX, CONTENT_TYPE_XHTML, HEADER_REFERER,
+};
+use crate::html_parser::tokenizer::tokenize;
+use crate::layout::block_layout::{BlockLayout, BlockLayoutRef};
 use crate::layout::document_layout::{DocumentLayout, DocumentLayoutRef};
-use crate::layout::draw_command::DrawCommand;
-use crate::net::url::Url;
-use crate::parser::css_parser::{CSSParser, CSSRules};
-use crate::parser::html_node::HTMLNodeRef;
-use crate::parser::html_parser::{get_links, HTMLParser};
+use crate::layout::draw_command::{DrawCommand, DrawText, DrawTransform, find_in_lines};
+use crate::layout::font_manager::{FontManager, FontManagerRef};
+use crate::layout::render_options::RenderOptions;
+use crate::net::charset::{decode, detect_charset, same_encoding};
+use crate::net::url::{Response, Url, spawn_request};
+use crate::parser::css_parser::{CSSRules, Stylesheet, parse_stylesheet};
+use crate::parser::html_node::{HTMLNode, HTMLNodeData, HTMLNodeRef};
+use crate::parser::html_parse::{HtmlParse, SimpleHtmlParser};
+use crate::parser::html_parser::{
+    document_title, get_anchors, get_base_href, get_favicon, get_hrefs, get_links,
+    get_meta_charset, get_preloads,
+};
 use crate::parser::selector::cascade_priority;
 use crate::parser::style::style;
 use gl_rs as gl;
@@ -23,16 +43,23 @@ use skia_safe::gpu::gl::Format;
 use skia_safe::gpu::gl::FramebufferInfo;
 use skia_safe::gpu::gl::Interface;
 use skia_safe::gpu::{backend_render_targets, DirectContext, SurfaceOrigin};
-use skia_safe::{gpu, Color, ColorType, Paint, Surface};
+use skia_safe::font_style::{Slant, Weight};
+use skia_safe::{gpu, Color, ColorSpace, ColorType, Font, Paint, Point, Rect, Surface};
+use std::collections::{HashMap, HashSet};
 use std::ffi::CString;
 use std::io::Write;
 use std::num::NonZeroU32;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 use winit::application::ApplicationHandler;
 use winit::dpi::LogicalSize;
-use winit::event::WindowEvent;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
-use winit::keyboard::{Key, NamedKey};
-use winit::window::{Window, WindowAttributes, WindowId};
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+use winit::window::{CursorIcon, Window, WindowAttributes, WindowId};
 
 // Guarantee the drop order inside the FnMut closure. `Window` _must_ be dropped after
 // `DirectContext`.
@@ -45,77 +72,328 @@ struct Env {
     gr_context: DirectContext,
     gl_context: PossiblyCurrentContext,
     window: Window,
+    // what the skia surface is rebuilt from when the window is resized
+    fb_info: FramebufferInfo,
+    num_samples: usize,
+    stencil_size: usize,
+}
+
+/// Holds window resizes back until the size has stopped changing for a moment, so dragging
+/// the window edge rebuilds the surface once at the end instead of on every step.
+#[derive(Debug)]
+struct ResizeDebounce {
+    // the size the surface was last built for
+    committed: (u32, u32),
+    // the latest size asked for and when it's due
+    pending: Option<((u32, u32), Instant)>,
+}
+
+impl ResizeDebounce {
+    fn new(size: (u32, u32)) -> Self {
+        Self {
+            committed: size,
+            pending: None,
+        }
+    }
+
+    /// Record a new size, pushing the commit back by the debounce delay.
+    fn request(&mut self, size: (u32, u32), now: Instant) {
+        self.pending = (size != self.committed)
+            .then(|| (size, now + Duration::from_millis(RESIZE_DEBOUNCE_MS)));
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.pending.map(|(_, at)| at)
+    }
+
+    /// The pending size once it's due, which then becomes the committed one.
+    fn commit_due(&mut self, now: Instant) -> Option<(u32, u32)> {
+        let (size, at) = self.pending?;
+        if now < at {
+            return None;
+        }
+        self.pending = None;
+        self.committed = size;
+        Some(size)
+    }
 }
 
-#[derive(Default)]
 pub struct Browser {
     env: Option<Env>,
+    html_parser: Box<dyn HtmlParse>,
     scroll: f32,
+    // where the smooth scroll animation is heading, equal to scroll when idle
+    scroll_target: f32,
+    smooth_scroll: bool,
+    // caps the content width and centers it, like a reader view
+    reader_mode: bool,
+    // the page currently shown, used by reload
+    url: Option<Url>,
+    // the status line and headers of the page's response
+    response: Option<Response>,
+    // the page navigated away from, sent as Referer
+    referer: Option<Url>,
     nodes: Option<HTMLNodeRef>,
     document: Option<DocumentLayoutRef>,
     display_list: Vec<DrawCommand>,
-    default_style_sheet: CSSRules,
+    default_style_sheet: Stylesheet,
+    render_options: RenderOptions,
+    modifiers: ModifiersState,
+    // Some while the find bar is open
+    find_query: Option<String>,
+    find_highlights: Vec<Rect>,
+    find_index: usize,
+    font_manager: Option<FontManagerRef>,
+    // last cursor position in window coordinates
+    cursor: (f32, f32),
+    hovered_title: Option<String>,
+    // when the hovered title's tooltip is due, None once it shows or without a title
+    tooltip_at: Option<Instant>,
+    tooltip_visible: bool,
+    // whether the cursor is over a link, shown as a pointer
+    hovered_pointer: bool,
+    // the loaded page's `<title>`
+    title: Option<String>,
+    // the page's icon, found but not fetched or shown yet
+    favicon: Option<Url>,
+    // where each id or named anchor starts, rebuilt after every layout
+    anchors: HashMap<String, f32>,
+    // (anchor, focus) of the text selection in page coordinates, the anchor is where it began
+    selection: Option<(Point, Point)>,
+    // the left button is held, so moving extends the selection
+    selecting: bool,
+    // the element Tab moved to, drawn with a ring, Enter follows it if it is a link
+    focused: Option<HTMLNodeRef>,
+    // every page loaded, without fragments, for `:visited`
+    visited: HashSet<String>,
+    // the markup `load_html` showed, what reload renders again instead of fetching the url
+    loaded_html: Option<String>,
+    resize: ResizeDebounce,
+}
+
+const DEFAULT_STYLE_SHEET: &str = include_str!("asset/browser.css");
+
+impl Default for Browser {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Browser {
     pub fn new() -> Self {
+        Self::with_default_style_sheet(DEFAULT_STYLE_SHEET)
+    }
+
+    /// Load the UA stylesheet from disk at runtime, falling back to the embedded one.
+    pub fn with_stylesheet(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(css) => Self::with_default_style_sheet(&css),
+            Err(err) => {
+                eprintln!(
+                    "Failed to read stylesheet {}: {}, using the default one.",
+                    path.display(),
+                    err
+                );
+                Self::new()
+            }
+        }
+    }
+
+    fn with_default_style_sheet(css: &str) -> Self {
         Self {
             scroll: 0.0,
+            scroll_target: 0.0,
+            smooth_scroll: false,
+            reader_mode: false,
+            url: None,
+            response: None,
+            referer: None,
             env: None,
+            html_parser: Box::new(SimpleHtmlParser),
             nodes: None,
             document: None,
             display_list: Vec::new(),
-            default_style_sheet: CSSParser::new(include_str!("asset/browser.css"))
-                .parse()
-                .unwrap_or(Vec::new()),
+            default_style_sheet: parse_stylesheet(css),
+            render_options: RenderOptions::default(),
+            modifiers: ModifiersState::default(),
+            find_query: None,
+            find_highlights: Vec::new(),
+            find_index: 0,
+            font_manager: None,
+            cursor: (0.0, 0.0),
+            hovered_title: None,
+            tooltip_at: None,
+            tooltip_visible: false,
+            hovered_pointer: false,
+            title: None,
+            favicon: None,
+            anchors: HashMap::new(),
+            selection: None,
+            selecting: false,
+            focused: None,
+            visited: HashSet::new(),
+            loaded_html: None,
+            resize: ResizeDebounce::new((0, 0)),
         }
     }
 
-    pub fn load(&mut self, url: &Url) {
-        let body = url.request();
-        self.nodes = HTMLParser::new(body).parse();
+    /// Swap the HTML parser used for every page loaded from now on.
+    pub fn set_html_parser(&mut self, html_parser: Box<dyn HtmlParse>) {
+        self.html_parser = html_parser;
+    }
 
-        let Some(node) = &self.nodes else {
+    pub fn set_render_options(&mut self, render_options: RenderOptions) {
+        self.render_options = render_options;
+    }
+
+    /// Open a new page from the top.
+    pub fn navigate(&mut self, url: &Url) {
+        self.referer = self.url.replace(url.clone());
+        self.scroll = 0.0;
+        self.scroll_target = 0.0;
+        self.load(url);
+        // reload asks for where redirects ended up
+        self.url = self.response().map(|response| response.url.clone());
+    }
+
+    /// The response of the page last loaded, from the url it ended up at after redirects.
+    pub fn response(&self) -> Option<&Response> {
+        self.response.as_ref()
+    }
+
+    /// Re-fetch and re-render the current page, keeping the scroll position where the new
+    /// document is still tall enough. A page from `load_html` is rendered from its markup again.
+    pub fn reload(&mut self) {
+        let Some(url) = self.url.clone() else {
             return;
         };
 
-        let mut rules = self.default_style_sheet.clone();
+        let scroll = self.scroll;
+        match self.loaded_html.clone() {
+            Some(html) => self.render_html(&html, &url),
+            None => self.load(&url),
+        }
+
+        self.scroll = scroll.clamp(0.0, self.max_scroll());
+        self.scroll_target = self.scroll;
+        self.draw();
+    }
+
+    /// Headers sent with the page request, a `Referer` once we've navigated from another page.
+    fn request_headers(&self) -> Vec<(String, String)> {
+        match &self.referer {
+            Some(referer) => vec![(HEADER_REFERER.to_string(), referer.href())],
+            None => Vec::new(),
+        }
+    }
+
+    pub fn load(&mut self, url: &Url) {
+        let (response, bytes) = url.fetch_bytes(&self.request_headers());
+        self.loaded_html = None;
+        self.visited.insert(url.href());
+        self.focused = None;
+        // relative urls on the page resolve against where redirects ended up
+        let url = &response.url.clone();
+        // links straight to where the redirects ended up count as visited too
+        self.visited.insert(url.href());
+        self.nodes = match &response.error {
+            // nothing came back, so say why instead of showing a blank page, as text so
+            // nothing in the message or the url is taken for markup
+            Some(error) => Some(plain_text_document(&format!(
+                "{}\n\n{}\n{}",
+                CONNECTION_ERROR_MESSAGE,
+                error,
+                url.href()
+            ))),
+            None => self.parse_bytes(url, &response, &bytes),
+        };
+        self.response = Some(response);
+
+        self.render_nodes(url);
+    }
 
-        let links = get_links(node.clone());
+    /// Decodes and parses a response body, trying again if a `<meta>` names another charset.
+    fn parse_bytes(&self, url: &Url, response: &Response, bytes: &[u8]) -> Option<HTMLNodeRef> {
+        let content_type = response.content_type();
+        let header_charset = response.charset();
+        let charset = detect_charset(bytes, header_charset.as_deref());
+        let nodes = self.parse_body(url, content_type.as_deref(), &decode(bytes, &charset));
 
-        for link in links.iter() {
-            let style_url = url.resolve(&link);
-            let body = style_url.request();
-            if let Ok(new_rules) = CSSParser::new(&body).parse() {
-                rules.extend(new_rules);
+        // a `<meta>` past the prescan can still name the charset the guess got wrong
+        let declared = match (&header_charset, &nodes) {
+            (None, Some(node)) => get_meta_charset(node.clone()),
+            _ => None,
+        };
+        match declared.filter(|declared| !same_encoding(declared, &charset)) {
+            Some(declared) => {
+                let body = decode(bytes, &declared);
+                self.parse_body(url, content_type.as_deref(), &body)
             }
+            None => nodes,
         }
+    }
 
-        #[cfg(debug_assertions)]
-        self.print_rules(&rules);
+    /// Show `html` as the page at `base_url` without fetching it. Relative links and
+    /// stylesheets still resolve against `base_url`, and the stylesheets are fetched.
+    pub fn load_html(&mut self, html: &str, base_url: &Url) {
+        self.url = Some(base_url.clone());
+        self.response = None;
+        self.scroll = 0.0;
+        self.scroll_target = 0.0;
+        self.loaded_html = Some(html.to_string());
+        self.render_html(html, base_url);
+    }
 
-        rules.sort_by_key(|rule| cascade_priority(rule));
+    /// Parse `html` and show it, its relative urls resolving against `base_url`.
+    fn render_html(&mut self, html: &str, base_url: &Url) {
+        self.focused = None;
+        self.nodes = self.html_parser.parse(html);
+        self.render_nodes(base_url);
+    }
 
-        style(node.clone(), &rules);
+    /// Style, lay out and paint the parsed page, relative urls on it resolving against `url`.
+    fn render_nodes(&mut self, url: &Url) {
+        let Some(node) = &self.nodes else {
+            return;
+        };
 
-        #[cfg(debug_assertions)]
-        node.borrow().print_tree(0);
+        self.title = document_title(node.clone());
+        if let Some(env) = &self.env {
+            env.window.set_title(self.window_title());
+        }
 
-        let doc_rc = DocumentLayout::new(node.clone());
-        self.document = Some(doc_rc.clone());
+        self.favicon = favicon_url(url, node);
+        #[cfg(debug_assertions)]
+        if let Some(favicon) = &self.favicon {
+            println!("Favicon: {}", favicon.href());
+        }
 
-        doc_rc.borrow_mut().layout();
+        let rules = self.page_rules(url, node);
 
         #[cfg(debug_assertions)]
-        doc_rc.borrow().print_tree(0);
+        self.print_rules(&rules);
 
-        self.display_list.clear();
+        let doc_rc = self.layout_nodes(url, node, &rules);
+        self.document = Some(doc_rc.clone());
+
+        #[cfg(debug_assertions)]
+        {
+            node.borrow().print_tree(0);
+            doc_rc.borrow().print_tree(0);
+        }
 
-        let Some(block) = &doc_rc.borrow().child else {
+        self.selection = None;
+        if doc_rc.borrow().child.is_none() {
             return;
-        };
+        }
 
-        self.paint_tree(block.clone());
+        self.repaint();
+        self.build_anchors();
+
+        if let Some(y) = self.fragment_y(url) {
+            self.scroll = y.clamp(0.0, self.max_scroll());
+            self.scroll_target = self.scroll;
+        }
 
         #[cfg(debug_assertions)]
         self.print_display_list();
@@ -123,267 +401,2308 @@ impl Browser {
         self.draw();
     }
 
-    #[cfg(debug_assertions)]
-    fn print_rules(&self, rules: &CSSRules) {
-        rules.iter().for_each(|rule| {
-            println!("Selector: {}    Body: {:?}", rule.0, rule.1);
-        });
+    /// The default rules and those of the page's stylesheets, in cascade order.
+    fn page_rules(&self, url: &Url, node: &HTMLNodeRef) -> CSSRules {
+        let mut rules = self.default_style_sheet.rules().clone();
+
+        // subresources are referred by the page itself
+        rules.extend(fetch_stylesheets(stylesheet_urls(url, node), url));
+
+        rules.sort_by_key(|rule| cascade_priority(rule));
+        rules
     }
 
-    #[cfg(debug_assertions)]
-    fn print_display_list(&self) {
-        if let Ok(file) = std::fs::File::create(std::path::Path::new("log/display_list.txt")) {
-            let mut writer = std::io::BufWriter::new(file);
+    /// Styles the page with `rules` and lays it out.
+    fn layout_nodes(&self, url: &Url, node: &HTMLNodeRef, rules: &CSSRules) -> DocumentLayoutRef {
+        let visited = visited_links(url, node, &self.visited);
+        style(node.clone(), rules, &visited);
+
+        let doc_rc = DocumentLayout::new(node.clone());
+        doc_rc
+            .borrow_mut()
+            .set_max_width(self.reader_mode.then_some(READER_MAX_WIDTH));
+        doc_rc.borrow_mut().layout();
+        doc_rc
+    }
 
-            for item in &self.display_list {
-                let _ = writeln!(writer, "{}", item);
+    /// The document a body of `content_type` shows, a note for types that can't be displayed.
+    fn parse_body(&self, url: &Url, content_type: Option<&str>, body: &str) -> Option<HTMLNodeRef> {
+        match content_type {
+            Some(CONTENT_TYPE_HTML | CONTENT_TYPE_XHTML) | None => self.html_parser.parse(body),
+            Some(text) if text.starts_with(CONTENT_TYPE_TEXT_PREFIX) => {
+                Some(plain_text_document(body))
             }
+            Some(other) => Some(plain_text_document(&format!(
+                "{} is {}, which can't be displayed. Download it instead.",
+                url.href(),
+                other
+            ))),
         }
     }
 
-    fn paint_tree(&mut self, block_rc: BlockLayoutRef) {
-        let block = &*block_rc.borrow();
-        self.display_list.append(&mut block.paint());
+    fn window_title(&self) -> &str {
+        self.title.as_deref().unwrap_or(WINDOW_TITLE)
+    }
 
-        for child in &block.children {
-            self.paint_tree(child.clone());
+    fn build_anchors(&mut self) {
+        self.anchors.clear();
+
+        let (Some(nodes), Some(document)) = (&self.nodes, &self.document) else {
+            return;
+        };
+
+        for (name, node) in get_anchors(nodes.clone()) {
+            if let Some(y) = document.borrow().node_y(&node) {
+                // the first anchor with a name wins
+                self.anchors.entry(name).or_insert(y);
+            }
         }
     }
 
-    pub fn run(&mut self) {
-        let event_loop = EventLoop::new().expect("Failed to create event loop");
-        event_loop.set_control_flow(ControlFlow::Wait);
-        event_loop.run_app(self).expect("run() failed");
+    /// Where the anchor named by the url's fragment starts, if the page has one.
+    fn fragment_y(&self, url: &Url) -> Option<f32> {
+        let fragment = url.fragment.as_ref()?;
+        self.anchors.get(fragment).copied()
     }
 
-    fn draw(&mut self) {
-        if let Some(env) = &mut self.env {
-            let canvas = env.surface.canvas();
-            canvas.clear(Color::WHITE);
+    /// Follow the link at a point in page coordinates. A link to an anchor on this page only
+    /// scrolls to it.
+    fn click(&mut self, x: f32, y: f32) {
+        let Some(href) = self
+            .document
+            .as_ref()
+            .and_then(|doc| doc.borrow().link_at(x, y))
+        else {
+            return;
+        };
 
-            canvas.save();
+        self.follow_link(&href);
+    }
 
-            let scale_factor = env.window.scale_factor() as f32;
-            canvas.scale((scale_factor, scale_factor));
+    /// Navigate to `href` resolved against the current page, or only scroll to its anchor
+    /// when it points into this page.
+    fn follow_link(&mut self, href: &str) {
+        let Some(current) = self.url.clone() else {
+            return;
+        };
 
-            let mut paint = Paint::default();
-            paint.set_anti_alias(true);
+        let url = current.resolve(href);
+        if url.fragment.is_some() && url.href() == current.href() {
+            if let Some(y) = self.fragment_y(&url) {
+                self.scroll_by(y - self.scroll_target);
+            }
+            return;
+        }
 
-            for cmd in self.display_list.iter() {
-                if cmd.get_top() > self.scroll + HEIGHT {
-                    continue;
-                }
-                if cmd.get_bottom() < self.scroll {
-                    continue;
-                }
+        self.navigate(&url);
+    }
 
-                // reset paint's color
-                paint.set_color(Color::BLACK);
+    /// Move focus to the next focusable element in document order, or the previous one,
+    /// wrapping around at either end.
+    fn focus_next(&mut self, backwards: bool) {
+        let Some(document) = &self.document else {
+            return;
+        };
+        let order = document.borrow().focus_order();
+        if order.is_empty() {
+            self.focused = None;
+            return;
+        }
 
-                cmd.execute(self.scroll, canvas, &mut paint);
-            }
+        let current = self
+            .focused
+            .as_ref()
+            .and_then(|focused| order.iter().position(|node| Rc::ptr_eq(node, focused)));
+        let index = match (current, backwards) {
+            (None, false) => 0,
+            (None, true) => order.len() - 1,
+            (Some(i), false) => (i + 1) % order.len(),
+            (Some(i), true) => (i + order.len() - 1) % order.len(),
+        };
+        self.focused = Some(order[index].clone());
+        self.scroll_to_focus();
+    }
 
-            canvas.restore();
+    /// The ring around the focused element in page coordinates.
+    fn focus_ring(&self) -> Option<Rect> {
+        let rect = self
+            .document
+            .as_ref()?
+            .borrow()
+            .node_rect(self.focused.as_ref()?)?;
+        Some(Rect::new(
+            rect.left - FOCUS_RING_OFFSET,
+            rect.top - FOCUS_RING_OFFSET,
+            rect.right + FOCUS_RING_OFFSET,
+            rect.bottom + FOCUS_RING_OFFSET,
+        ))
+    }
 
-            env.gr_context.flush_and_submit();
-            env.gl_surface.swap_buffers(&env.gl_context).unwrap();
+    fn scroll_to_focus(&mut self) {
+        if let Some(rect) = self.focus_ring()
+            && (rect.top < self.scroll_target || rect.bottom > self.scroll_target + HEIGHT)
+        {
+            self.scroll_by(rect.top - HEIGHT / 2.0 - self.scroll_target);
         }
     }
-}
 
-impl ApplicationHandler for Browser {
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        println!("ApplicationHandler::resumed");
-        let window_attributes = WindowAttributes::default()
-            .with_title("Even Browser")
-            .with_inner_size(LogicalSize::new(WIDTH, HEIGHT));
+    /// Follow the focused element if it is a link.
+    fn activate_focus(&mut self) {
+        let href = self
+            .focused
+            .as_ref()
+            .and_then(|node| match &node.borrow().data {
+                HTMLNodeData::Element(e) if e.tag == ANCHOR => {
+                    e.attributes.get(ATTRIBUTE_KEY_HREF).cloned()
+                }
+                _ => None,
+            });
 
-        let template = ConfigTemplateBuilder::new()
-            .with_alpha_size(8)
-            .with_transparency(true);
+        if let Some(href) = href {
+            self.follow_link(&href);
+        }
+    }
 
-        let display_builder =
-            DisplayBuilder::new().with_window_attributes(window_attributes.into());
+    pub fn dump_dom(&mut self, url: &Url) {
+        let body = url.request();
+        self.nodes = self.html_parser.parse(&body);
 
-        let (window, gl_config) = display_builder
-            .build(event_loop, template, |configs| {
-                // Find the config with the minimum number of samples. Usually Skia takes care of
-                // anti-aliasing and may not be able to create appropriate Surfaces for samples > 0.
-                // See https://github.com/rust-skia/rust-skia/issues/782
-                // And https://github.com/rust-skia/rust-skia/issues/764
-                configs
-                    .reduce(|accum, config| {
-                        let transparency_check = config.supports_transparency().unwrap_or(false)
-                            & !accum.supports_transparency().unwrap_or(false);
+        if let Some(node) = &self.nodes {
+            node.borrow().print_tree(0);
+        }
+    }
 
-                        if transparency_check || config.num_samples() < accum.num_samples() {
-                            config
-                        } else {
-                            accum
-                        }
-                    })
-                    .unwrap()
-            })
-            .unwrap();
+    /// Prints the page's preload and prefetch hints, resolved, with what each is preloaded as.
+    pub fn dump_preloads(&self, url: &Url) {
+        let Some(node) = self.html_parser.parse(&url.request()) else {
+            return;
+        };
 
-        println!("Picked a config with {} samples", gl_config.num_samples());
-        let window = window.expect("Could not create window with OpenGL context");
-        let window_handle = window
-            .window_handle()
-            .expect("Failed to retrieve RawWindowHandle");
-        let raw_window_handle = window_handle.as_raw();
+        let base = base_url(url, &node);
+        for (href, as_type) in get_preloads(node.clone()) {
+            println!("{} as={}", base.resolve(&href).href(), as_type);
+        }
+    }
 
-        // The context creation part. It can be created before surface and that's how
-        // it's expected in multithreaded + multiwindow operation mode, since you
-        // can send NotCurrentContext, but not Surface.
-        let context_attributes = ContextAttributesBuilder::new().build(Some(raw_window_handle));
+    pub fn dump_tokens(&self, url: &Url) {
+        for token in tokenize(&url.request()) {
+            println!("{}", token);
+        }
+    }
 
-        // Since glutin by default tries to create OpenGL core context, which may not be
-        // present we should try gles.
-        let fallback_context_attributes = ContextAttributesBuilder::new()
-            .with_context_api(ContextApi::Gles(None))
-            .build(Some(raw_window_handle));
-        let not_current_gl_context = unsafe {
-            gl_config
-                .display()
-                .create_context(&gl_config, &context_attributes)
-                .unwrap_or_else(|_| {
-                    gl_config
-                        .display()
-                        .create_context(&gl_config, &fallback_context_attributes)
-                        .expect("failed to create context")
-                })
-        };
+    /// Prints the layout tree of the page at `url`, without showing it or any debug output.
+    pub fn dump_layout(&self, url: &Url) {
+        // the error was already printed
+        let (response, bytes) = url.fetch_bytes(&self.request_headers());
+        if response.error.is_some() {
+            return;
+        }
 
-        let (width, height): (u32, u32) = window.inner_size().into();
+        let url = &response.url;
+        if let Some(node) = self.parse_bytes(url, &response, &bytes) {
+            let rules = self.page_rules(url, &node);
+            self.layout_nodes(url, &node, &rules).borrow().print_tree(0);
+        }
+    }
 
-        let attrs = SurfaceAttributesBuilder::<WindowSurface>::new().build(
-            raw_window_handle,
-            NonZeroU32::new(width).unwrap(),
-            NonZeroU32::new(height).unwrap(),
-        );
+    /// Prints the default stylesheet's rules for `tag`, then what couldn't be parsed in it.
+    pub fn dump_rules(&self, tag: &str) {
+        let sheet = &self.default_style_sheet;
+        if sheet.is_empty() {
+            eprintln!("The default stylesheet has no rules");
+        }
 
-        let gl_surface = unsafe {
-            gl_config
-                .display()
-                .create_window_surface(&gl_config, &attrs)
-                .expect("Could not create gl window surface")
-        };
+        let rules = sheet.rules_for_tag(tag);
+        for (selector, body) in &rules {
+            println!("Selector: {}    Body: {:?}", selector, body);
+        }
+        println!("{} of {} rules select {}", rules.len(), sheet.len(), tag);
 
-        let gl_context = not_current_gl_context
-            .make_current(&gl_surface)
-            .expect("Could not make GL context current when setting up skia renderer");
+        for error in sheet.errors() {
+            eprintln!("{}", error);
+        }
+    }
 
-        gl::load_with(|s| {
-            gl_config
-                .display()
-                .get_proc_address(CString::new(s).unwrap().as_c_str())
+    #[cfg(debug_assertions)]
+    fn print_rules(&self, rules: &CSSRules) {
+        rules.iter().for_each(|rule| {
+            println!("Selector: {}    Body: {:?}", rule.0, rule.1);
         });
+    }
 
-        let interface = Interface::new_load_with(|name| {
-            if name == "eglGetCurrentDisplay" {
-                return std::ptr::null();
+    #[cfg(debug_assertions)]
+    fn print_display_list(&self) {
+        if let Ok(file) = std::fs::File::create(std::path::Path::new("log/display_list.txt")) {
+            let mut writer = std::io::BufWriter::new(file);
+
+            for line in self.dump_display_list(self.scroll) {
+                let _ = writeln!(writer, "{}", line);
             }
-            gl_config
-                .display()
-                .get_proc_address(CString::new(name).unwrap().as_c_str())
-        })
-        .expect("Could not create interface");
+        }
+    }
 
-        let mut gr_context = gpu::direct_contexts::make_gl(interface, None)
-            .expect("Could not create direct context");
+    /// One line per display list command: its top and bottom on screen at `scroll`,
+    /// then the command itself in page coordinates.
+    pub fn dump_display_list(&self, scroll: f32) -> Vec<String> {
+        self.display_list
+            .iter()
+            .map(|cmd| {
+                format!(
+                    "top={} bottom={} {}",
+                    cmd.painted_top(scroll),
+                    cmd.painted_bottom(scroll),
+                    cmd
+                )
+            })
+            .collect()
+    }
 
-        let fb_info = {
-            let mut fboid: GLint = 0;
-            unsafe { gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut fboid) };
+    /// Rects of every case-insensitive match of `query` in the display list's text, where it
+    /// is painted through the transforms around it, e.g. of a scrolled box.
+    pub fn find_matches(&self, query: &str) -> Vec<Rect> {
+        let mut matches = Vec::new();
+        let mut transforms: Vec<&DrawTransform> = Vec::new();
+        let mut texts: Vec<&DrawText> = Vec::new();
 
-            FramebufferInfo {
-                fboid: fboid.try_into().unwrap(),
-                format: Format::RGBA8.into(),
-                ..Default::default()
+        // the texts between two transform markers are all under the same transforms
+        let mut flush = |texts: &mut Vec<&DrawText>, transforms: &[&DrawTransform]| {
+            let found = find_in_lines(texts, query).into_iter().map(|rect| {
+                transforms
+                    .iter()
+                    .rev()
+                    .fold(rect, |rect, transform| transform.apply_rect(rect))
+            });
+            matches.extend(found);
+            texts.clear();
+        };
+        for cmd in &self.display_list {
+            match cmd {
+                DrawCommand::Text(text) => texts.push(text),
+                DrawCommand::PushTransform(transform) => {
+                    flush(&mut texts, &transforms);
+                    transforms.push(transform);
+                }
+                DrawCommand::PopTransform => {
+                    flush(&mut texts, &transforms);
+                    transforms.pop();
+                }
+                _ => {}
             }
+        }
+        flush(&mut texts, &transforms);
+
+        matches
+    }
+
+    /// The selected part of each text item between two points in page coordinates, in reading
+    /// order. Lines between the points' lines are selected whole.
+    fn selected_runs(&self, start: Point, end: Point) -> Vec<(Rect, String)> {
+        let on_line = |cmd: &DrawCommand, y: f32| cmd.get_top() <= y && y <= cmd.get_bottom();
+        let same_line = self.display_list.iter().any(|cmd| {
+            matches!(cmd, DrawCommand::Text(_)) && on_line(cmd, start.y) && on_line(cmd, end.y)
+        });
+
+        // dragging up or to the left selects the same as the other way round
+        let (start, end) = if (same_line && end.x < start.x) || (!same_line && end.y < start.y) {
+            (end, start)
+        } else {
+            (start, end)
         };
 
-        let num_samples = gl_config.num_samples() as usize;
-        let stencil_size = gl_config.stencil_size() as usize;
+        self.display_list
+            .iter()
+            .filter_map(|cmd| {
+                let DrawCommand::Text(text) = cmd else {
+                    return None;
+                };
+                // fixed text isn't in page coordinates
+                if cmd.is_fixed() || cmd.get_bottom() < start.y || cmd.get_top() > end.y {
+                    return None;
+                }
 
-        let size = window.inner_size();
-        let size = (
-            size.width.try_into().expect("Could not convert width"),
-            size.height.try_into().expect("Could not convert height"),
-        );
-        let backend_render_target =
-            backend_render_targets::make_gl(size, num_samples, stencil_size, fb_info);
+                let from = if on_line(cmd, start.y) {
+                    start.x
+                } else {
+                    f32::MIN
+                };
+                let to = if on_line(cmd, end.y) { end.x } else { f32::MAX };
+                text.select(from, to)
+            })
+            .collect()
+    }
 
-        let surface = gpu::surfaces::wrap_backend_render_target(
-            &mut gr_context,
-            &backend_render_target,
-            SurfaceOrigin::BottomLeft,
-            ColorType::RGBA8888,
-            None,
-            None,
-        )
-        .expect("Could not create skia surface");
+    /// The text between two points in page coordinates, words on a line joined by spaces and
+    /// lines by newlines.
+    pub fn text_in_range(&self, start: Point, end: Point) -> String {
+        let mut text = String::new();
+        let mut previous: Option<Rect> = None;
 
-        self.env = Some(Env {
-            window,
-            surface,
-            gl_context,
-            gr_context,
-            gl_surface,
-        });
+        for (rect, run) in self.selected_runs(start, end) {
+            if let Some(previous) = previous {
+                text.push(if rect.top >= previous.bottom {
+                    '\n'
+                } else {
+                    ' '
+                });
+            }
+            text.push_str(&run);
+            previous = Some(rect);
+        }
+
+        text
+    }
+
+    fn selected_text(&self) -> Option<String> {
+        let (anchor, focus) = self.selection?;
+        Some(self.text_in_range(anchor, focus)).filter(|text| !text.is_empty())
+    }
+
+    /// Moves the selection's focus to the cursor while dragging, returns whether it changed.
+    fn extend_selection(&mut self) -> bool {
+        let point = Point::new(self.cursor.0, self.cursor.1 + self.scroll);
+        match &mut self.selection {
+            Some((_, focus)) if self.selecting && *focus != point => {
+                *focus = point;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn set_smooth_scroll(&mut self, smooth_scroll: bool) {
+        self.smooth_scroll = smooth_scroll;
+    }
+
+    pub fn set_reader_mode(&mut self, reader_mode: bool) {
+        self.reader_mode = reader_mode;
+    }
+
+    fn scroll_by(&mut self, delta: f32) {
+        self.scroll_target = (self.scroll_target + delta).clamp(0.0, self.max_scroll());
+
+        if !self.smooth_scroll {
+            self.scroll = self.scroll_target;
+        }
 
         if let Some(env) = &self.env {
             env.window.request_redraw();
         }
     }
 
-    fn window_event(
-        &mut self,
-        event_loop: &ActiveEventLoop,
-        _window_id: WindowId,
-        event: WindowEvent,
-    ) {
-        match event {
-            WindowEvent::CloseRequested => {
-                println!("The close button was pressed; stopping");
-                event_loop.exit();
+    /// Scroll the innermost scrollable box under the cursor that can still move that way,
+    /// else the page.
+    fn wheel(&mut self, delta: f32) {
+        let (x, y) = (self.cursor.0, self.cursor.1 + self.scroll);
+        let container = self
+            .document
+            .as_ref()
+            .and_then(|doc| doc.borrow().child.clone())
+            .and_then(|block| BlockLayout::scroll_container_at(&block, x, y, delta));
+
+        let Some(container) = container else {
+            self.scroll_by(delta);
+            return;
+        };
+
+        if container.borrow_mut().scroll_by(delta) {
+            self.repaint();
+            // the matches moved with the content
+            if let Some(query) = &self.find_query {
+                self.find_highlights = self.find_matches(query);
             }
-            WindowEvent::RedrawRequested => {
-                self.draw();
+            if let Some(env) = &self.env {
+                env.window.request_redraw();
             }
-            WindowEvent::KeyboardInput {
-                event: key_event, ..
-            } => {
-                if key_event.state.is_pressed() {
-                    match key_event.logical_key {
-                        Key::Named(NamedKey::ArrowDown) => {
-                            let max_y = self.document.clone().map_or(0.0, |d| {
-                                d.borrow().height + 2.0 * DEFAULT_BROWSER_PADDING - HEIGHT
-                            });
-                            self.scroll = (self.scroll + SCROLL_STEP).min(max_y);
+        }
+    }
 
-                            if let Some(env) = &self.env {
-                                env.window.request_redraw();
-                            }
-                        }
-                        Key::Named(NamedKey::ArrowUp) => {
-                            self.scroll -= SCROLL_STEP;
+    /// Move one frame toward the scroll target, returns whether the animation is still running.
+    fn step_scroll(&mut self) -> bool {
+        let distance = self.scroll_target - self.scroll;
 
-                            if self.scroll < 0.0 {
-                                self.scroll = 0.0;
-                            }
+        if distance.abs() < SMOOTH_SCROLL_EPSILON {
+            self.scroll = self.scroll_target;
+            return false;
+        }
 
-                            if let Some(env) = &self.env {
-                                env.window.request_redraw();
-                            }
-                        }
-                        _ => (),
-                    }
+        self.scroll += distance * SMOOTH_SCROLL_FACTOR;
+        true
+    }
+
+    fn max_scroll(&self) -> f32 {
+        self.document
+            .clone()
+            .map_or(0.0, |d| {
+                d.borrow().height + 2.0 * DEFAULT_BROWSER_PADDING - HEIGHT
+            })
+            .max(0.0)
+    }
+
+    fn update_find(&mut self) {
+        self.find_highlights = match &self.find_query {
+            Some(query) => self.find_matches(query),
+            None => Vec::new(),
+        };
+        self.find_index = 0;
+        self.scroll_to_highlight();
+    }
+
+    fn next_find_match(&mut self) {
+        if !self.find_highlights.is_empty() {
+            self.find_index = (self.find_index + 1) % self.find_highlights.len();
+            self.scroll_to_highlight();
+        }
+    }
+
+    fn scroll_to_highlight(&mut self) {
+        if let Some(rect) = self.find_highlights.get(self.find_index) {
+            let visible = HEIGHT - FIND_BAR_HEIGHT;
+            if rect.top < self.scroll || rect.bottom > self.scroll + visible {
+                self.scroll = (rect.top - visible / 2.0).clamp(0.0, self.max_scroll());
+                self.scroll_target = self.scroll;
+            }
+        }
+    }
+
+    /// Keystrokes while the find bar is open, returns whether the key was consumed.
+    fn find_key(&mut self, key: &Key) -> bool {
+        let Some(query) = &mut self.find_query else {
+            return false;
+        };
+
+        match key {
+            Key::Named(NamedKey::Escape) => {
+                self.find_query = None;
+                self.update_find();
+            }
+            Key::Named(NamedKey::Enter) => self.next_find_match(),
+            Key::Named(NamedKey::Backspace) => {
+                query.pop();
+                self.update_find();
+            }
+            Key::Named(NamedKey::Space) => {
+                query.push(' ');
+                self.update_find();
+            }
+            Key::Character(c) if !self.modifiers.control_key() && !self.modifiers.super_key() => {
+                query.push_str(c.as_str());
+                self.update_find();
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    // browser UI like the find bar and tooltips uses its own font, independent of page styles
+    fn overlay_font(&mut self) -> Font {
+        self.font_manager
+            .get_or_insert_with(FontManager::new)
+            .borrow_mut()
+            .get_font(FIND_BAR_FONT_SIZE, Weight::NORMAL, Slant::Upright)
+    }
+
+    /// Track the element under the cursor, returns whether its title changed.
+    fn hover(&mut self, x: f32, y: f32) -> bool {
+        self.cursor = (x, y);
+
+        let title = self
+            .document
+            .as_ref()
+            .and_then(|doc| doc.borrow().title_at(x, y + self.scroll));
+
+        self.hovered_pointer = self
+            .document
+            .as_ref()
+            .is_some_and(|doc| doc.borrow().is_pointer_at(x, y + self.scroll));
+
+        let changed = title != self.hovered_title;
+        if changed {
+            // a new title waits for the delay again
+            let delay = Duration::from_millis(TOOLTIP_DELAY_MS);
+            self.tooltip_at = title.as_ref().map(|_| Instant::now() + delay);
+            self.tooltip_visible = false;
+        }
+        self.hovered_title = title;
+        changed
+    }
+
+    /// Shows the pending tooltip once its delay is over, returns whether it just appeared.
+    fn show_due_tooltip(&mut self, now: Instant) -> bool {
+        match self.tooltip_at {
+            Some(at) if now >= at => {
+                self.tooltip_at = None;
+                self.tooltip_visible = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Rebuild the GL and skia surfaces for a new window size in physical pixels. The page
+    /// keeps its fixed layout width, only the area it's drawn into changes.
+    fn resize_surface(&mut self, (width, height): (u32, u32)) {
+        let Some(env) = &mut self.env else {
+            return;
+        };
+        // a minimized window has no area to draw into
+        let (Some(non_zero_width), Some(non_zero_height)) =
+            (NonZeroU32::new(width), NonZeroU32::new(height))
+        else {
+            return;
+        };
+
+        env.gl_surface
+            .resize(&env.gl_context, non_zero_width, non_zero_height);
+        env.surface = wrap_surface(
+            &mut env.gr_context,
+            (width, height),
+            env.fb_info,
+            env.num_samples,
+            env.stencil_size,
+            self.render_options.color_space(),
+        );
+    }
+
+    fn draw_tooltip(&mut self, canvas: &skia_safe::Canvas, paint: &mut Paint) {
+        let Some(title) = self.hovered_title.clone() else {
+            return;
+        };
+        if !self.tooltip_visible {
+            return;
+        }
+
+        let font = self.overlay_font();
+        let width = font.measure_str(&title, None).0 + 2.0 * TOOLTIP_PADDING;
+        let height = font.spacing() + 2.0 * TOOLTIP_PADDING;
+
+        // keep the box inside the window
+        let left = (self.cursor.0 + TOOLTIP_OFFSET).min(WIDTH - width).max(0.0);
+        let top = (self.cursor.1 + TOOLTIP_OFFSET)
+            .min(HEIGHT - height)
+            .max(0.0);
+        let baseline = top + TOOLTIP_PADDING - font.metrics().1.ascent;
+
+        let cmds = [
+            DrawCommand::rect(left, top, left + width, top + height, TOOLTIP_COLOR),
+            DrawCommand::outline(
+                left,
+                top,
+                left + width,
+                top + height,
+                1.0,
+                TOOLTIP_BORDER_COLOR,
+            ),
+            DrawCommand::text(
+                left + TOOLTIP_PADDING,
+                top + TOOLTIP_PADDING,
+                baseline,
+                title,
+                font,
+                "black",
+                "transparent",
+            ),
+        ];
+
+        for cmd in cmds.iter() {
+            paint.set_color(Color::BLACK);
+            cmd.execute(0.0, canvas, paint, &self.render_options);
+        }
+    }
+
+    fn draw_find_bar(&mut self, canvas: &skia_safe::Canvas, paint: &mut Paint) {
+        let Some(query) = self.find_query.clone() else {
+            return;
+        };
+
+        let font = self.overlay_font();
+
+        let top = HEIGHT - FIND_BAR_HEIGHT;
+        let text = format!("Find: {} ({} matches)", query, self.find_highlights.len());
+        let baseline = top + (FIND_BAR_HEIGHT - font.spacing()) / 2.0 - font.metrics().1.ascent;
+
+        let cmds = [
+            DrawCommand::rect(0.0, top, WIDTH, HEIGHT, FIND_BAR_COLOR),
+            DrawCommand::text(
+                DEFAULT_BROWSER_PADDING,
+                top,
+                baseline,
+                text,
+                font,
+                "black",
+                "transparent",
+            ),
+        ];
+
+        for cmd in cmds.iter() {
+            paint.set_color(Color::BLACK);
+            // the bar stays in place regardless of scroll
+            cmd.execute(0.0, canvas, paint, &self.render_options);
+        }
+    }
+
+    /// Rebuild the display list from the current layout, e.g. after a box scrolled.
+    fn repaint(&mut self) {
+        self.display_list.clear();
+
+        if let Some(block) = self
+            .document
+            .as_ref()
+            .and_then(|doc| doc.borrow().child.clone())
+        {
+            self.paint_tree(block, false);
+        }
+    }
+
+    // everything inside a fixed box is pinned with it
+    fn paint_tree(&mut self, block_rc: BlockLayoutRef, fixed: bool) {
+        let block = &*block_rc.borrow();
+        let fixed = fixed || block.is_fixed();
+
+        let transform = block.transform();
+        let is_transformed = transform.is_some();
+        let clip = block.clip();
+        let is_clipped = clip.is_some();
+        let scroll = block.scroll_transform();
+        let is_scrolled = scroll.is_some();
+
+        let painted = match scroll {
+            // the box stays in place while its content moves
+            Some(scroll) => {
+                let (mut cmds, content) = block.paint_parts();
+                cmds.push(scroll);
+                cmds.extend(content);
+                cmds
+            }
+            None => block.paint(),
+        };
+        let mut cmds: Vec<DrawCommand> = transform.into_iter().chain(clip).chain(painted).collect();
+        for cmd in cmds.iter_mut() {
+            cmd.set_fixed(fixed);
+        }
+        self.display_list.append(&mut cmds);
+
+        for child in &block.children {
+            self.paint_tree(child.clone(), fixed);
+        }
+
+        if is_scrolled {
+            self.display_list.push(DrawCommand::PopTransform);
+        }
+        if is_clipped {
+            self.display_list.push(DrawCommand::PopClip);
+        }
+        if is_transformed {
+            self.display_list.push(DrawCommand::PopTransform);
+        }
+    }
+
+    pub fn run(&mut self) {
+        let event_loop = EventLoop::new().expect("Failed to create event loop");
+        event_loop.set_control_flow(ControlFlow::Wait);
+        event_loop.run_app(self).expect("run() failed");
+    }
+
+    /// Paint the page and what's drawn over it onto `surface`. With `srgb` they're painted into
+    /// a layer in linear light first, so blended colors and anti-aliased edges mix light instead
+    /// of encoded values, then converted into the surface's sRGB.
+    fn render(&mut self, surface: &mut Surface, scale_factor: f32) {
+        let layer = self
+            .render_options
+            .linear_layer_info(surface.image_info().dimensions())
+            .and_then(|info| surface.new_surface(&info));
+
+        match layer {
+            Some(mut layer) => {
+                self.paint_canvas(layer.canvas(), scale_factor);
+                let sampling = skia_safe::SamplingOptions::default();
+                layer.draw(surface.canvas(), (0.0, 0.0), sampling, None);
+            }
+            None => self.paint_canvas(surface.canvas(), scale_factor),
+        }
+    }
+
+    fn paint_canvas(&mut self, canvas: &skia_safe::Canvas, scale_factor: f32) {
+        canvas.clear(self.render_options.clear_color);
+
+        canvas.save();
+        canvas.scale((scale_factor, scale_factor));
+
+        let mut paint = Paint::default();
+        self.render_options.apply_to_paint(&mut paint);
+
+        let mut transforms = Vec::new();
+        for cmd in self.display_list.iter() {
+            match cmd {
+                DrawCommand::PushTransform(transform) => transforms.push(transform),
+                DrawCommand::PopTransform => {
+                    transforms.pop();
                 }
+                _ => {}
             }
 
-            _ => (),
+            if !cmd.is_marker() {
+                let (top, bottom) = cmd.transformed_span(self.scroll, &transforms);
+                if top > HEIGHT || bottom < 0.0 {
+                    continue;
+                }
+            }
+
+            // reset paint's color
+            paint.set_color(Color::BLACK);
+
+            cmd.execute(self.scroll, canvas, &mut paint, &self.render_options);
+        }
+
+        // highlights go over the page with a translucent color so the text stays readable
+        let selection = match self.selection {
+            Some((anchor, focus)) => self.selected_runs(anchor, focus),
+            None => Vec::new(),
+        };
+        for (rect, _) in &selection {
+            let cmd = DrawCommand::rect(
+                rect.left,
+                rect.top,
+                rect.right,
+                rect.bottom,
+                SELECTION_COLOR,
+            );
+            cmd.execute(self.scroll, canvas, &mut paint, &self.render_options);
+        }
+
+        for rect in &self.find_highlights {
+            let cmd = DrawCommand::rect(
+                rect.left,
+                rect.top,
+                rect.right,
+                rect.bottom,
+                FIND_HIGHLIGHT_COLOR,
+            );
+            cmd.execute(self.scroll, canvas, &mut paint, &self.render_options);
+        }
+
+        if let Some(rect) = self.focus_ring() {
+            let cmd = DrawCommand::outline(
+                rect.left,
+                rect.top,
+                rect.right,
+                rect.bottom,
+                FOCUS_RING_THICKNESS,
+                FOCUS_RING_COLOR,
+            );
+            cmd.execute(self.scroll, canvas, &mut paint, &self.render_options);
+        }
+
+        self.draw_find_bar(canvas, &mut paint);
+        self.draw_tooltip(canvas, &mut paint);
+
+        canvas.restore();
+    }
+
+    fn draw(&mut self) {
+        if let Some(mut env) = self.env.take() {
+            let scale_factor = env.window.scale_factor() as f32;
+            self.render(&mut env.surface, scale_factor);
+
+            env.gr_context.flush_and_submit();
+            env.gl_surface.swap_buffers(&env.gl_context).unwrap();
+
+            self.env = Some(env);
         }
     }
 }
+
+impl ApplicationHandler for Browser {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        println!("ApplicationHandler::resumed");
+        let window_attributes = WindowAttributes::default()
+            .with_title(self.window_title())
+            .with_inner_size(LogicalSize::new(WIDTH, HEIGHT));
+
+        let template = ConfigTemplateBuilder::new()
+            .with_alpha_size(8)
+            .with_transparency(true);
+
+        let display_builder =
+            DisplayBuilder::new().with_window_attributes(window_attributes.into());
+
+        let (window, gl_config) = display_builder
+            .build(event_loop, template, |configs| {
+                // Find the config with the minimum number of samples. Usually Skia takes care of
+                // anti-aliasing and may not be able to create appropriate Surfaces for samples > 0.
+                // See https://github.com/rust-skia/rust-skia/issues/782
+                // And https://github.com/rust-skia/rust-skia/issues/764
+                configs
+                    .reduce(|accum, config| {
+                        let transparency_check = config.supports_transparency().unwrap_or(false)
+                            & !accum.supports_transparency().unwrap_or(false);
+
+                        if transparency_check || config.num_samples() < accum.num_samples() {
+                            config
+                        } else {
+                            accum
+                        }
+                    })
+                    .unwrap()
+            })
+            .unwrap();
+
+        println!("Picked a config with {} samples", gl_config.num_samples());
+        let window = window.expect("Could not create window with OpenGL context");
+        let window_handle = window
+            .window_handle()
+            .expect("Failed to retrieve RawWindowHandle");
+        let raw_window_handle = window_handle.as_raw();
+
+        // The context creation part. It can be created before surface and that's how
+        // it's expected in multithreaded + multiwindow operation mode, since you
+        // can send NotCurrentContext, but not Surface.
+        let context_attributes = ContextAttributesBuilder::new().build(Some(raw_window_handle));
+
+        // Since glutin by default tries to create OpenGL core context, which may not be
+        // present we should try gles.
+        let fallback_context_attributes = ContextAttributesBuilder::new()
+            .with_context_api(ContextApi::Gles(None))
+            .build(Some(raw_window_handle));
+        let not_current_gl_context = unsafe {
+            gl_config
+                .display()
+                .create_context(&gl_config, &context_attributes)
+                .unwrap_or_else(|_| {
+                    gl_config
+                        .display()
+                        .create_context(&gl_config, &fallback_context_attributes)
+                        .expect("failed to create context")
+                })
+        };
+
+        let (width, height): (u32, u32) = window.inner_size().into();
+
+        let attrs = SurfaceAttributesBuilder::<WindowSurface>::new().build(
+            raw_window_handle,
+            NonZeroU32::new(width).unwrap(),
+            NonZeroU32::new(height).unwrap(),
+        );
+
+        let gl_surface = unsafe {
+            gl_config
+                .display()
+                .create_window_surface(&gl_config, &attrs)
+                .expect("Could not create gl window surface")
+        };
+
+        let gl_context = not_current_gl_context
+            .make_current(&gl_surface)
+            .expect("Could not make GL context current when setting up skia renderer");
+
+        gl::load_with(|s| {
+            gl_config
+                .display()
+                .get_proc_address(CString::new(s).unwrap().as_c_str())
+        });
+
+        let interface = Interface::new_load_with(|name| {
+            if name == "eglGetCurrentDisplay" {
+                return std::ptr::null();
+            }
+            gl_config
+                .display()
+                .get_proc_address(CString::new(name).unwrap().as_c_str())
+        })
+        .expect("Could not create interface");
+
+        let mut gr_context = gpu::direct_contexts::make_gl(interface, None)
+            .expect("Could not create direct context");
+
+        let fb_info = {
+            let mut fboid: GLint = 0;
+            unsafe { gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut fboid) };
+
+            FramebufferInfo {
+                fboid: fboid.try_into().unwrap(),
+                format: Format::RGBA8.into(),
+                ..Default::default()
+            }
+        };
+
+        let num_samples = gl_config.num_samples() as usize;
+        let stencil_size = gl_config.stencil_size() as usize;
+
+        let size = window.inner_size();
+        self.resize = ResizeDebounce::new(size.into());
+        let surface = wrap_surface(
+            &mut gr_context,
+            size.into(),
+            fb_info,
+            num_samples,
+            stencil_size,
+            self.render_options.color_space(),
+        );
+
+        self.env = Some(Env {
+            window,
+            surface,
+            gl_context,
+            gr_context,
+            gl_surface,
+            fb_info,
+            num_samples,
+            stencil_size,
+        });
+
+        if let Some(env) = &self.env {
+            env.window.request_redraw();
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        match event {
+            WindowEvent::CloseRequested => {
+                println!("The close button was pressed; stopping");
+                event_loop.exit();
+            }
+            WindowEvent::RedrawRequested => {
+                self.draw();
+            }
+            // until the size settles the old surface is kept, stretched by the window system
+            WindowEvent::Resized(size) => self.resize.request(size.into(), Instant::now()),
+            WindowEvent::CursorMoved { position, .. } => {
+                let Some(scale_factor) = self.env.as_ref().map(|env| env.window.scale_factor())
+                else {
+                    return;
+                };
+                let position = position.to_logical::<f32>(scale_factor);
+
+                let was_pointer = self.hovered_pointer;
+                let changed = self.hover(position.x, position.y);
+                if self.hovered_pointer != was_pointer
+                    && let Some(env) = &self.env
+                {
+                    env.window.set_cursor(if self.hovered_pointer {
+                        CursorIcon::Pointer
+                    } else {
+                        CursorIcon::Default
+                    });
+                }
+                let selected = self.extend_selection();
+                // the tooltip follows the cursor
+                if (changed || selected || self.hovered_title.is_some())
+                    && let Some(env) = &self.env
+                {
+                    env.window.request_redraw();
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let scale_factor = self
+                    .env
+                    .as_ref()
+                    .map_or(1.0, |env| env.window.scale_factor());
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(_, lines) => -lines * WHEEL_LINE_HEIGHT,
+                    MouseScrollDelta::PixelDelta(position) => {
+                        -position.to_logical::<f32>(scale_factor).y
+                    }
+                };
+                self.wheel(delta);
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let point = Point::new(self.cursor.0, self.cursor.1 + self.scroll);
+                if state == ElementState::Pressed {
+                    self.selection = Some((point, point));
+                    self.selecting = true;
+                } else {
+                    self.selecting = false;
+                    // releasing where the press was is a click, not a selection
+                    if self
+                        .selection
+                        .is_some_and(|(anchor, focus)| anchor == focus)
+                    {
+                        self.selection = None;
+                        self.click(point.x, point.y);
+                    }
+                }
+
+                if let Some(env) = &self.env {
+                    env.window.request_redraw();
+                }
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
+            WindowEvent::KeyboardInput {
+                event: key_event, ..
+            } => {
+                if key_event.state.is_pressed() {
+                    if self.find_key(&key_event.logical_key) {
+                        if let Some(env) = &self.env {
+                            env.window.request_redraw();
+                        }
+                        return;
+                    }
+
+                    match key_event.logical_key {
+                        Key::Character(c)
+                            if c.eq_ignore_ascii_case("f")
+                                && (self.modifiers.control_key() || self.modifiers.super_key()) =>
+                        {
+                            self.find_query = Some(String::new());
+                            self.update_find();
+
+                            if let Some(env) = &self.env {
+                                env.window.request_redraw();
+                            }
+                        }
+                        Key::Character(c)
+                            if c.eq_ignore_ascii_case("c")
+                                && (self.modifiers.control_key() || self.modifiers.super_key()) =>
+                        {
+                            if let Some(text) = self.selected_text() {
+                                copy_to_clipboard(&text);
+                            }
+                        }
+                        Key::Character(c)
+                            if c.eq_ignore_ascii_case("r")
+                                && (self.modifiers.control_key() || self.modifiers.super_key()) =>
+                        {
+                            self.reload();
+                        }
+                        Key::Named(NamedKey::F5) => self.reload(),
+                        Key::Named(NamedKey::Tab) => {
+                            self.focus_next(self.modifiers.shift_key());
+                            if let Some(env) = &self.env {
+                                env.window.request_redraw();
+                            }
+                        }
+                        Key::Named(NamedKey::Enter) => self.activate_focus(),
+                        Key::Named(NamedKey::ArrowDown) => self.scroll_by(SCROLL_STEP),
+                        Key::Named(NamedKey::ArrowUp) => self.scroll_by(-SCROLL_STEP),
+                        _ => (),
+                    }
+                }
+            }
+
+            _ => (),
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let mut wake_at = None;
+        let mut redraw = self.show_due_tooltip(Instant::now());
+
+        if let Some(size) = self.resize.commit_due(Instant::now()) {
+            self.resize_surface(size);
+            redraw = true;
+        }
+
+        if self.scroll != self.scroll_target {
+            if self.step_scroll() {
+                wake_at = Some(Instant::now() + Duration::from_millis(SMOOTH_SCROLL_FRAME_MS));
+            }
+            redraw = true;
+        }
+
+        for at in [self.tooltip_at, self.resize.deadline()]
+            .into_iter()
+            .flatten()
+        {
+            wake_at = Some(wake_at.map_or(at, |wake_at| wake_at.min(at)));
+        }
+
+        event_loop.set_control_flow(match wake_at {
+            Some(at) => ControlFlow::WaitUntil(at),
+            None => ControlFlow::Wait,
+        });
+
+        if redraw && let Some(env) = &self.env {
+            env.window.request_redraw();
+        }
+    }
+}
+
+/// A skia surface drawing into the window's framebuffer at `size` in physical pixels.
+fn wrap_surface(
+    gr_context: &mut DirectContext,
+    (width, height): (u32, u32),
+    fb_info: FramebufferInfo,
+    num_samples: usize,
+    stencil_size: usize,
+    color_space: Option<ColorSpace>,
+) -> Surface {
+    let size = (
+        width.try_into().expect("Could not convert width"),
+        height.try_into().expect("Could not convert height"),
+    );
+    let backend_render_target =
+        backend_render_targets::make_gl(size, num_samples, stencil_size, fb_info);
+
+    gpu::surfaces::wrap_backend_render_target(
+        gr_context,
+        &backend_render_target,
+        SurfaceOrigin::BottomLeft,
+        ColorType::RGBA8888,
+        color_space,
+        None,
+    )
+    .expect("Could not create skia surface")
+}
+
+/// Puts `text` on the system clipboard through the platform's copy command.
+fn copy_to_clipboard(text: &str) {
+    for (program, args) in CLIPBOARD_COMMANDS {
+        let Ok(mut child) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .spawn()
+        else {
+            continue;
+        };
+
+        // closing stdin lets the command finish
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(text.as_bytes());
+        }
+        if child.wait().is_ok_and(|status| status.success()) {
+            return;
+        }
+    }
+
+    eprintln!("No clipboard command found, the selection wasn't copied.");
+}
+
+/// What the page's relative URLs resolve against, its `<base href>` when it has one.
+fn base_url(url: &Url, node: &HTMLNodeRef) -> Url {
+    match get_base_href(node.clone()) {
+        Some(href) => url.resolve(&href),
+        None => url.clone(),
+    }
+}
+
+/// The page's stylesheet links, resolved against its base URL.
+fn stylesheet_urls(url: &Url, node: &HTMLNodeRef) -> Vec<Url> {
+    let base = base_url(url, node);
+
+    get_links(node.clone())
+        .iter()
+        .map(|link| base.resolve(link))
+        .collect()
+}
+
+/// A document with `text` in a `pre`, to show responses that aren't HTML as they are.
+fn plain_text_document(text: &str) -> HTMLNodeRef {
+    let html = HTMLNode::new_element(None, HTML.to_string(), HashMap::new(), false);
+    let mut parent = html.clone();
+    for tag in [BODY, PRE] {
+        let weak = Some(Rc::downgrade(&parent));
+        let node = HTMLNode::new_element(weak, tag.to_string(), HashMap::new(), false);
+        parent.borrow_mut().children.push(node.clone());
+        parent = node;
+    }
+
+    let text = HTMLNode::new_text(Some(Rc::downgrade(&parent)), text.to_string());
+    parent.borrow_mut().children.push(text);
+    html
+}
+
+/// The `href`s of the page's links that resolve to a visited URL.
+fn visited_links(url: &Url, node: &HTMLNodeRef, visited: &HashSet<String>) -> HashSet<String> {
+    let base = base_url(url, node);
+
+    get_hrefs(node.clone())
+        .into_iter()
+        .filter(|href| visited.contains(&base.resolve(href).href()))
+        .collect()
+}
+
+/// The page's `rel=icon` link, resolved against its base URL.
+fn favicon_url(url: &Url, node: &HTMLNodeRef) -> Option<Url> {
+    get_favicon(node.clone()).map(|href| base_url(url, node).resolve(&href))
+}
+
+/// Fetches the stylesheets each on its own thread, returning their rules in `style_urls` order
+/// so the cascade sees them as the page declared them.
+fn fetch_stylesheets(style_urls: Vec<Url>, referer: &Url) -> CSSRules {
+    let (sender, receiver) = mpsc::channel();
+    let count = style_urls.len();
+    for (i, style_url) in style_urls.into_iter().enumerate() {
+        let sender = sender.clone();
+        let referer = referer.clone();
+        spawn_request(move || {
+            // the receiver is only dropped after every sender
+            let _ = sender.send((i, fetch_stylesheet(&style_url, &referer, 0)));
+        });
+    }
+    drop(sender);
+
+    let mut sheets = vec![Vec::new(); count];
+    for (i, rules) in receiver {
+        sheets[i] = rules;
+    }
+    sheets.concat()
+}
+
+/// Fetches and parses a stylesheet, the rules of its `@import`s coming before its own.
+/// Imports resolve against, and are referred by, the sheet that imports them.
+fn fetch_stylesheet(style_url: &Url, referer: &Url, depth: usize) -> CSSRules {
+    let headers = vec![(HEADER_REFERER.to_string(), referer.href())];
+    let body = style_url.request_with_headers(&headers);
+    let sheet = parse_stylesheet(&body);
+
+    let mut rules = Vec::new();
+    for import in sheet.imports() {
+        if depth >= MAX_IMPORT_DEPTH {
+            eprintln!("Too many nested @imports, skipping {}", import);
+            continue;
+        }
+        rules.extend(fetch_stylesheet(
+            &style_url.resolve(import),
+            style_url,
+            depth + 1,
+        ));
+    }
+
+    rules.extend(sheet.rules().iter().cloned());
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constant::html::TEMPLATE;
+    use crate::constant::style::STYLE_KEY_COLOR;
+    use crate::net::url::{override_host, set_no_network};
+    use crate::parser::html_parse::SpecHtmlParser;
+    use crate::parser::html_parser::{HTMLParser, walk_tree};
+
+    fn find_tag(node: &HTMLNodeRef, tag: &str) -> Option<HTMLNodeRef> {
+        if let HTMLNodeData::Element(e) = &node.borrow().data
+            && e.tag == tag
+        {
+            return Some(node.clone());
+        }
+        node.borrow()
+            .children
+            .iter()
+            .find_map(|child| find_tag(child, tag))
+    }
+
+    fn computed_color(browser: &Browser, html: &str, tag: &str) -> Option<String> {
+        let node = HTMLParser::new(html.to_string()).parse().unwrap();
+        style(
+            node.clone(),
+            browser.default_style_sheet.rules(),
+            &HashSet::new(),
+        );
+        let element = find_tag(&node, tag)?;
+        element.borrow().style.get(STYLE_KEY_COLOR).cloned()
+    }
+
+    #[test]
+    fn test_find_matches_case_insensitive() {
+        let font = FontManager::new()
+            .borrow_mut()
+            .get_font(12, Weight::NORMAL, Slant::Upright);
+
+        let mut browser = Browser::new();
+        browser.display_list = vec![
+            DrawCommand::text(
+                0.0,
+                0.0,
+                10.0,
+                "Hello".to_string(),
+                font.clone(),
+                "black",
+                "transparent",
+            ),
+            DrawCommand::text(
+                50.0,
+                0.0,
+                10.0,
+                "world".to_string(),
+                font.clone(),
+                "black",
+                "transparent",
+            ),
+            DrawCommand::text(
+                0.0,
+                20.0,
+                30.0,
+                "WORLDWORLD".to_string(),
+                font,
+                "black",
+                "transparent",
+            ),
+            DrawCommand::rect(0.0, 0.0, 10.0, 10.0, "red"),
+        ];
+
+        let matches = browser.find_matches("world");
+        assert_eq!(matches.len(), 3);
+        assert_eq!(matches[0].left, 50.0);
+        assert_eq!(matches[1].top, 20.0);
+        assert!(matches[2].left > matches[1].right - 0.001);
+
+        assert!(browser.find_matches("missing").is_empty());
+        assert!(browser.find_matches("").is_empty());
+
+        // a match can run over the space between the words of a line, not across lines
+        let hello_world = browser.find_matches("hello WORLD");
+        assert_eq!(hello_world.len(), 1);
+        assert_eq!(hello_world[0].left, 0.0);
+        assert_eq!(hello_world[0].right, matches[0].right);
+        assert_eq!(browser.find_matches("o w").len(), 1);
+        assert!(browser.find_matches("world world").is_empty());
+    }
+
+    fn layout_html(browser: &mut Browser, html: &str) {
+        let node = HTMLParser::new(html.to_string()).parse().unwrap();
+        style(
+            node.clone(),
+            browser.default_style_sheet.rules(),
+            &HashSet::new(),
+        );
+
+        let document = DocumentLayout::new(node);
+        document.borrow_mut().layout();
+        let block = document.borrow().child.clone().unwrap();
+        browser.document = Some(document);
+        browser.paint_tree(block, false);
+    }
+
+    #[test]
+    fn test_tab_moves_focus_between_links() {
+        let mut browser = Browser::new();
+        layout_html(
+            &mut browser,
+            "<p><a href=/one>One</a> and <a>none</a> <a href=/two>Two</a></p><input>",
+        );
+
+        let href = |browser: &Browser| match &browser.focused.as_ref().unwrap().borrow().data {
+            HTMLNodeData::Element(e) => e.attributes.get("href").cloned(),
+            _ => None,
+        };
+
+        browser.focus_next(false);
+        assert_eq!(href(&browser), Some("/one".to_string()));
+        let (ring, word) = (
+            browser.focus_ring().unwrap(),
+            browser.find_matches("One")[0],
+        );
+        assert!(ring.left < word.left && ring.right > word.right);
+        assert!(ring.right < browser.find_matches("and")[0].left);
+
+        // the `a` without an href is skipped
+        browser.focus_next(false);
+        assert_eq!(href(&browser), Some("/two".to_string()));
+
+        browser.focus_next(false);
+        assert_eq!(href(&browser), None);
+        browser.focus_next(false);
+        assert_eq!(href(&browser), Some("/one".to_string()));
+        browser.focus_next(true);
+        assert_eq!(href(&browser), None);
+    }
+
+    #[test]
+    fn test_text_in_range() {
+        let mut browser = Browser::new();
+        layout_html(&mut browser, "<p>Hello brave world</p><p>Second line</p>");
+
+        let rect = |word: &str| browser.find_matches(word)[0];
+        let (brave, world, second) = (rect("brave"), rect("world"), rect("Second"));
+        let middle_y = |rect: Rect| (rect.top + rect.bottom) / 2.0;
+        let start = Point::new(brave.left, middle_y(brave));
+        let end = Point::new(brave.right, middle_y(brave));
+
+        assert_eq!(browser.text_in_range(start, end), "brave");
+        assert_eq!(browser.text_in_range(end, start), "brave");
+
+        // part of a word, then across words on the line
+        let middle = Point::new((brave.left + brave.right) / 2.0, middle_y(brave));
+        assert!("brave".ends_with(&browser.text_in_range(middle, end)));
+        let end = Point::new(world.right, middle_y(world));
+        assert_eq!(browser.text_in_range(start, end), "brave world");
+
+        // later lines run up to the end point, earlier ones from the start point
+        let end = Point::new(second.right, middle_y(second));
+        assert_eq!(browser.text_in_range(start, end), "brave world\nSecond");
+        assert_eq!(browser.text_in_range(end, start), "brave world\nSecond");
+    }
+
+    #[test]
+    fn test_fixed_element_ignores_scroll() {
+        let html = "<body><div style=\"position:fixed;top:0px;background-color:red\">header</div><p style=\"background-color:blue\">content</p></body>";
+        let mut browser = Browser::new();
+        layout_html(&mut browser, html);
+
+        let painted = |color: &str| {
+            browser
+                .display_list
+                .iter()
+                .find(|cmd| matches!(cmd, DrawCommand::Rect(_)) && cmd.to_string().contains(color))
+                .unwrap()
+        };
+
+        let fixed = painted("red");
+        assert_eq!(fixed.painted_top(0.0), fixed.painted_top(300.0));
+
+        let scrolled = painted("blue");
+        assert_eq!(
+            scrolled.painted_top(0.0) - 300.0,
+            scrolled.painted_top(300.0)
+        );
+
+        // the fixed header is out of flow, so the paragraph starts at the top
+        assert_eq!(fixed.get_top(), scrolled.get_top());
+    }
+
+    #[test]
+    fn test_dump_display_list_in_viewport_space() {
+        let mut browser = Browser::new();
+        layout_html(
+            &mut browser,
+            "<p style=\"background-color:blue\">content</p>",
+        );
+
+        let top = |line: &String| -> f32 {
+            let top = line.split_whitespace().next().unwrap();
+            top.strip_prefix("top=").unwrap().parse().unwrap()
+        };
+
+        let at_top = browser.dump_display_list(0.0);
+        let scrolled = browser.dump_display_list(50.0);
+        assert_eq!(at_top.len(), browser.display_list.len());
+        assert!(at_top[0].contains("DrawRect"));
+
+        for (line, scrolled_line) in at_top.iter().zip(&scrolled) {
+            assert_eq!(top(line) - 50.0, top(scrolled_line));
+        }
+    }
+
+    #[test]
+    fn test_scroll_is_instant_by_default() {
+        let mut browser = Browser::new();
+        layout_html(&mut browser, &"<p>line</p>".repeat(200));
+
+        browser.scroll_by(SCROLL_STEP);
+        assert_eq!(browser.scroll, SCROLL_STEP);
+
+        browser.scroll_by(-3.0 * SCROLL_STEP);
+        assert_eq!(browser.scroll, 0.0);
+    }
+
+    #[test]
+    fn test_smooth_scroll_settles_on_target() {
+        let mut browser = Browser::new();
+        browser.set_smooth_scroll(true);
+        layout_html(&mut browser, &"<p>line</p>".repeat(200));
+
+        browser.scroll_by(SCROLL_STEP);
+        assert_eq!(browser.scroll, 0.0);
+
+        let mut frames = 0;
+        while browser.step_scroll() {
+            assert!(browser.scroll > 0.0 && browser.scroll < SCROLL_STEP);
+            frames += 1;
+        }
+        assert!(frames > 1);
+        assert_eq!(browser.scroll, SCROLL_STEP);
+
+        // the target clamps to the same bounds as instant scrolling
+        browser.scroll_by(1_000_000.0);
+        assert_eq!(browser.scroll_target, browser.max_scroll());
+    }
+
+    #[test]
+    fn test_load_html_renders_without_fetching_the_page() {
+        // only the stylesheet is served, relative to the base url
+        let base = serve(vec![
+            "p { background-color: red; }".to_string(),
+            "p { background-color: blue; }".to_string(),
+        ]);
+        let html = "<link rel=stylesheet href=style.css><p>inline <a href=next>page</a></p>";
+
+        let mut browser = Browser::new();
+        browser.load_html(html, &base);
+
+        let painted = |kind: &str, what: &str| {
+            browser
+                .display_list
+                .iter()
+                .any(|cmd| cmd.to_string().starts_with(kind) && cmd.to_string().contains(what))
+        };
+        assert!(painted("DrawText", "text=inline)"));
+        assert!(painted("DrawText", "text=page)"));
+        assert!(painted("DrawRect", "red"));
+
+        assert!(browser.response().is_none());
+        assert_eq!(
+            browser.url.as_ref().map(|url| url.href()),
+            Some(base.href())
+        );
+
+        // reload renders the markup again, only the stylesheet is fetched anew
+        browser.reload();
+        let painted = |kind: &str, what: &str| {
+            browser
+                .display_list
+                .iter()
+                .any(|cmd| cmd.to_string().starts_with(kind) && cmd.to_string().contains(what))
+        };
+        assert!(painted("DrawText", "text=inline)"));
+        assert!(painted("DrawRect", "blue"));
+        assert!(browser.response().is_none());
+    }
+
+    /// Serve each body once, in order, over plain HTTP on a local port.
+    fn serve(bodies: Vec<String>) -> Url {
+        let responses = bodies
+            .into_iter()
+            .map(|body| (String::new(), body.into_bytes()));
+        serve_responses(responses.collect())
+    }
+
+    /// Like `serve`, each body sent after its own extra header lines.
+    fn serve_responses(responses: Vec<(String, Vec<u8>)>) -> Url {
+        let heads = responses
+            .into_iter()
+            .map(|(headers, body)| (format!("HTTP/1.0 200 OK\r\n{}", headers), body));
+        serve_heads(heads.collect())
+    }
+
+    /// Like `serve_responses`, each head starting with its own status line.
+    fn serve_heads(responses: Vec<(String, Vec<u8>)>) -> Url {
+        let responses = std::sync::Mutex::new(responses.into_iter());
+        serve_with(move |_| responses.lock().unwrap().next())
+    }
+
+    /// Answers each request on its own thread with the head and body `respond` gives for its
+    /// path, the head without the blank line ending it. `None` closes the connection unanswered.
+    fn serve_with<F>(respond: F) -> Url
+    where
+        F: Fn(&str) -> Option<(String, Vec<u8>)> + Send + Sync + 'static,
+    {
+        use std::io::{BufRead, BufReader};
+        use std::net::TcpListener;
+        use std::sync::Arc;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let respond = Arc::new(respond);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let respond = respond.clone();
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line).unwrap();
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                        line.clear();
+                    }
+
+                    let path = request_line.split(' ').nth(1).unwrap_or_default();
+                    if let Some((head, body)) = respond(path) {
+                        stream
+                            .write_all(format!("{}\r\n", head).as_bytes())
+                            .unwrap();
+                        stream.write_all(&body).unwrap();
+                    }
+                });
+            }
+        });
+
+        Url::new(&format!("http://127.0.0.1:{}/", port))
+    }
+
+    #[test]
+    fn test_reload_keeps_scroll_and_navigate_resets_it() {
+        let long = "<p>line</p>".repeat(200);
+        let short = "<p>line</p>".repeat(60);
+        let url = serve(vec![long.clone(), long.clone(), short, long]);
+
+        let mut browser = Browser::new();
+        browser.navigate(&url);
+        browser.scroll = 2000.0;
+
+        browser.reload();
+        assert_eq!(browser.scroll, 2000.0);
+
+        // the shorter page can't scroll as far
+        browser.reload();
+        assert!(browser.scroll < 2000.0);
+        assert_eq!(browser.scroll, browser.max_scroll());
+
+        browser.navigate(&url);
+        assert_eq!(browser.scroll, 0.0);
+    }
+
+    #[test]
+    fn test_fragment_scrolls_to_element() {
+        let page = format!(
+            "{}<h1 id=foo>Foo</h1>{}<p>bottom <span id=bar>bar</span></p>",
+            "<p>line</p>".repeat(100),
+            "<p>line</p>".repeat(100)
+        );
+        let url = serve(vec![page.clone(), page.clone(), page]);
+
+        let mut browser = Browser::new();
+        browser.navigate(&url.resolve("/page.html#foo"));
+        let foo_top = browser.find_matches("Foo")[0].top;
+        assert!(browser.scroll > 0.0);
+        assert_eq!(browser.scroll, foo_top);
+
+        // an inline element near the bottom can only scroll as far as the page does
+        browser.navigate(&url.resolve("/page.html#bar"));
+        assert_eq!(browser.scroll, browser.max_scroll());
+
+        browser.navigate(&url.resolve("/page.html#missing"));
+        assert_eq!(browser.scroll, 0.0);
+    }
+
+    #[test]
+    fn test_anchor_registry_and_in_page_links() {
+        let page = format!(
+            "<p><a href=#foo>jump</a> <a href=#old>back</a></p>{}<h1 id=foo>Foo</h1>{}<p><a name=old>Old</a></p>{}",
+            "<p>line</p>".repeat(50),
+            "<p>line</p>".repeat(50),
+            "<p>line</p>".repeat(50)
+        );
+        let url = serve(vec![page]);
+
+        let mut browser = Browser::new();
+        browser.navigate(&url);
+        let foo_top = browser.find_matches("Foo")[0].top;
+        let old_top = browser.find_matches("Old")[0].top;
+        assert_eq!(browser.anchors.get("foo"), Some(&foo_top));
+        assert_eq!(browser.anchors.get("old"), Some(&old_top));
+        assert_eq!(browser.anchors.get("missing"), None);
+
+        // in-page links scroll without another request, the server only answers once
+        let jump = browser.find_matches("jump")[0];
+        browser.click(jump.left + 1.0, jump.top + 1.0);
+        assert_eq!(browser.scroll, foo_top);
+
+        let back = browser.find_matches("back")[0];
+        browser.click(back.left + 1.0, back.top + 1.0);
+        assert_eq!(browser.scroll, old_top);
+    }
+
+    #[test]
+    fn test_base_href_changes_stylesheet_resolution() {
+        let url = Url::new("https://example.org/docs/page.html");
+        let link = "<link rel=stylesheet href=\"main.css\">";
+
+        let node = HTMLParser::new(format!("<head>{}</head>", link))
+            .parse()
+            .unwrap();
+        let urls = stylesheet_urls(&url, &node);
+        assert_eq!(urls[0].path, "/docs/main.css");
+
+        let node = HTMLParser::new(format!("<head><base href=\"/assets/\">{}</head>", link))
+            .parse()
+            .unwrap();
+        let urls = stylesheet_urls(&url, &node);
+        assert_eq!(urls[0].host, "example.org");
+        assert_eq!(urls[0].path, "/assets/main.css");
+    }
+
+    #[test]
+    fn test_favicon_link_is_resolved() {
+        let url = Url::new("https://example.org/docs/page.html");
+        let head =
+            "<head><link rel=stylesheet href=main.css><link rel=ICON href=\"img/fav.ico\"></head>";
+        let node = HTMLParser::new(head.to_string()).parse().unwrap();
+        let favicon = favicon_url(&url, &node).unwrap();
+        assert_eq!(favicon.href(), "https://example.org/docs/img/fav.ico");
+
+        // `shortcut icon` needs the spec parser, the simple one splits attributes at spaces
+        let head = "<head><link rel=\"shortcut icon\" href=\"/favicon.ico\"></head>";
+        let node = SpecHtmlParser.parse(head).unwrap();
+        assert_eq!(get_favicon(node), Some("/favicon.ico".to_string()));
+
+        let node = HTMLParser::new("<head><link rel=stylesheet href=main.css></head>".to_string())
+            .parse()
+            .unwrap();
+        assert!(favicon_url(&url, &node).is_none());
+    }
+
+    #[test]
+    fn test_visited_link_is_styled_differently() {
+        let url = serve(vec![
+            "<p><a href=\"seen\">seen</a> <a href=\"/new\">new</a></p>".to_string(),
+        ]);
+
+        let mut browser = Browser::new();
+        browser.visited.insert(url.resolve("/seen").href());
+        browser.navigate(&url);
+
+        let nodes: Vec<_> = walk_tree(browser.nodes.clone().unwrap()).collect();
+        let colors: Vec<Option<String>> = nodes
+            .iter()
+            .filter(|node| matches!(&node.borrow().data, HTMLNodeData::Element(e) if e.tag == "a"))
+            .map(|node| node.borrow().style.get(STYLE_KEY_COLOR).cloned())
+            .collect();
+        assert_eq!(
+            colors,
+            vec![Some("purple".to_string()), Some("blue".to_string())]
+        );
+        assert!(browser.visited.contains(&url.href()));
+    }
+
+    #[test]
+    fn test_parallel_stylesheets_keep_source_order() {
+        let page =
+            "<link rel=stylesheet href=first.css><link rel=stylesheet href=second.css><p>text</p>";
+
+        // answers by path, the first sheet slowest so it arrives last
+        let server = serve_with(move |path| {
+            let body = match path {
+                "/first.css" => {
+                    std::thread::sleep(Duration::from_millis(100));
+                    "p { color: red; }"
+                }
+                "/second.css" => "p { color: green; }",
+                _ => page,
+            };
+            Some(("HTTP/1.0 200 OK\r\n".to_string(), body.into()))
+        });
+
+        override_host("sheets.example:80", ("127.0.0.1".to_string(), server.port));
+        let mut browser = Browser::new();
+        browser.navigate(&Url::new("http://sheets.example/"));
+
+        let p = find_tag(browser.nodes.as_ref().unwrap(), "p").unwrap();
+        assert_eq!(
+            p.borrow().style.get(STYLE_KEY_COLOR),
+            Some(&"green".to_string())
+        );
+    }
+
+    #[test]
+    fn test_response_is_from_the_redirect_target() {
+        let heads = [
+            "HTTP/1.0 302 Found\r\nLocation: /moved/page\r\n",
+            "HTTP/1.0 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n",
+        ];
+        let body = "<a href=next>next</a>";
+        let server = serve_heads(
+            heads
+                .into_iter()
+                .map(|head| (head.to_string(), body.into()))
+                .collect(),
+        );
+
+        let requested = Url::new(&format!("{}old#top", server.href()));
+        let mut browser = Browser::new();
+        browser.navigate(&requested);
+
+        let response = browser.response().unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type().as_deref(), Some("text/html"));
+        assert_ne!(response.url.href(), requested.href());
+        assert_eq!(response.url.path, "/moved/page");
+        assert_eq!(response.url.fragment.as_deref(), Some("top"));
+        assert_eq!(browser.url.as_ref().unwrap().path, "/moved/page");
+        assert!(browser.visited.contains(&requested.href()));
+        assert!(browser.visited.contains(&response.url.href()));
+    }
+
+    #[test]
+    fn test_resize_waits_for_the_size_to_settle() {
+        let start = Instant::now();
+        let delay = Duration::from_millis(RESIZE_DEBOUNCE_MS);
+        let mut resize = ResizeDebounce::new((800, 600));
+
+        resize.request((810, 600), start);
+        resize.request((820, 610), start + delay / 2);
+        // each step of the drag pushes the commit back
+        assert_eq!(resize.commit_due(start + delay), None);
+        assert_eq!(resize.deadline(), Some(start + delay / 2 + delay));
+
+        assert_eq!(resize.commit_due(start + delay * 2), Some((820, 610)));
+        assert_eq!(resize.committed, (820, 610));
+        assert_eq!(resize.deadline(), None);
+
+        // dragging back to the committed size leaves nothing to do
+        resize.request((830, 610), start + delay * 3);
+        resize.request((820, 610), start + delay * 3);
+        assert_eq!(resize.commit_due(start + delay * 5), None);
+    }
+
+    #[test]
+    fn test_hover_tracks_title() {
+        let mut browser = Browser::new();
+        layout_html(
+            &mut browser,
+            "<p>plain <span title=\"Details\">hover</span></p>",
+        );
+
+        let rect = browser.find_matches("hover")[0];
+        assert!(browser.hover(rect.left + 1.0, rect.top + 1.0));
+        assert_eq!(browser.hovered_title, Some("Details".to_string()));
+
+        // same element, nothing changed
+        assert!(!browser.hover(rect.left + 2.0, rect.top + 1.0));
+
+        let rect = browser.find_matches("plain")[0];
+        assert!(browser.hover(rect.left + 1.0, rect.top + 1.0));
+        assert_eq!(browser.hovered_title, None);
+    }
+
+    #[test]
+    fn test_tooltip_title_of_nearest_titled_ancestor() {
+        let mut browser = Browser::new();
+        layout_html(
+            &mut browser,
+            "<p><abbr title=\"Outer\"><b>bold</b> <span title=\"Inner\">nested</span></abbr></p>",
+        );
+
+        let rect = browser.find_matches("bold")[0];
+        browser.hover(rect.left + 1.0, rect.top + 1.0);
+        assert_eq!(browser.hovered_title, Some("Outer".to_string()));
+
+        let rect = browser.find_matches("nested")[0];
+        browser.hover(rect.left + 1.0, rect.top + 1.0);
+        assert_eq!(browser.hovered_title, Some("Inner".to_string()));
+
+        // the tooltip only shows once the delay is over
+        let due = browser.tooltip_at.unwrap();
+        assert!(!browser.show_due_tooltip(due - Duration::from_millis(1)));
+        assert!(!browser.tooltip_visible);
+        assert!(browser.show_due_tooltip(due));
+        assert!(browser.tooltip_visible);
+    }
+
+    #[test]
+    fn test_hover_pointer_over_links() {
+        let mut browser = Browser::new();
+        layout_html(
+            &mut browser,
+            "<p>plain <a href=\"/next\">link</a> <span style=\"cursor:pointer\">button</span></p>",
+        );
+
+        let mut hover = |word: &str| {
+            let rect = browser.find_matches(word)[0];
+            browser.hover(rect.left + 1.0, rect.top + 1.0);
+            browser.hovered_pointer
+        };
+
+        assert!(hover("link"));
+        assert!(hover("button"));
+        assert!(!hover("plain"));
+    }
+
+    #[test]
+    fn test_template_content_is_inert() {
+        let html = "<template><p>hidden</p></template><p>shown</p>";
+        let parsers: [Box<dyn HtmlParse>; 2] =
+            [Box::new(SimpleHtmlParser), Box::new(SpecHtmlParser)];
+        for parser in parsers {
+            let url = serve(vec![html.to_string()]);
+            let mut browser = Browser::new();
+            browser.set_html_parser(parser);
+            browser.navigate(&url);
+            assert!(browser.find_matches("hidden").is_empty());
+            assert_eq!(browser.find_matches("shown").len(), 1);
+
+            // the content is still in the tree, under the template the walk doesn't go into
+            let template = walk_tree(browser.nodes.clone().unwrap())
+                .find(|node| match &node.borrow().data {
+                    HTMLNodeData::Element(e) => e.tag == TEMPLATE,
+                    HTMLNodeData::Text(_) => false,
+                })
+                .unwrap();
+            let children = template.borrow().children.clone();
+            let nodes: Vec<_> = children.into_iter().flat_map(walk_tree).collect();
+            assert!(nodes.iter().any(|node| match &node.borrow().data {
+                HTMLNodeData::Text(t) => t.text == "hidden",
+                _ => false,
+            }));
+        }
+    }
+
+    #[test]
+    fn test_swapped_html_parser_is_used_for_loading() {
+        struct FixedParser;
+
+        impl HtmlParse for FixedParser {
+            fn parse(&self, _html: &str) -> Option<HTMLNodeRef> {
+                HTMLParser::new("<p>replaced</p>".to_string()).parse()
+            }
+        }
+
+        let url = serve(vec![
+            "<p>served</p>".to_string(),
+            "<p>served</p>".to_string(),
+        ]);
+
+        let mut browser = Browser::new();
+        browser.set_html_parser(Box::new(FixedParser));
+        browser.navigate(&url);
+        assert_eq!(browser.find_matches("replaced").len(), 1);
+        assert!(browser.find_matches("served").is_empty());
+
+        browser.set_html_parser(Box::new(SpecHtmlParser));
+        browser.reload();
+        assert_eq!(browser.find_matches("served").len(), 1);
+    }
+
+    #[test]
+    fn test_non_html_content_type_skips_the_html_parser() {
+        struct PanickingParser;
+
+        impl HtmlParse for PanickingParser {
+            fn parse(&self, _html: &str) -> Option<HTMLNodeRef> {
+                panic!("only HTML should be parsed");
+            }
+        }
+
+        let url = serve_responses(vec![
+            (
+                "Content-Type: text/plain; charset=utf-8\r\n".to_string(),
+                b"<p>as is</p>".to_vec(),
+            ),
+            (
+                "Content-Type: image/png\r\n".to_string(),
+                b"\x01PNG".to_vec(),
+            ),
+        ]);
+
+        let mut browser = Browser::new();
+        browser.set_html_parser(Box::new(PanickingParser));
+        browser.navigate(&url);
+        assert_eq!(browser.find_matches("<p>as").len(), 1);
+
+        browser.reload();
+        assert_eq!(browser.find_matches("image/png").len(), 1);
+    }
+
+    #[test]
+    fn test_late_meta_charset_redecodes_the_page() {
+        // the declaration sits past the prescan, so the first guess is utf-8
+        let mut page = format!("<!--{}-->", "-".repeat(1100)).into_bytes();
+        page.extend_from_slice(b"<meta charset=iso-8859-1><p>caf\xe9</p>");
+        let url = serve_responses(vec![("Content-Type: text/html\r\n".to_string(), page)]);
+
+        let mut browser = Browser::new();
+        browser.navigate(&url);
+        assert_eq!(browser.find_matches("caf\u{e9}").len(), 1);
+    }
+
+    #[test]
+    fn test_unreachable_host_shows_an_error_page() {
+        // nothing listens on a port once its listener is dropped
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = Url::new(&format!("http://127.0.0.1:{}/", port));
+
+        let mut browser = Browser::new();
+        browser.navigate(&url);
+        assert_eq!(browser.response().map(|response| response.status), Some(0));
+        assert_eq!(browser.find_matches("could not connect").len(), 1);
+    }
+
+    #[test]
+    fn test_refused_request_shows_why_as_text() {
+        set_no_network(true);
+        let mut browser = Browser::new();
+        browser.navigate(&Url::new("http://offline.example/?q=<b>"));
+        set_no_network(false);
+
+        assert_eq!(browser.find_matches("network access is disabled").len(), 1);
+        assert_eq!(browser.find_matches("could not connect").len(), 0);
+        // the url stays text instead of turning into markup
+        assert_eq!(browser.find_matches("/?q=<b>").len(), 2);
+    }
+
+    #[test]
+    fn test_navigate_sends_previous_page_as_referer() {
+        let url = serve(vec![String::new(), String::new()]);
+
+        let mut browser = Browser::new();
+        browser.navigate(&url);
+        assert!(browser.request_headers().is_empty());
+
+        browser.navigate(&url);
+        assert_eq!(
+            browser.request_headers(),
+            vec![(HEADER_REFERER.to_string(), url.href())]
+        );
+    }
+
+    #[test]
+    fn test_noscript_content_is_rendered() {
+        let mut browser = Browser::new();
+        layout_html(
+            &mut browser,
+            "<noscript><p>No JS</p></noscript><p>after</p>",
+        );
+
+        let top_of = |word: &str| {
+            browser
+                .display_list
+                .iter()
+                .find(|cmd| matches!(cmd, DrawCommand::Text(_)) && cmd.to_string().contains(word))
+                .map(|cmd| cmd.get_top())
+                .expect("Missing word")
+        };
+
+        assert_eq!(top_of("text=No)"), top_of("text=JS)"));
+        assert!(top_of("text=No)") < top_of("text=after)"));
+    }
+
+    #[test]
+    fn test_translate_shifts_the_painted_subtree() {
+        let mut browser = Browser::new();
+        layout_html(
+            &mut browser,
+            "<div style=transform:translate(10px,20px)><p>moved</p></div><p>still</p>",
+        );
+
+        let position = |word: &str| {
+            browser
+                .display_list
+                .iter()
+                .position(|cmd| cmd.to_string().contains(word))
+                .expect("Missing command")
+        };
+        let push = position("DrawTransform");
+        let moved = position("text=moved)");
+        let pop = position("PopTransform");
+        assert!(push < moved && moved < pop && pop < position("text=still)"));
+
+        let DrawCommand::PushTransform(transform) = &browser.display_list[push] else {
+            panic!("expected a transform");
+        };
+        let top = browser.display_list[moved].get_top();
+        assert_eq!(transform.apply(5.0, top), (15.0, top + 20.0));
+        let (painted_top, _) = browser.display_list[moved].transformed_span(0.0, &[transform]);
+        assert_eq!(painted_top, top + 20.0);
+    }
+
+    #[test]
+    fn test_overflow_hidden_emits_clip() {
+        let mut browser = Browser::new();
+        layout_html(
+            &mut browser,
+            "<div style=\"overflow:hidden;height:20px\"><p>one</p><p>two</p><p>three</p></div><p>after</p>",
+        );
+
+        let kinds: Vec<String> = browser
+            .display_list
+            .iter()
+            .map(|cmd| match cmd {
+                DrawCommand::PushClip(_) => "push".to_string(),
+                DrawCommand::PopClip => "pop".to_string(),
+                _ => cmd.to_string(),
+            })
+            .collect();
+
+        let push = kinds
+            .iter()
+            .position(|k| k == "push")
+            .expect("Missing clip");
+        let pop = kinds.iter().position(|k| k == "pop").expect("Missing pop");
+        let three = kinds.iter().position(|k| k.contains("three")).unwrap();
+        let after = kinds.iter().position(|k| k.contains("after")).unwrap();
+        assert!(push < three && three < pop && pop < after);
+
+        let clip = &browser.display_list[push];
+        assert_eq!(clip.get_bottom() - clip.get_top(), 20.0);
+
+        // without an explicit height there's nothing to clip to
+        let mut browser = Browser::new();
+        layout_html(
+            &mut browser,
+            "<div style=\"overflow:hidden\"><p>one</p></div>",
+        );
+        assert!(!browser.display_list.iter().any(|cmd| cmd.is_marker()));
+    }
+
+    /// Paint `browser` into an offscreen surface of `size` and encode it as a PNG.
+    fn render_png(browser: &mut Browser, size: (i32, i32)) -> Vec<u8> {
+        use skia_safe::{ImageInfo, png_encoder, surfaces};
+
+        let info = ImageInfo::new_n32_premul(size, browser.render_options.color_space());
+        let mut surface = surfaces::raster(&info, None, None).unwrap();
+        browser.render(&mut surface, 1.0);
+
+        let image = surface.image_snapshot();
+        let png = png_encoder::encode_image(None, &image, &png_encoder::Options::default());
+        png.unwrap().as_bytes().to_vec()
+    }
+
+    /// Compare `png` with the snapshot `name`, recording it on the first run.
+    fn assert_snapshot(name: &str, png: &[u8]) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/asset/snapshot")
+            .join(name);
+        match std::fs::read(&path) {
+            Ok(snapshot) => assert!(snapshot == png, "{} differs from its snapshot", name),
+            Err(_) => {
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(&path, png).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_srgb_edge_matches_its_snapshots() {
+        use skia_safe::{CachingHint, Data, Image, ImageInfo};
+
+        let (width, height) = (20, 8);
+        // a gradient edge: a black box whose right edge covers more of the pixel at x = 10
+        // on every row down
+        let edge = |srgb: bool| {
+            let mut browser = Browser::new();
+            browser.render_options.srgb = srgb;
+            browser.display_list = (0..height)
+                .map(|row| {
+                    let right = 10.0 + row as f32 / height as f32;
+                    DrawCommand::rect(0.0, row as f32, right, 1.0, "black")
+                })
+                .collect();
+            render_png(&mut browser, (width, height))
+        };
+        let (device, linear) = (edge(false), edge(true));
+        assert_snapshot("srgb_off.png", &device);
+        assert_snapshot("srgb_on.png", &linear);
+
+        // the brightness of the edge pixel on each row, decoded from the snapshot
+        let column = |png: &[u8]| {
+            let image = Image::from_encoded(Data::new_copy(png)).unwrap();
+            let info = ImageInfo::new_n32_premul((width, height), None);
+            let row_bytes = info.min_row_bytes();
+            let mut pixels = vec![0u8; row_bytes * height as usize];
+            assert!(image.read_pixels(&info, &mut pixels, row_bytes, (0, 0), CachingHint::Allow));
+            // gray, so any channel will do
+            (0..height as usize)
+                .map(|row| pixels[row * row_bytes + 10 * 4])
+                .collect::<Vec<_>>()
+        };
+        let (device, linear) = (column(&device), column(&linear));
+
+        // the uncovered pixel is the page's white either way
+        assert_eq!((device[0], linear[0]), (255, 255));
+        for row in 1..height as usize {
+            // more coverage darkens the edge in both
+            assert!(device[row] < device[row - 1]);
+            assert!(linear[row] < linear[row - 1]);
+            // mixing light keeps a partly covered pixel lighter than mixing encoded values
+            assert!(linear[row] > device[row]);
+        }
+    }
+
+    #[test]
+    fn test_wheel_scrolls_an_overflow_box_on_its_own() {
+        let mut browser = Browser::new();
+        layout_html(
+            &mut browser,
+            "<div style=\"overflow:auto;height:20px\"><p>one</p><p>two</p><p>three</p></div><p>after</p>",
+        );
+
+        // where a word is painted on screen, through the transforms around it
+        let painted_top = |browser: &Browser, word: &str| {
+            let mut transforms = Vec::new();
+            for cmd in &browser.display_list {
+                match cmd {
+                    DrawCommand::PushTransform(transform) => transforms.push(transform),
+                    DrawCommand::PopTransform => {
+                        transforms.pop();
+                    }
+                    DrawCommand::Text(_) if cmd.to_string().contains(word) => {
+                        return cmd.transformed_span(browser.scroll, &transforms).0;
+                    }
+                    _ => {}
+                }
+            }
+            panic!("Missing {}", word);
+        };
+        let (one, after) = (painted_top(&browser, "one"), painted_top(&browser, "after"));
+
+        let rect = browser.find_matches("one")[0];
+        browser.cursor = (rect.left + 1.0, rect.top + 1.0);
+        browser.wheel(20.0);
+
+        assert_eq!(painted_top(&browser, "one"), one - 20.0);
+        assert_eq!(painted_top(&browser, "after"), after);
+        assert_eq!(browser.scroll, 0.0);
+
+        // the box stops at its top instead of scrolling further
+        browser.wheel(-100.0);
+        assert_eq!(painted_top(&browser, "one"), one);
+    }
+
+    #[test]
+    fn test_find_and_focus_follow_a_scrolled_box() {
+        let mut browser = Browser::new();
+        layout_html(
+            &mut browser,
+            "<div style=\"overflow:auto;height:20px\"><p>one</p><p><a href=x>two</a></p></div>",
+        );
+        browser.find_query = Some("two".to_string());
+        browser.update_find();
+        let highlight = browser.find_highlights[0];
+        browser.focus_next(false);
+        let ring = browser.focus_ring().unwrap();
+
+        let rect = browser.find_matches("one")[0];
+        browser.cursor = (rect.left + 1.0, rect.top + 1.0);
+        browser.wheel(10.0);
+        let moved = rect.top - browser.find_matches("one")[0].top;
+
+        assert!(moved > 0.0);
+        assert_eq!(browser.find_matches("two")[0].top, highlight.top - moved);
+        assert_eq!(browser.find_highlights[0].top, highlight.top - moved);
+        assert_eq!(browser.focus_ring().unwrap().top, ring.top - moved);
+    }
+
+    #[test]
+    fn test_q_is_quoted_and_blockquote_indented() {
+        let mut browser = Browser::new();
+        layout_html(
+            &mut browser,
+            "<p><q>hi</q></p><blockquote>quoted</blockquote><p>plain</p>",
+        );
+
+        let left = |text: &str| browser.find_matches(text)[0].left;
+        assert!(left("\u{201C}") < left("hi") && left("hi") < left("\u{201D}"));
+        assert_eq!(left("quoted"), left("plain") + 40.0);
+    }
+
+    #[test]
+    fn test_custom_stylesheet_changes_computed_style() {
+        let path = std::env::temp_dir().join("even_browser_test_ua.css");
+        std::fs::write(&path, "p { color: red; }").unwrap();
+
+        let browser = Browser::with_stylesheet(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            computed_color(&browser, "<p>hi</p>", "p"),
+            Some("red".to_string())
+        );
+    }
+
+    #[test]
+    fn test_missing_stylesheet_falls_back_to_default() {
+        let browser = Browser::with_stylesheet(Path::new("does/not/exist.css"));
+        assert_eq!(
+            computed_color(&browser, "<a>link</a>", "a"),
+            Some("blue".to_string())
+        );
+    }
+}
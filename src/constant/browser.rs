@@ -1,4 +1,43 @@
 pub const WIDTH: f32 = 800.0;
 pub const HEIGHT: f32 = 600.0;
 pub const SCROLL_STEP: f32 = 100.0;
-pub const DEFAULT_BROWSER_PADDING: f32 = 20.0;
\ No newline at end of file
+// how far one notch of the mouse wheel scrolls
+pub const WHEEL_LINE_HEIGHT: f32 = 40.0;
+pub const DEFAULT_BROWSER_PADDING: f32 = 20.0;
+// fraction of the remaining distance covered per frame
+pub const SMOOTH_SCROLL_FACTOR: f32 = 0.3;
+pub const SMOOTH_SCROLL_EPSILON: f32 = 0.5;
+pub const SMOOTH_SCROLL_FRAME_MS: u64 = 16;
+pub const FIND_BAR_HEIGHT: f32 = 24.0;
+pub const FIND_BAR_FONT_SIZE: i32 = 14;
+pub const FIND_BAR_COLOR: &str = "#eeeeee";
+pub const FIND_HIGHLIGHT_COLOR: &str = "rgba(255, 200, 0, 0.4)";
+pub const SELECTION_COLOR: &str = "rgba(0, 120, 215, 0.3)";
+pub const FOCUS_RING_COLOR: &str = "#1a73e8";
+pub const FOCUS_RING_THICKNESS: f32 = 2.0;
+// room between the focused element and its ring
+pub const FOCUS_RING_OFFSET: f32 = 2.0;
+/// Tried in order to put a copy on the system clipboard, the first that runs wins.
+pub const CLIPBOARD_COMMANDS: [(&str, &[&str]); 5] = [
+    ("pbcopy", &[]),
+    ("clip", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+pub const TOOLTIP_COLOR: &str = "lightyellow";
+pub const TOOLTIP_BORDER_COLOR: &str = "gray";
+pub const TOOLTIP_PADDING: f32 = 4.0;
+// distance from the cursor, so the tooltip doesn't sit under it
+pub const TOOLTIP_OFFSET: f32 = 16.0;
+// how long the cursor stays over a titled element before its tooltip shows
+pub const TOOLTIP_DELAY_MS: u64 = 500;
+// how long a window size has to hold before the surface is rebuilt for it
+pub const RESIZE_DEBOUNCE_MS: u64 = 150;
+/// Used until a page with a `<title>` is loaded.
+pub const WINDOW_TITLE: &str = "Even Browser";
+// how deep `@import`s are followed, so a sheet importing itself doesn't loop forever
+pub const MAX_IMPORT_DEPTH: usize = 4;
+/// Heads the error shown in place of a page whose request got no response.
+pub const CONNECTION_ERROR_MESSAGE: &str = "Could not load page";
+pub const DEFAULT_URL: &str = "https://browser.engineering/styles.html";
@@ -1,5 +1,7 @@
 pub const COLON: char = ':';
 pub const HASH: char = '#';
+pub const AT: char = '@';
+pub const QUESTION_MARK: char = '?';
 pub const DASH: char = '-';
 pub const DOT: char = '.';
 pub const PERCENT: char = '%';
@@ -11,3 +13,9 @@ pub const SLASH: char = '/';
 pub const SINGLE_QUOTE: char = '\'';
 pub const DOUBLE_QUOTE: char = '"';
 pub const EQUALS: char = '=';
+pub const GREATER_THAN: char = '>';
+pub const OPENING_BRACKET: char = '[';
+pub const CLOSING_BRACKET: char = ']';
+pub const OPENING_PARENTHESIS: char = '(';
+pub const CLOSING_PARENTHESIS: char = ')';
+pub const COMMA: char = ',';
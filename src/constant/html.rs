@@ -3,13 +3,35 @@ pub const SLASH_HTML: &str = "/html";
 pub const HEAD: &str = "head";
 pub const SLASH_HEAD: &str = "/head";
 pub const BODY: &str = "body";
+pub const PRE: &str = "pre";
 pub const STYLE: &str = "style";
 pub const LINK: &str = "link";
+pub const BASE: &str = "base";
+pub const TITLE: &str = "title";
+pub const NOSCRIPT: &str = "noscript";
+/// Its content is kept in the tree but never laid out or painted.
+pub const TEMPLATE: &str = "template";
+pub const SLASH_NOSCRIPT: &str = "/noscript";
+pub const COMMENT_START: &str = "<!--";
+pub const COMMENT_END: &str = "-->";
 
 pub const ATTRIBUTE_KEY_STYLE: &str = "style";
 pub const ATTRIBUTE_KEY_REL: &str = "rel";
 pub const ATTRIBUTE_REL_VALUE_STYLESHEET: &str = "stylesheet";
+pub const ATTRIBUTE_REL_VALUE_ICON: &str = "icon";
+/// Resources the page hints it will need, listed but never fetched ahead.
+pub const PRELOAD_REL_VALUES: [&str; 2] = ["preload", "prefetch"];
+pub const ATTRIBUTE_KEY_AS: &str = "as";
 pub const ATTRIBUTE_KEY_HREF: &str = "href";
+pub const ATTRIBUTE_KEY_ID: &str = "id";
+pub const ATTRIBUTE_KEY_CLASS: &str = "class";
+pub const ATTRIBUTE_KEY_HIDDEN: &str = "hidden";
+pub const ATTRIBUTE_KEY_NAME: &str = "name";
+pub const ANCHOR: &str = "a";
+pub const ATTRIBUTE_KEY_DIR: &str = "dir";
+pub const DIR_RTL: &str = "rtl";
+/// Overrides the direction of its content, `bdi` needs nothing as every character is laid out ltr.
+pub const BDO: &str = "bdo";
 
 pub const SELF_CLOSING_ELEMENTS: [&str; 14] = [
     "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
@@ -20,6 +42,10 @@ pub const HEAD_ELEMENTS: [&str; 9] = [
     "base", "basefont", "bgsound", "noscript", "link", "meta", "title", "style", "script",
 ];
 
+/// What a `noscript` in the head may hold when scripting is disabled, anything else ends the head.
+pub const NOSCRIPT_HEAD_ELEMENTS: [&str; 6] =
+    ["basefont", "bgsound", "link", "meta", "noframes", "style"];
+
 pub const BLOCK_ELEMENTS: [&str; 37] = [
     HTML,
     BODY,
@@ -59,3 +85,59 @@ pub const BLOCK_ELEMENTS: [&str; 37] = [
     "details",
     "summary",
 ];
+
+pub const INPUT: &str = "input";
+pub const TEXTAREA: &str = "textarea";
+
+pub const ATTRIBUTE_KEY_TYPE: &str = "type";
+pub const INPUT_TYPE_HIDDEN: &str = "hidden";
+pub const INPUT_TYPE_CHECKBOX: &str = "checkbox";
+pub const INPUT_TYPE_RADIO: &str = "radio";
+/// Input types that aren't typed into, every other type takes text like `text` does.
+pub const NON_TEXT_INPUT_TYPES: [&str; 6] = [
+    INPUT_TYPE_HIDDEN,
+    INPUT_TYPE_CHECKBOX,
+    INPUT_TYPE_RADIO,
+    "submit",
+    "reset",
+    "button",
+];
+pub const ATTRIBUTE_KEY_SIZE: &str = "size";
+pub const ATTRIBUTE_KEY_VALUE: &str = "value";
+pub const ATTRIBUTE_KEY_ROWS: &str = "rows";
+pub const ATTRIBUTE_KEY_COLS: &str = "cols";
+
+pub const IMG: &str = "img";
+/// Frames and plugins, drawn as a placeholder box instead of loading their content.
+pub const EMBEDDED_ELEMENTS: [&str; 3] = ["iframe", "object", "embed"];
+pub const ATTRIBUTE_KEY_SRC: &str = "src";
+pub const ATTRIBUTE_KEY_DATA: &str = "data";
+pub const ATTRIBUTE_KEY_HEIGHT: &str = "height";
+pub const HR: &str = "hr";
+/// A break opportunity inside a word, taking no room when the line doesn't break there.
+pub const WBR: &str = "wbr";
+pub const ATTRIBUTE_KEY_ALT: &str = "alt";
+pub const ATTRIBUTE_KEY_TITLE: &str = "title";
+
+pub const META: &str = "meta";
+pub const ATTRIBUTE_KEY_CHARSET: &str = "charset";
+pub const ATTRIBUTE_KEY_HTTP_EQUIV: &str = "http-equiv";
+pub const ATTRIBUTE_KEY_CONTENT: &str = "content";
+pub const HTTP_EQUIV_CONTENT_TYPE: &str = "content-type";
+
+pub const LIST_ITEM: &str = "li";
+pub const ATTRIBUTE_KEY_START: &str = "start";
+
+pub const TABLE: &str = "table";
+pub const TABLE_ROW: &str = "tr";
+pub const TABLE_DATA: &str = "td";
+pub const TABLE_HEADER: &str = "th";
+pub const TABLE_ROW_GROUPS: [&str; 3] = ["thead", "tbody", "tfoot"];
+pub const TABLE_CAPTION: &str = "caption";
+pub const TABLE_COLUMN_GROUP: &str = "colgroup";
+pub const TABLE_COLUMN: &str = "col";
+
+pub const ATTRIBUTE_KEY_WIDTH: &str = "width";
+pub const ATTRIBUTE_KEY_SPAN: &str = "span";
+pub const ATTRIBUTE_KEY_COLSPAN: &str = "colspan";
+pub const ATTRIBUTE_KEY_ROWSPAN: &str = "rowspan";
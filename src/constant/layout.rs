@@ -5,3 +5,30 @@ pub const VSTEP: f32 = 18.0;
 pub const DEFAULT_X: f32 = DEFAULT_BROWSER_PADDING;
 pub const DEFAULT_Y: f32 = DEFAULT_BROWSER_PADDING;
 pub const DEFAULT_WIDTH: f32 = WIDTH - 2.0 * DEFAULT_BROWSER_PADDING;
+// the widest the content gets in reader mode, centered in the viewport
+pub const READER_MAX_WIDTH: f32 = 720.0;
+// reader mode would change nothing in a viewport narrower than the cap
+const _: () = assert!(DEFAULT_WIDTH > READER_MAX_WIDTH);
+
+pub const INPUT_PADDING: f32 = 2.0;
+pub const INPUT_DEFAULT_SIZE: usize = 20;
+pub const TEXTAREA_DEFAULT_ROWS: usize = 2;
+pub const TEXTAREA_DEFAULT_COLS: usize = 20;
+pub const INPUT_BORDER_COLOR: &str = "gray";
+
+pub const EMBED_DEFAULT_WIDTH: f32 = 300.0;
+pub const EMBED_DEFAULT_HEIGHT: f32 = 150.0;
+pub const EMBED_PLACEHOLDER_COLOR: &str = "lightgray";
+
+pub const TABLE_CELL_SPACING: f32 = 2.0;
+
+pub const LIST_MARKER_GAP: f32 = 6.0;
+
+pub const HR_MARGIN: f32 = 8.0;
+pub const HR_DEFAULT_SIZE: f32 = 2.0;
+
+// ends a line cut short by `text-overflow: ellipsis`
+pub const ELLIPSIS: &str = "\u{2026}";
+
+// the size of small capitals relative to the font's
+pub const SMALL_CAPS_SCALE: f32 = 0.8;
@@ -1,2 +1,47 @@
 pub const HTTP: &str = "http";
-pub const HTTPS: &str = "https";
\ No newline at end of file
+pub const HTTPS: &str = "https";
+/// What a scheme may have after its first letter, besides ASCII letters and digits.
+pub const SCHEME_PUNCTUATION: [char; 3] = ['+', '-', '.'];
+
+pub const HEADER_REFERER: &str = "Referer";
+pub const HEADER_AUTHORIZATION: &str = "Authorization";
+pub const USER_AGENT: &str = "even_browser/0.1";
+pub const ACCEPT_ENCODING: &str = "gzip, deflate";
+pub const CONTENT_ENCODING_GZIP: &str = "gzip";
+pub const CONTENT_ENCODING_DEFLATE: &str = "deflate";
+
+/// `1` logs request and status lines to stderr, `2` their headers too.
+pub const LOG_LEVEL_ENV: &str = "EVEN_BROWSER_LOG";
+pub const LOG_LEVEL_LINES: u8 = 1;
+pub const LOG_LEVEL_HEADERS: u8 = 2;
+pub const METHOD_GET: &str = "GET";
+pub const METHOD_HEAD: &str = "HEAD";
+/// Response headers are keyed in lowercase.
+pub const HEADER_CONTENT_TYPE: &str = "content-type";
+pub const HEADER_CONTENT_ENCODING: &str = "content-encoding";
+pub const HEADER_TRANSFER_ENCODING: &str = "transfer-encoding";
+pub const CONTENT_TYPE_HTML: &str = "text/html";
+pub const CONTENT_TYPE_XHTML: &str = "application/xhtml+xml";
+/// Other text types are shown as is instead of being parsed.
+pub const CONTENT_TYPE_TEXT_PREFIX: &str = "text/";
+/// The `Content-Type` parameter naming the body's encoding.
+pub const CONTENT_TYPE_CHARSET: &str = "charset";
+// `<meta charset>` is only looked for this far into the body
+pub const META_CHARSET_PRESCAN_BYTES: usize = 1024;
+pub const CHARSET_UTF_8_LABELS: [&str; 3] = ["utf-8", "utf8", "unicode-1-1-utf-8"];
+/// Latin-1 and ASCII are decoded as windows-1252, which agrees with them where they're defined.
+pub const CHARSET_WINDOWS_1252_LABELS: [&str; 9] = [
+    "windows-1252",
+    "cp1252",
+    "x-cp1252",
+    "iso-8859-1",
+    "iso8859-1",
+    "latin1",
+    "l1",
+    "us-ascii",
+    "ascii",
+];
+pub const HEADER_LOCATION: &str = "location";
+/// Statuses followed to their `Location`, every one re-requested with `GET`.
+pub const REDIRECT_STATUSES: [u16; 5] = [301, 302, 303, 307, 308];
+pub const MAX_REDIRECTS: usize = 5;
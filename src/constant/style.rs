@@ -1,20 +1,128 @@
 use std::collections::HashMap;
 use std::sync::OnceLock;
 
+pub const STYLE_KEY_BACKGROUND: &str = "background";
 pub const STYLE_KEY_BACKGROUND_COLOR: &str = "background-color";
 pub const BACKGROUND_COLOR_DEFAULT_VALUE: &str = "transparent";
 pub const UNIT_PIXEL: &str = "px";
 pub const STYLE_KEY_FONT_SIZE: &str = "font-size";
 pub const DEFAULT_FONT_SIZE_NUM: i32 = 12;
 pub const DEFAULT_FONT_SIZE: &str = "12px";
+pub const UNIT_EM: &str = "em";
+/// Keyword sizes as multiples of `medium`, which is the default font size.
+pub const FONT_SIZE_KEYWORDS: [(&str, f32); 8] = [
+    ("xx-small", 0.6),
+    ("x-small", 0.75),
+    ("small", 0.89),
+    ("medium", 1.0),
+    ("large", 1.2),
+    ("x-large", 1.5),
+    ("xx-large", 2.0),
+    ("xxx-large", 3.0),
+];
 pub const STYLE_KEY_FONT_STYLE: &str = "font-style";
 pub const DEFAULT_FONT_STYLE: &str = "normal";
 pub const STYLE_KEY_FONT_WEIGHT: &str = "font-weight";
 pub const DEFAULT_FONT_WEIGHT: &str = "normal";
 pub const STYLE_KEY_COLOR: &str = "color";
+pub const STYLE_KEY_TEXT_TRANSFORM: &str = "text-transform";
+pub const DEFAULT_TEXT_TRANSFORM: &str = "none";
+pub const STYLE_KEY_FONT_VARIANT: &str = "font-variant";
+pub const DEFAULT_FONT_VARIANT: &str = "normal";
+pub const FONT_VARIANT_SMALL_CAPS: &str = "small-caps";
+/// Lets a line break between any two characters of a word that doesn't fit.
+pub const STYLE_KEY_WORD_BREAK: &str = "word-break";
+pub const DEFAULT_WORD_BREAK: &str = "normal";
+pub const WORD_BREAK_BREAK_ALL: &str = "break-all";
+pub const STYLE_KEY_LETTER_SPACING: &str = "letter-spacing";
+pub const STYLE_KEY_WORD_SPACING: &str = "word-spacing";
+pub const DEFAULT_SPACING: &str = "normal";
+pub const STYLE_KEY_WIDTH: &str = "width";
+pub const STYLE_KEY_PADDING: &str = "padding";
+pub const STYLE_KEY_BORDER_WIDTH: &str = "border-width";
+pub const STYLE_KEY_MARGIN: &str = "margin";
+pub const STYLE_KEY_MARGIN_TOP: &str = "margin-top";
+pub const STYLE_KEY_MARGIN_RIGHT: &str = "margin-right";
+pub const STYLE_KEY_MARGIN_BOTTOM: &str = "margin-bottom";
+pub const STYLE_KEY_MARGIN_LEFT: &str = "margin-left";
+pub const STYLE_KEY_BORDER_COLOR: &str = "border-color";
+pub const STYLE_KEY_BOX_SIZING: &str = "box-sizing";
+pub const BOX_SIZING_BORDER_BOX: &str = "border-box";
+pub const STYLE_KEY_MIN_WIDTH: &str = "min-width";
+pub const STYLE_KEY_MAX_WIDTH: &str = "max-width";
+pub const STYLE_KEY_HEIGHT: &str = "height";
+pub const STYLE_KEY_MIN_HEIGHT: &str = "min-height";
+pub const STYLE_KEY_MAX_HEIGHT: &str = "max-height";
+/// Only `inline-block` is told apart, every other value keeps the element's default display.
+pub const STYLE_KEY_DISPLAY: &str = "display";
+pub const DISPLAY_INLINE_BLOCK: &str = "inline-block";
+pub const STYLE_KEY_OVERFLOW: &str = "overflow";
+pub const OVERFLOW_HIDDEN: &str = "hidden";
+/// Both clip like `hidden` and let the wheel scroll the content, no scrollbar is drawn.
+pub const OVERFLOW_SCROLLABLE: [&str; 2] = ["scroll", "auto"];
+/// Only has an effect together with `overflow: hidden`.
+pub const STYLE_KEY_TEXT_OVERFLOW: &str = "text-overflow";
+pub const TEXT_OVERFLOW_ELLIPSIS: &str = "ellipsis";
+pub const STYLE_KEY_LIST_STYLE_TYPE: &str = "list-style-type";
+pub const DEFAULT_LIST_STYLE_TYPE: &str = "disc";
+pub const LIST_STYLE_NONE: &str = "none";
+pub const LIST_STYLE_CIRCLE: &str = "circle";
+pub const LIST_STYLE_SQUARE: &str = "square";
+pub const LIST_STYLE_DECIMAL: &str = "decimal";
+/// The markers painted for `disc`, `circle` and `square`, any other type gets a disc.
+pub const LIST_MARKER_DISC: &str = "\u{2022}";
+pub const LIST_MARKER_CIRCLE: &str = "\u{25E6}";
+pub const LIST_MARKER_SQUARE: &str = "\u{25AA}";
+/// What follows the item's number with `decimal`.
+pub const LIST_MARKER_DECIMAL_SUFFIX: char = '.';
+pub const STYLE_KEY_OPACITY: &str = "opacity";
+/// Only `translate()` and uniform `scale()`, about the box's center.
+pub const STYLE_KEY_TRANSFORM: &str = "transform";
+pub const TRANSFORM_TRANSLATE: &str = "translate";
+pub const TRANSFORM_SCALE: &str = "scale";
+pub const STYLE_KEY_WHITE_SPACE: &str = "white-space";
+pub const DEFAULT_WHITE_SPACE: &str = "normal";
+pub const STYLE_KEY_CURSOR: &str = "cursor";
+pub const DEFAULT_CURSOR: &str = "auto";
+pub const CURSOR_POINTER: &str = "pointer";
+pub const STYLE_KEY_VISIBILITY: &str = "visibility";
+pub const DEFAULT_VISIBILITY: &str = "visible";
+pub const VISIBILITY_HIDDEN: &str = "hidden";
+pub const STYLE_KEY_POSITION: &str = "position";
+pub const POSITION_FIXED: &str = "fixed";
+pub const STYLE_KEY_TOP: &str = "top";
+pub const STYLE_KEY_LEFT: &str = "left";
+/// Anything else, like the default `baseline`, sits on the line's baseline.
+pub const STYLE_KEY_VERTICAL_ALIGN: &str = "vertical-align";
+pub const VERTICAL_ALIGN_TOP: &str = "top";
+pub const VERTICAL_ALIGN_MIDDLE: &str = "middle";
+pub const VERTICAL_ALIGN_BOTTOM: &str = "bottom";
+/// The `vertical-align`s relative to the line box rather than the baseline.
+pub const LINE_BOX_ALIGNS: [&str; 3] = [
+    VERTICAL_ALIGN_TOP,
+    VERTICAL_ALIGN_MIDDLE,
+    VERTICAL_ALIGN_BOTTOM,
+];
+
+/// Only quoted strings and the quote keywords are supported, anything else like `none`
+/// generates nothing.
+pub const STYLE_KEY_CONTENT: &str = "content";
+pub const CONTENT_OPEN_QUOTE: &str = "open-quote";
+pub const CONTENT_CLOSE_QUOTE: &str = "close-quote";
+// what the quote keywords generate, nested quotes don't alternate to single marks
+pub const OPEN_QUOTE_MARK: &str = "\u{201C}";
+pub const CLOSE_QUOTE_MARK: &str = "\u{201D}";
 
 pub const DEFAULT_COLOR_STR: &str = "black";
 
+// keywords any property takes, resolved by the style pass, compared lowercased
+pub const KEYWORD_INHERIT: &str = "inherit";
+pub const KEYWORD_INITIAL: &str = "initial";
+pub const KEYWORD_CURRENT_COLOR: &str = "currentcolor";
+
+pub const AT_RULE_IMPORT: &str = "import";
+pub const URL_FUNCTION: &str = "url(";
+
 static INHERITED_PROPERTIES: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
 
 pub fn get_inherited_properties() -> &'static HashMap<&'static str, &'static str> {
@@ -24,6 +132,15 @@ pub fn get_inherited_properties() -> &'static HashMap<&'static str, &'static str
         m.insert(STYLE_KEY_FONT_STYLE, DEFAULT_FONT_STYLE);
         m.insert(STYLE_KEY_FONT_WEIGHT, DEFAULT_FONT_WEIGHT);
         m.insert(STYLE_KEY_COLOR, DEFAULT_COLOR_STR);
+        m.insert(STYLE_KEY_TEXT_TRANSFORM, DEFAULT_TEXT_TRANSFORM);
+        m.insert(STYLE_KEY_FONT_VARIANT, DEFAULT_FONT_VARIANT);
+        m.insert(STYLE_KEY_WORD_BREAK, DEFAULT_WORD_BREAK);
+        m.insert(STYLE_KEY_LETTER_SPACING, DEFAULT_SPACING);
+        m.insert(STYLE_KEY_WORD_SPACING, DEFAULT_SPACING);
+        m.insert(STYLE_KEY_VISIBILITY, DEFAULT_VISIBILITY);
+        m.insert(STYLE_KEY_LIST_STYLE_TYPE, DEFAULT_LIST_STYLE_TYPE);
+        m.insert(STYLE_KEY_CURSOR, DEFAULT_CURSOR);
+        m.insert(STYLE_KEY_WHITE_SPACE, DEFAULT_WHITE_SPACE);
         m
     })
 }
@@ -1,5 +1,5 @@
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub(crate) struct Attribute {
-    pub(crate) name: String,
-    pub(crate) value: String,
+pub struct Attribute {
+    pub name: String,
+    pub value: String,
 }
@@ -0,0 +1,31 @@
+/// https://developer.mozilla.org/en-US/docs/Web/API/DocumentType
+#[derive(Debug)]
+pub(crate) struct DocumentType {
+    name: String,
+    /// https://developer.mozilla.org/en-US/docs/Web/API/DocumentType/publicId
+    public_id: String,
+    /// https://developer.mozilla.org/en-US/docs/Web/API/DocumentType/systemId
+    system_id: String,
+}
+
+impl DocumentType {
+    pub(crate) fn new(name: &str, public_id: &str, system_id: &str) -> DocumentType {
+        DocumentType {
+            name: name.to_string(),
+            public_id: public_id.to_string(),
+            system_id: system_id.to_string(),
+        }
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn public_id(&self) -> &str {
+        &self.public_id
+    }
+
+    pub(crate) fn system_id(&self) -> &str {
+        &self.system_id
+    }
+}
@@ -19,4 +19,8 @@ impl Element {
     pub(crate) fn tag_name(&self) -> &str {
         &self.tag_name
     }
+
+    pub(crate) fn attributes(&self) -> &NamedNodeMap {
+        &self.attributes
+    }
 }
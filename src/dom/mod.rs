@@ -2,6 +2,7 @@ pub(crate) mod attribute;
 mod character_data;
 mod comment;
 mod document;
+mod document_type;
 mod element;
 pub(crate) mod named_node_map;
 pub(crate) mod node;
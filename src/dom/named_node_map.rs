@@ -11,6 +11,10 @@ impl NamedNodeMap {
         NamedNodeMap { data: attributes }
     }
 
+    pub(crate) fn iter(&self) -> std::slice::Iter<'_, Attribute> {
+        self.data.iter()
+    }
+
     pub(crate) fn set(&mut self, name: &str, value: &str) {
         self.data.push(Attribute {
             name: name.to_string(),
@@ -1,8 +1,8 @@
 use std::ptr::{self, NonNull};
 
 use crate::dom::{
-    comment::Comment, document::Document, element::Element, named_node_map::NamedNodeMap,
-    text::Text,
+    comment::Comment, document::Document, document_type::DocumentType, element::Element,
+    named_node_map::NamedNodeMap, text::Text,
 };
 
 pub(crate) type NodePtr = ptr::NonNull<Node>;
@@ -11,6 +11,7 @@ pub(crate) type NodeBox = Box<Node>;
 #[derive(Debug)]
 pub(crate) enum NodeSubtype {
     Document(Document),
+    DocumentType(DocumentType),
     Element(Element),
     Text(Text),
     Comment(Comment),
@@ -33,6 +34,19 @@ impl Node {
         })
     }
 
+    pub(crate) fn new_doc_type(
+        parent: Option<NodePtr>,
+        name: &str,
+        public_id: &str,
+        system_id: &str,
+    ) -> NodeBox {
+        Box::new(Node {
+            parent,
+            children: Vec::new(),
+            subtype: NodeSubtype::DocumentType(DocumentType::new(name, public_id, system_id)),
+        })
+    }
+
     pub(crate) fn new_element(
         parent: Option<NodePtr>,
         tag_name: &str,
@@ -120,6 +134,13 @@ impl std::fmt::Display for Node {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.subtype {
             NodeSubtype::Document(_) => write!(f, "#document"),
+            NodeSubtype::DocumentType(d) => write!(
+                f,
+                "#doctype: {} public={:?} system={:?}",
+                d.name(),
+                d.public_id(),
+                d.system_id()
+            ),
             NodeSubtype::Element(e) => write!(f, "#{}", e.tag_name()),
             NodeSubtype::Text(t) => write!(f, "#text: {}", t.data()),
             NodeSubtype::Comment(c) => write!(f, "#comment: {}", c.data()),
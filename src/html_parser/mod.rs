@@ -1,2 +1,2 @@
 pub(crate) mod parser;
-mod tokenizer;
+pub mod tokenizer;
@@ -6,7 +6,7 @@ use crate::{
         named_node_map::NamedNodeMap,
         node::{Node, NodeBox, NodePtr, NodeSubtype},
     },
-    html_parser::tokenizer::{Tag, TagKind, Token, Tokenizer},
+    html_parser::tokenizer::{Doctype, Tag, TagKind, Token, Tokenizer},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -15,6 +15,7 @@ enum InsertionMode {
     BeforeHtml,
     BeforeHead,
     InHead,
+    InHeadNoscript,
     AfterHead,
     InBody,
     AfterBody,
@@ -42,10 +43,12 @@ pub(crate) struct HtmlParser {
     document: NodeBox,
     mode: Cell<InsertionMode>,
     original_mode: Cell<InsertionMode>,
+    // the mode to return to once each open template ends
+    template_modes: Vec<InsertionMode>,
 }
 
 impl HtmlParser {
-    fn new(html: &str) -> HtmlParser {
+    pub(crate) fn new(html: &str) -> HtmlParser {
         let mut document = Node::new_document();
         let open_elements = unsafe { vec![NonNull::new_unchecked(&mut *document)] };
 
@@ -55,10 +58,11 @@ impl HtmlParser {
             document,
             mode: Cell::new(InsertionMode::Initial),
             original_mode: Cell::new(InsertionMode::Initial),
+            template_modes: Vec::new(),
         }
     }
 
-    fn parse(mut self) -> Box<Node> {
+    pub(crate) fn parse(mut self) -> Box<Node> {
         loop {
             let token = self.tokenizer.next();
 
@@ -114,7 +118,15 @@ impl HtmlParser {
 
                     return StepResult::Consumed(None);
                 }
-                // TODO: A DOCTYPE token
+                // A DOCTYPE token
+                Token::Doctype(doctype) => {
+                    // TODO: If the DOCTYPE token's name is not "html", or the token's public identifier is not missing, or the token's system identifier is neither missing nor "about:legacy-compat", then there is a parse error.
+                    // Append a DocumentType node to the Document node, with its name set to the name given in the DOCTYPE token, or the empty string if the name was missing; its public ID set to the public identifier given in the DOCTYPE token, or the empty string if the public identifier was missing; and its system ID set to the system identifier given in the DOCTYPE token, or the empty string if the system identifier was missing.
+                    self.insert_doc_type(&doctype);
+                    // TODO: Then, if the document is not an iframe srcdoc document, and the parser cannot change the mode flag is false, and the DOCTYPE token matches one of the conditions in the following list, then set the Document to quirks mode.
+                    // Then, switch the insertion mode to "before html".
+                    return StepResult::Consumed(Some(InsertionMode::BeforeHtml));
+                }
                 _ => {
                     // If the document is not an iframe srcdoc document, then this is a parse error; if the parser cannot change the mode flag is false, set the Document to quirks mode.
                     // In any case, switch the insertion mode to "before html", then reprocess the token.
@@ -123,7 +135,11 @@ impl HtmlParser {
             },
             // https://html.spec.whatwg.org/multipage/parsing.html#the-before-html-insertion-mode
             InsertionMode::BeforeHtml => match token {
-                // TODO: A DOCTYPE token - Parse error. Ignore the token.
+                // A DOCTYPE token
+                Token::Doctype(_) => {
+                    // Parse error. Ignore the token.
+                    return StepResult::Ignored;
+                }
                 Token::Comment(c) => {
                     // Insert a comment as the last child of the Document object.
                     self.insert_comment_into_document(&c);
@@ -191,7 +207,11 @@ impl HtmlParser {
 
                     return StepResult::Consumed(None);
                 }
-                // TODO: A DOCTYPE token - Parse error. Ignore the token.
+                // A DOCTYPE token
+                Token::Doctype(_) => {
+                    // Parse error. Ignore the token.
+                    return StepResult::Ignored;
+                }
                 Token::Tag(tag) => match &tag.kind {
                     TagKind::StartTag => match tag.name.as_str() {
                         // A start tag whose tag name is "html"
@@ -258,7 +278,11 @@ impl HtmlParser {
 
                     return StepResult::Consumed(None);
                 }
-                // TODO: A DOCTYPE token - Parse error. Ignore the token.
+                // A DOCTYPE token
+                Token::Doctype(_) => {
+                    // Parse error. Ignore the token.
+                    return StepResult::Ignored;
+                }
                 Token::Tag(tag) => match &tag.kind {
                     TagKind::StartTag => match tag.name.as_str() {
                         // A start tag whose tag name is "html"
@@ -289,18 +313,36 @@ impl HtmlParser {
                             self.original_mode.set(InsertionMode::InHead);
                             return StepResult::Consumed(Some(InsertionMode::Text));
                         }
-                        // A start tag whose tag name is "noscript", if the scripting flag is enabled
                         // A start tag whose tag name is one of: "noframes", "style"
-                        "noscript" | "noframes" | "style" => {
+                        "noframes" | "style" => {
                             // Follow the generic raw text element parsing algorithm.
                             self.insert_html_element(&tag.name, tag.attributes, false);
                             // TODO: https://html.spec.whatwg.org/multipage/parsing.html#generic-raw-text-element-parsing-algorithm
-                            self.original_mode.set(InsertionMode::InHead);
+                            // The current mode, which is "in head noscript" for a style inside a noscript.
+                            self.original_mode.set(self.mode.get());
                             return StepResult::Consumed(Some(InsertionMode::Text));
                         }
-                        // TODO: A start tag whose tag name is "noscript", if the scripting flag is disabled
+                        // A start tag whose tag name is "noscript", if the scripting flag is disabled
+                        // Scripts never run here, so the scripting flag is always disabled.
+                        "noscript" => {
+                            // Insert an HTML element for the token.
+                            // Switch the insertion mode to "in head noscript".
+                            self.insert_html_element(&tag.name, tag.attributes, false);
+                            return StepResult::Consumed(Some(InsertionMode::InHeadNoscript));
+                        }
                         // TODO: A start tag whose tag name is "script"
-                        // TODO: A start tag whose tag name is "template"
+                        // A start tag whose tag name is "template"
+                        "template" => {
+                            // Insert an HTML element for the token.
+                            // TODO: Insert a marker at the end of the list of active formatting elements.
+                            // TODO: Set the frameset-ok flag to "not ok".
+                            // Switch the insertion mode to "in template".
+                            // Push "in template" onto the stack of template insertion modes.
+                            // There's no "in template" mode, the content is parsed with the "in body" rules it mostly defers to, and the mode to return to is kept instead.
+                            self.insert_html_element(&tag.name, tag.attributes, false);
+                            self.template_modes.push(self.mode.get());
+                            return StepResult::Consumed(Some(InsertionMode::InBody));
+                        }
                         // A start tag whose tag name is "head"
                         "head" => {
                             // Parse error. Ignore the token.
@@ -328,7 +370,22 @@ impl HtmlParser {
                             self.open_elements.pop();
                             return StepResult::Reprocess(InsertionMode::AfterHead, Token::Tag(tag));
                         }
-                        // TODO: An end tag whose tag name is "template"
+                        // An end tag whose tag name is "template"
+                        "template" => {
+                            // If there is no template element on the stack of open elements, then this is a parse error; ignore the token.
+                            let Some(position) = self.open_elements.iter().rposition(|node_ptr| {
+                                matches!(unsafe { node_ptr.as_ref() }.get_element(), Some(e) if e.tag_name() == "template")
+                            }) else {
+                                return StepResult::Ignored;
+                            };
+                            // TODO: Generate all implied end tags thoroughly.
+                            // Pop elements from the stack of open elements until a template element has been popped from the stack.
+                            self.open_elements.truncate(position);
+                            // TODO: Clear the list of active formatting elements up to the last marker.
+                            // Pop the current template insertion mode off the stack of template insertion modes.
+                            // Reset the insertion mode appropriately.
+                            return StepResult::Consumed(self.template_modes.pop());
+                        }
                         // Any other end tag
                         _ => {
                             // Parse error. Ignore the token.
@@ -344,6 +401,80 @@ impl HtmlParser {
                     return StepResult::Reprocess(InsertionMode::AfterHead, token);
                 }
             },
+            // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inheadnoscript
+            InsertionMode::InHeadNoscript => match token {
+                // A character token that is one of U+0009 CHARACTER TABULATION, U+000A LINE FEED (LF), U+000C FORM FEED (FF), U+000D CARRIAGE RETURN (CR), or U+0020 SPACE
+                // A comment token
+                Token::Character('\t')
+                | Token::Character('\n')
+                | Token::Character('\x0C')
+                | Token::Character('\r')
+                | Token::Character(' ')
+                | Token::Comment(_) => {
+                    // Process the token using the rules for the "in head" insertion mode.
+                    return self.step(InsertionMode::InHead, token);
+                }
+                // A DOCTYPE token
+                Token::Doctype(_) => {
+                    // Parse error. Ignore the token.
+                    return StepResult::Ignored;
+                }
+                Token::Tag(tag) => match &tag.kind {
+                    TagKind::StartTag => match tag.name.as_str() {
+                        // A start tag whose tag name is "html"
+                        "html" => {
+                            // Process the token using the rules for the "in body" insertion mode.
+                            return self.step(InsertionMode::InBody, Token::Tag(tag));
+                        }
+                        // A start tag whose tag name is one of: "basefont", "bgsound", "link", "meta", "noframes", "style"
+                        "basefont" | "bgsound" | "link" | "meta" | "noframes" | "style" => {
+                            // Process the token using the rules for the "in head" insertion mode.
+                            return self.step(InsertionMode::InHead, Token::Tag(tag));
+                        }
+                        // A start tag whose tag name is one of: "head", "noscript"
+                        "head" | "noscript" => {
+                            // Parse error. Ignore the token.
+                            return StepResult::Ignored;
+                        }
+                        _ => {
+                            // Parse error.
+                            // Pop the current node (which will be a noscript element) from the stack of open elements; the new current node will be a head element.
+                            // Switch the insertion mode to "in head".
+                            // Reprocess the token.
+                            self.open_elements.pop();
+                            return StepResult::Reprocess(InsertionMode::InHead, Token::Tag(tag));
+                        }
+                    },
+                    TagKind::EndTag => match tag.name.as_str() {
+                        // An end tag whose tag name is "noscript"
+                        "noscript" => {
+                            // Pop the current node (which will be a noscript element) from the stack of open elements; the new current node will be a head element.
+                            // Switch the insertion mode to "in head".
+                            self.open_elements.pop();
+                            return StepResult::Consumed(Some(InsertionMode::InHead));
+                        }
+                        // An end tag whose tag name is "br"
+                        "br" => {
+                            // Act as described in the "anything else" entry below.
+                            self.open_elements.pop();
+                            return StepResult::Reprocess(InsertionMode::InHead, Token::Tag(tag));
+                        }
+                        // Any other end tag
+                        _ => {
+                            // Parse error. Ignore the token.
+                            return StepResult::Ignored;
+                        }
+                    },
+                },
+                _ => {
+                    // Parse error.
+                    // Pop the current node (which will be a noscript element) from the stack of open elements; the new current node will be a head element.
+                    // Switch the insertion mode to "in head".
+                    // Reprocess the token.
+                    self.open_elements.pop();
+                    return StepResult::Reprocess(InsertionMode::InHead, token);
+                }
+            },
             // https://html.spec.whatwg.org/multipage/parsing.html#the-after-head-insertion-mode
             InsertionMode::AfterHead => match token {
                 // A character token that is one of U+0009 CHARACTER TABULATION, U+000A LINE FEED (LF), U+000C FORM FEED (FF), U+000D CARRIAGE RETURN (CR), or U+0020 SPACE
@@ -364,7 +495,11 @@ impl HtmlParser {
 
                     return StepResult::Consumed(None);
                 }
-                // TODO: A DOCTYPE token - Parse error. Ignore the token.
+                // A DOCTYPE token
+                Token::Doctype(_) => {
+                    // Parse error. Ignore the token.
+                    return StepResult::Ignored;
+                }
                 Token::Tag(tag) => match &tag.kind {
                     TagKind::StartTag => match tag.name.as_str() {
                         // A start tag whose tag name is "html"
@@ -447,7 +582,11 @@ impl HtmlParser {
 
                     return StepResult::Consumed(None);
                 }
-                // TODO: A DOCTYPE token - Parse error. Ignore the token.
+                // A DOCTYPE token
+                Token::Doctype(_) => {
+                    // Parse error. Ignore the token.
+                    return StepResult::Ignored;
+                }
                 Token::Tag(tag) => match &tag.kind {
                     TagKind::StartTag => match tag.name.as_str() {
                         // A start tag whose tag name is "html"
@@ -489,7 +628,8 @@ impl HtmlParser {
                         // TODO: A start tag whose tag name is "xmp"
                         // TODO: A start tag whose tag name is "iframe"
                         // TODO: A start tag whose tag name is "noembed"
-                        // TODO: A start tag whose tag name is "noscrA start tag whose tag name is "noscript", if the scripting flag is enabledipt", if the scripting flag is enabled
+                        // A start tag whose tag name is "noscript", if the scripting flag is enabled
+                        // The scripting flag is always disabled, so noscript is an ordinary element below.
                         // TODO: A start tag whose tag name is "select"
                         // TODO: A start tag whose tag name is "option"
                         // TODO: A start tag whose tag name is "optgroup"
@@ -508,7 +648,11 @@ impl HtmlParser {
                         }
                     },
                     TagKind::EndTag => match tag.name.as_str() {
-                        // TODO: An end tag whose tag name is "template" - Process the token using the rules for the "in head" insertion mode.
+                        // An end tag whose tag name is "template"
+                        "template" => {
+                            // Process the token using the rules for the "in head" insertion mode.
+                            return self.step(InsertionMode::InHead, Token::Tag(tag));
+                        }
                         // An end tag whose tag name is "body"
                         "body" => {
                             // TODO: If the stack of open elements does not have a body element in scope, this is a parse error; ignore the token.
@@ -618,7 +762,11 @@ impl HtmlParser {
                     self.insert_comment(&c);
                     return StepResult::Consumed(None);
                 }
-                // TODO: A DOCTYPE token - Parse error. Ignore the token.
+                // A DOCTYPE token
+                Token::Doctype(_) => {
+                    // Parse error. Ignore the token.
+                    return StepResult::Ignored;
+                }
                 Token::Tag(tag) => match &tag.kind {
                     TagKind::StartTag => match tag.name.as_str() {
                         // A start tag whose tag name is "html"
@@ -661,13 +809,14 @@ impl HtmlParser {
                     self.insert_comment_into_document(&c);
                     return StepResult::Consumed(None);
                 }
-                // TODO: A DOCTYPE token
+                // A DOCTYPE token
                 // A character token that is one of U+0009 CHARACTER TABULATION, U+000A LINE FEED (LF), U+000C FORM FEED (FF), U+000D CARRIAGE RETURN (CR), or U+0020 SPACE
                 Token::Character('\t')
                 | Token::Character('\n')
                 | Token::Character('\x0C')
                 | Token::Character('\r')
-                | Token::Character(' ') => {
+                | Token::Character(' ')
+                | Token::Doctype(_) => {
                     // Process the token using the rules for the "in body" insertion mode.
                     return self.step(InsertionMode::InBody, token);
                 }
@@ -772,6 +921,17 @@ impl HtmlParser {
         insertion_location.append_child(comment);
     }
 
+    fn insert_doc_type(&mut self, doctype: &Doctype) {
+        let insertion_location = self.document.as_mut();
+        let doc_type = Node::new_doc_type(
+            Some(insertion_location.get_ptr()),
+            doctype.name.as_deref().unwrap_or_default(),
+            doctype.public_id.as_deref().unwrap_or_default(),
+            doctype.system_id.as_deref().unwrap_or_default(),
+        );
+        insertion_location.append_child(doc_type);
+    }
+
     /// https://html.spec.whatwg.org/multipage/parsing.html#insert-a-comment
     fn insert_comment(&mut self, data: &str) {
         let insertion_location = self.appropriate_place_for_insertion();
@@ -869,6 +1029,47 @@ mod tests {
         assert_eq!(text.get_text().unwrap().data(), "a b");
     }
 
+    #[test]
+    fn test_noscript_in_head() {
+        let parser = HtmlParser::new("<head><noscript><link rel=stylesheet href=a.css></noscript><noscript><p>No JS</p></noscript></head>");
+        let document = parser.parse();
+        print_node_tree(&document, 0);
+
+        let html = document.child_nodes().iter().find(|n| matches!(n.get_element(), Some(e) if e.tag_name() == "html")).expect("Missing html");
+        let head = html.child_nodes().iter().find(|n| matches!(n.get_element(), Some(e) if e.tag_name() == "head")).expect("Missing head");
+        let body = html.child_nodes().iter().find(|n| matches!(n.get_element(), Some(e) if e.tag_name() == "body")).expect("Missing body");
+
+        // head content stays inside the noscript
+        let noscript = &head.child_nodes()[0];
+        assert_eq!(noscript.get_element().unwrap().tag_name(), "noscript");
+        assert_eq!(noscript.child_nodes()[0].get_element().unwrap().tag_name(), "link");
+
+        // anything else closes the noscript and the head, and is parsed as body content
+        let p = body.child_nodes().iter().find(|n| matches!(n.get_element(), Some(e) if e.tag_name() == "p")).expect("Missing p");
+        assert_eq!(p.child_nodes()[0].get_text().unwrap().data(), "No JS");
+    }
+
+    #[test]
+    fn test_template_content() {
+        let parser = HtmlParser::new("<head><template><p>x</p></template><title>t</title></head><body><template><div>y</div></template><p>z</p></body>");
+        let document = parser.parse();
+        print_node_tree(&document, 0);
+
+        let html = document.child_nodes().iter().find(|n| matches!(n.get_element(), Some(e) if e.tag_name() == "html")).expect("Missing html");
+        let head = html.child_nodes().iter().find(|n| matches!(n.get_element(), Some(e) if e.tag_name() == "head")).expect("Missing head");
+        let body = html.child_nodes().iter().find(|n| matches!(n.get_element(), Some(e) if e.tag_name() == "body")).expect("Missing body");
+
+        // the content stays in the template and the head goes on after it
+        let template = &head.child_nodes()[0];
+        assert_eq!(template.get_element().unwrap().tag_name(), "template");
+        assert_eq!(template.child_nodes()[0].get_element().unwrap().tag_name(), "p");
+        assert_eq!(head.child_nodes()[1].get_element().unwrap().tag_name(), "title");
+
+        let names: Vec<&str> = body.child_nodes().iter().filter_map(|n| n.get_element()).map(|e| e.tag_name()).collect();
+        assert_eq!(names, vec!["template", "p"]);
+        assert_eq!(body.child_nodes()[0].child_nodes()[0].get_element().unwrap().tag_name(), "div");
+    }
+
     #[test]
     fn test_comments() {
         let parser = HtmlParser::new("<!-- doc comment --><html><!-- head comment --><head></head><body></body></html><!-- after comment -->");
@@ -888,4 +1089,26 @@ mod tests {
             _ => false,
         }), "Missing head comment");
     }
+
+    #[test]
+    fn test_legacy_doctype() {
+        let parser = HtmlParser::new("<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Transitional//EN\"\n  'http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd'><html><!DOCTYPE html><p>x</p></html>");
+        let document = parser.parse();
+        print_node_tree(&document, 0);
+
+        let doc_types: Vec<_> = document.child_nodes().iter().filter_map(|n| match n.subtype() {
+            NodeSubtype::DocumentType(d) => Some(d),
+            _ => None,
+        }).collect();
+        // the second doctype is a parse error and dropped
+        assert_eq!(doc_types.len(), 1);
+        assert_eq!(doc_types[0].name(), "html");
+        assert_eq!(doc_types[0].public_id(), "-//W3C//DTD XHTML 1.0 Transitional//EN");
+        assert_eq!(doc_types[0].system_id(), "http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd");
+
+        // it doesn't end up in the tree as text or an element
+        let html = document.child_nodes().iter().find(|n| matches!(n.get_element(), Some(e) if e.tag_name() == "html")).expect("Missing html");
+        let body = html.child_nodes().iter().find(|n| matches!(n.get_element(), Some(e) if e.tag_name() == "body")).expect("Missing body");
+        assert_eq!(body.child_nodes().len(), 1);
+    }
 }
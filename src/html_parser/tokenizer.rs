@@ -5,22 +5,32 @@ use std::ops::DerefMut;
 use crate::dom::attribute::Attribute;
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
-pub(crate) enum TagKind {
+pub enum TagKind {
     #[default]
     StartTag,
     EndTag,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub(crate) struct Tag {
-    pub(crate) kind: TagKind,
-    pub(crate) name: String,
-    pub(crate) self_closing: bool,
-    pub(crate) attributes: Vec<Attribute>,
+pub struct Tag {
+    pub kind: TagKind,
+    pub name: String,
+    pub self_closing: bool,
+    pub attributes: Vec<Attribute>,
+}
+
+/// Missing identifiers are `None`, which isn't the same as an empty quoted one. The
+/// force-quirks flag isn't kept, as nothing is laid out differently in quirks mode.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Doctype {
+    pub name: Option<String>,
+    pub public_id: Option<String>,
+    pub system_id: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) enum Token {
+pub enum Token {
+    Doctype(Doctype),
     Tag(Tag),
     Character(char),
     Comment(String),
@@ -30,6 +40,11 @@ pub(crate) enum Token {
 impl std::fmt::Display for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Token::Doctype(doctype) => write!(
+                f,
+                "doctype name={:?} public_id={:?} system_id={:?}",
+                doctype.name, doctype.public_id, doctype.system_id
+            ),
             Token::Tag(tag) => write!(
                 f,
                 "tag name={} kind={:#?} self_closing={} attributes={:?}",
@@ -49,6 +64,12 @@ enum AttrValueKind {
     DoubleQuoted,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum DoctypeIdKind {
+    Public,
+    System,
+}
+
 #[derive(Debug, Copy, Clone)]
 enum State {
     Data,
@@ -67,6 +88,18 @@ enum State {
     Comment,
     CommentEnd,
     BogusComment,
+    Doctype,
+    BeforeDoctypeName,
+    DoctypeName,
+    AfterDoctypeName,
+    AfterDoctypeKeyword(DoctypeIdKind),
+    BeforeDoctypeIdentifier(DoctypeIdKind),
+    // the quote that closes the identifier
+    DoctypeIdentifier(DoctypeIdKind, char),
+    AfterDoctypePublicIdentifier,
+    BetweenDoctypePublicAndSystemIdentifiers,
+    AfterDoctypeSystemIdentifier,
+    BogusDoctype,
 }
 
 pub(crate) struct Tokenizer {
@@ -77,6 +110,7 @@ pub(crate) struct Tokenizer {
     pending_tokens: RefCell<Vec<Token>>,
     cur_tag: RefCell<Tag>,
     cur_comment: RefCell<String>,
+    cur_doctype: RefCell<Doctype>,
 }
 
 impl Tokenizer {
@@ -89,6 +123,7 @@ impl Tokenizer {
             pending_tokens: RefCell::new(Vec::new()),
             cur_tag: RefCell::new(Tag::default()),
             cur_comment: RefCell::new(String::new()),
+            cur_doctype: RefCell::new(Doctype::default()),
         }
     }
 
@@ -439,6 +474,8 @@ impl Tokenizer {
                             // Treat it as per the "anything else" entry below.
                             self.cur_tag.borrow_mut().attributes.last_mut().expect("[State::AttributeValue(AttrValueKind::Unquoted)] last attribute is invalid").value.push(ch);
                         }
+                        // A '/' is part of an unquoted value, so `href=/a/b` keeps its slashes.
+                        // A tag only self-closes on `/>` after whitespace or a quoted value.
                         _ => {
                             // Append the current input character to the current attribute's value.
                             self.cur_tag.borrow_mut().attributes.last_mut().expect("[State::AttributeValue(AttrValueKind::Unquoted)] last attribute is invalid").value.push(ch);
@@ -522,8 +559,12 @@ impl Tokenizer {
                 },
                 // https://html.spec.whatwg.org/multipage/parsing.html#markup-declaration-open-state
                 State::MarkupDeclarationOpen => match c {
+                    Some(_) if self.consume_keyword("DOCTYPE") => {
+                        // ASCII case-insensitive match for the word "DOCTYPE" - Consume those characters and switch to the DOCTYPE state.
+                        self.state.set(State::Doctype);
+                    }
                     Some(_) => {
-                        // TODO: 1. Two U+002D HYPHEN-MINUS characters (-) - Consume those two characters, create a comment token whose data is the empty string, and switch to the comment start state. 2. ASCII case-insensitive match for the word "DOCTYPE" - Consume those characters and switch to the DOCTYPE state. 3. The string "[CDATA[" (the five uppercase letters "CDATA" with a U+005B LEFT SQUARE BRACKET character before and after) - Consume those characters. If there is an adjusted current node and it is not an element in the HTML namespace, then switch to the CDATA section state. Otherwise, this is a cdata-in-html-content parse error. Create a comment token whose data is the "[CDATA[" string. Switch to the bogus comment state.
+                        // TODO: 1. Two U+002D HYPHEN-MINUS characters (-) - Consume those two characters, create a comment token whose data is the empty string, and switch to the comment start state. 2. The string "[CDATA[" (the five uppercase letters "CDATA" with a U+005B LEFT SQUARE BRACKET character before and after) - Consume those characters. If there is an adjusted current node and it is not an element in the HTML namespace, then switch to the CDATA section state. Otherwise, this is a cdata-in-html-content parse error. Create a comment token whose data is the "[CDATA[" string. Switch to the bogus comment state.
                         self.create_comment();
                         self.reconsume.set(true);
                         self.state.set(State::CommentStart);
@@ -600,10 +641,304 @@ impl Tokenizer {
                         return self.emit_comment();
                     }
                 },
+                // https://html.spec.whatwg.org/multipage/parsing.html#doctype-state
+                State::Doctype => match c {
+                    Some('\t' | '\n' | '\x0C' | ' ') => {
+                        // Switch to the before DOCTYPE name state.
+                        self.state.set(State::BeforeDoctypeName);
+                    }
+                    Some(_) => {
+                        // '>' - Reconsume in the before DOCTYPE name state.
+                        // Anything else - This is a missing-whitespace-before-doctype-name parse error. Reconsume in the before DOCTYPE name state.
+                        self.reconsume.set(true);
+                        self.state.set(State::BeforeDoctypeName);
+                    }
+                    None => {
+                        // This is an eof-in-doctype parse error. Create a new DOCTYPE token. Emit the current token. Emit an end-of-file token.
+                        self.create_doctype();
+                        return self.emit_doctype_then_eof();
+                    }
+                },
+                // https://html.spec.whatwg.org/multipage/parsing.html#before-doctype-name-state
+                State::BeforeDoctypeName => match c {
+                    Some(ch) => match ch {
+                        '\t' | '\n' | '\x0C' | ' ' => {
+                            // Ignore the character.
+                        }
+                        '>' => {
+                            // This is a missing-doctype-name parse error. Create a new DOCTYPE token. Switch to the data state. Emit the current token.
+                            self.create_doctype();
+                            self.state.set(State::Data);
+                            return self.emit_doctype();
+                        }
+                        // TODO: U+0000 NULL - This is an unexpected-null-character parse error. Create a new DOCTYPE token. Set the token's name to a U+FFFD REPLACEMENT CHARACTER character. Switch to the DOCTYPE name state.
+                        _ => {
+                            // Create a new DOCTYPE token. Set the token's name to the current input character, lowercased. Switch to the DOCTYPE name state.
+                            self.create_doctype();
+                            self.cur_doctype.borrow_mut().name =
+                                Some(ch.to_ascii_lowercase().to_string());
+                            self.state.set(State::DoctypeName);
+                        }
+                    },
+                    None => {
+                        // This is an eof-in-doctype parse error. Create a new DOCTYPE token. Emit the current token. Emit an end-of-file token.
+                        self.create_doctype();
+                        return self.emit_doctype_then_eof();
+                    }
+                },
+                // https://html.spec.whatwg.org/multipage/parsing.html#doctype-name-state
+                State::DoctypeName => match c {
+                    Some(ch) => match ch {
+                        '\t' | '\n' | '\x0C' | ' ' => {
+                            // Switch to the after DOCTYPE name state.
+                            self.state.set(State::AfterDoctypeName);
+                        }
+                        '>' => {
+                            // Switch to the data state. Emit the current DOCTYPE token.
+                            self.state.set(State::Data);
+                            return self.emit_doctype();
+                        }
+                        _ => {
+                            // Append the current input character, lowercased, to the current DOCTYPE token's name.
+                            if let Some(name) = self.cur_doctype.borrow_mut().name.as_mut() {
+                                name.push(ch.to_ascii_lowercase());
+                            }
+                        }
+                    },
+                    None => {
+                        // This is an eof-in-doctype parse error. Emit the current DOCTYPE token. Emit an end-of-file token.
+                        return self.emit_doctype_then_eof();
+                    }
+                },
+                // https://html.spec.whatwg.org/multipage/parsing.html#after-doctype-name-state
+                State::AfterDoctypeName => match c {
+                    Some(ch) => match ch {
+                        '\t' | '\n' | '\x0C' | ' ' => {
+                            // Ignore the character.
+                        }
+                        '>' => {
+                            // Switch to the data state. Emit the current DOCTYPE token.
+                            self.state.set(State::Data);
+                            return self.emit_doctype();
+                        }
+                        _ if self.consume_keyword("PUBLIC") => {
+                            // Consume those characters and switch to the after DOCTYPE public keyword state.
+                            self.state
+                                .set(State::AfterDoctypeKeyword(DoctypeIdKind::Public));
+                        }
+                        _ if self.consume_keyword("SYSTEM") => {
+                            // Consume those characters and switch to the after DOCTYPE system keyword state.
+                            self.state
+                                .set(State::AfterDoctypeKeyword(DoctypeIdKind::System));
+                        }
+                        _ => {
+                            // This is an invalid-character-sequence-after-doctype-name parse error. Reconsume in the bogus DOCTYPE state.
+                            self.reconsume.set(true);
+                            self.state.set(State::BogusDoctype);
+                        }
+                    },
+                    None => {
+                        // This is an eof-in-doctype parse error. Emit the current DOCTYPE token. Emit an end-of-file token.
+                        return self.emit_doctype_then_eof();
+                    }
+                },
+                // https://html.spec.whatwg.org/multipage/parsing.html#after-doctype-public-keyword-state
+                // https://html.spec.whatwg.org/multipage/parsing.html#after-doctype-system-keyword-state
+                State::AfterDoctypeKeyword(kind) => match c {
+                    Some(ch) => match ch {
+                        '\t' | '\n' | '\x0C' | ' ' => {
+                            // Switch to the before DOCTYPE public/system identifier state.
+                            self.state.set(State::BeforeDoctypeIdentifier(kind));
+                        }
+                        '"' | '\'' => {
+                            // This is a missing-whitespace-after-doctype-public/system-keyword parse error. Set the identifier to the empty string, switch to the quoted identifier state.
+                            self.start_doctype_id(kind);
+                            self.state.set(State::DoctypeIdentifier(kind, ch));
+                        }
+                        '>' => {
+                            // This is a missing-doctype-public/system-identifier parse error. Switch to the data state. Emit the current DOCTYPE token.
+                            self.state.set(State::Data);
+                            return self.emit_doctype();
+                        }
+                        _ => {
+                            // This is a missing-quote-before-doctype-public/system-identifier parse error. Reconsume in the bogus DOCTYPE state.
+                            self.reconsume.set(true);
+                            self.state.set(State::BogusDoctype);
+                        }
+                    },
+                    None => {
+                        // This is an eof-in-doctype parse error. Emit the current DOCTYPE token. Emit an end-of-file token.
+                        return self.emit_doctype_then_eof();
+                    }
+                },
+                // https://html.spec.whatwg.org/multipage/parsing.html#before-doctype-public-identifier-state
+                // https://html.spec.whatwg.org/multipage/parsing.html#before-doctype-system-identifier-state
+                State::BeforeDoctypeIdentifier(kind) => match c {
+                    Some(ch) => match ch {
+                        '\t' | '\n' | '\x0C' | ' ' => {
+                            // Ignore the character.
+                        }
+                        '"' | '\'' => {
+                            // Set the identifier to the empty string, switch to the quoted identifier state.
+                            self.start_doctype_id(kind);
+                            self.state.set(State::DoctypeIdentifier(kind, ch));
+                        }
+                        '>' => {
+                            // This is a missing-doctype-public/system-identifier parse error. Switch to the data state. Emit the current DOCTYPE token.
+                            self.state.set(State::Data);
+                            return self.emit_doctype();
+                        }
+                        _ => {
+                            // This is a missing-quote-before-doctype-public/system-identifier parse error. Reconsume in the bogus DOCTYPE state.
+                            self.reconsume.set(true);
+                            self.state.set(State::BogusDoctype);
+                        }
+                    },
+                    None => {
+                        // This is an eof-in-doctype parse error. Emit the current DOCTYPE token. Emit an end-of-file token.
+                        return self.emit_doctype_then_eof();
+                    }
+                },
+                // https://html.spec.whatwg.org/multipage/parsing.html#doctype-public-identifier-(double-quoted)-state
+                // and the single-quoted and system identifier states like it
+                State::DoctypeIdentifier(kind, quote) => match c {
+                    Some(ch) if ch == quote => {
+                        // Switch to the after DOCTYPE public/system identifier state.
+                        self.state.set(match kind {
+                            DoctypeIdKind::Public => State::AfterDoctypePublicIdentifier,
+                            DoctypeIdKind::System => State::AfterDoctypeSystemIdentifier,
+                        });
+                    }
+                    Some('>') => {
+                        // This is an abrupt-doctype-public/system-identifier parse error. Switch to the data state. Emit the current DOCTYPE token.
+                        self.state.set(State::Data);
+                        return self.emit_doctype();
+                    }
+                    // TODO: U+0000 NULL - This is an unexpected-null-character parse error. Append a U+FFFD REPLACEMENT CHARACTER character to the identifier.
+                    Some(ch) => {
+                        // Append the current input character to the current DOCTYPE token's identifier.
+                        let mut doctype = self.cur_doctype.borrow_mut();
+                        let id = match kind {
+                            DoctypeIdKind::Public => &mut doctype.public_id,
+                            DoctypeIdKind::System => &mut doctype.system_id,
+                        };
+                        id.get_or_insert_with(String::new).push(ch);
+                    }
+                    None => {
+                        // This is an eof-in-doctype parse error. Emit the current DOCTYPE token. Emit an end-of-file token.
+                        return self.emit_doctype_then_eof();
+                    }
+                },
+                // https://html.spec.whatwg.org/multipage/parsing.html#after-doctype-public-identifier-state
+                // https://html.spec.whatwg.org/multipage/parsing.html#between-doctype-public-and-system-identifiers-state
+                State::AfterDoctypePublicIdentifier
+                | State::BetweenDoctypePublicAndSystemIdentifiers => match c {
+                    Some(ch) => match ch {
+                        '\t' | '\n' | '\x0C' | ' ' => {
+                            // After the public identifier: switch to the between DOCTYPE public and system identifiers state. Between them: ignore the character.
+                            self.state
+                                .set(State::BetweenDoctypePublicAndSystemIdentifiers);
+                        }
+                        '>' => {
+                            // Switch to the data state. Emit the current DOCTYPE token.
+                            self.state.set(State::Data);
+                            return self.emit_doctype();
+                        }
+                        '"' | '\'' => {
+                            // Set the system identifier to the empty string, switch to the quoted system identifier state.
+                            // Straight after the public identifier this is a missing-whitespace-between-doctype-public-and-system-identifiers parse error.
+                            self.start_doctype_id(DoctypeIdKind::System);
+                            self.state
+                                .set(State::DoctypeIdentifier(DoctypeIdKind::System, ch));
+                        }
+                        _ => {
+                            // This is a missing-quote-before-doctype-system-identifier parse error. Reconsume in the bogus DOCTYPE state.
+                            self.reconsume.set(true);
+                            self.state.set(State::BogusDoctype);
+                        }
+                    },
+                    None => {
+                        // This is an eof-in-doctype parse error. Emit the current DOCTYPE token. Emit an end-of-file token.
+                        return self.emit_doctype_then_eof();
+                    }
+                },
+                // https://html.spec.whatwg.org/multipage/parsing.html#after-doctype-system-identifier-state
+                State::AfterDoctypeSystemIdentifier => match c {
+                    Some(ch) => match ch {
+                        '\t' | '\n' | '\x0C' | ' ' => {
+                            // Ignore the character.
+                        }
+                        '>' => {
+                            // Switch to the data state. Emit the current DOCTYPE token.
+                            self.state.set(State::Data);
+                            return self.emit_doctype();
+                        }
+                        _ => {
+                            // This is an unexpected-character-after-doctype-system-identifier parse error. Reconsume in the bogus DOCTYPE state.
+                            self.reconsume.set(true);
+                            self.state.set(State::BogusDoctype);
+                        }
+                    },
+                    None => {
+                        // This is an eof-in-doctype parse error. Emit the current DOCTYPE token. Emit an end-of-file token.
+                        return self.emit_doctype_then_eof();
+                    }
+                },
+                // https://html.spec.whatwg.org/multipage/parsing.html#bogus-doctype-state
+                State::BogusDoctype => match c {
+                    Some('>') => {
+                        // Switch to the data state. Emit the DOCTYPE token.
+                        self.state.set(State::Data);
+                        return self.emit_doctype();
+                    }
+                    Some(_) => {
+                        // Ignore the character.
+                    }
+                    None => {
+                        // Emit the DOCTYPE token. Emit an end-of-file token.
+                        return self.emit_doctype_then_eof();
+                    }
+                },
             }
         }
     }
 
+    /// If the input from the current character on matches `keyword`, ignoring ASCII case,
+    /// consume it all.
+    fn consume_keyword(&self, keyword: &str) -> bool {
+        let start = self.pos.get() - 1;
+        let matches = keyword.chars().enumerate().all(|(offset, k)| {
+            self.input
+                .get(start + offset)
+                .is_some_and(|c| c.eq_ignore_ascii_case(&k))
+        });
+        if matches {
+            self.pos.set(start + keyword.chars().count());
+        }
+        matches
+    }
+
+    fn create_doctype(&self) {
+        *self.cur_doctype.borrow_mut() = Doctype::default();
+    }
+
+    fn start_doctype_id(&self, kind: DoctypeIdKind) {
+        let mut doctype = self.cur_doctype.borrow_mut();
+        match kind {
+            DoctypeIdKind::Public => doctype.public_id = Some(String::new()),
+            DoctypeIdKind::System => doctype.system_id = Some(String::new()),
+        }
+    }
+
+    fn emit_doctype(&self) -> Token {
+        Token::Doctype(mem::take(self.cur_doctype.borrow_mut().deref_mut()))
+    }
+
+    fn emit_doctype_then_eof(&self) -> Token {
+        self.pending_tokens.borrow_mut().push(Token::EOF);
+        self.emit_doctype()
+    }
+
     fn create_comment(&self) {
         *self.cur_comment.borrow_mut() = String::new();
     }
@@ -641,25 +976,25 @@ impl Tokenizer {
     }
 }
 
+/// Every token of `input` up to and including the final `EOF`.
+pub fn tokenize(input: &str) -> Vec<Token> {
+    let tokenizer = Tokenizer::new(input);
+    let mut tokens = Vec::new();
+    loop {
+        let token = tokenizer.next();
+        let is_eof = matches!(token, Token::EOF);
+        tokens.push(token);
+        if is_eof {
+            return tokens;
+        }
+    }
+}
+
 /// Generated by Gemini 3 Pro
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn collect_tokens(input: &str) -> Vec<Token> {
-        let tokenizer = Tokenizer::new(input);
-        let mut tokens = Vec::new();
-        loop {
-            let token = tokenizer.next();
-            let is_eof = matches!(token, Token::EOF);
-            tokens.push(token);
-            if is_eof {
-                break;
-            }
-        }
-        tokens
-    }
-
     fn attr(name: &str, value: &str) -> Attribute {
         Attribute {
             name: name.to_string(),
@@ -687,7 +1022,7 @@ mod tests {
 
     #[test]
     fn test_basic_text() {
-        let tokens = collect_tokens("abc");
+        let tokens = tokenize("abc");
         assert_eq!(
             tokens,
             vec![
@@ -701,7 +1036,7 @@ mod tests {
 
     #[test]
     fn test_basic_tags() {
-        let tokens = collect_tokens("<div></div>");
+        let tokens = tokenize("<div></div>");
         assert_eq!(
             tokens,
             vec![start_tag("div", vec![], false), end_tag("div"), Token::EOF]
@@ -711,7 +1046,7 @@ mod tests {
     #[test]
     fn test_tag_case_insensitivity() {
         // 标签名应自动转小写
-        let tokens = collect_tokens("<DIV></div >");
+        let tokens = tokenize("<DIV></div >");
         assert_eq!(
             tokens,
             vec![start_tag("div", vec![], false), end_tag("div"), Token::EOF]
@@ -721,7 +1056,7 @@ mod tests {
     #[test]
     fn test_attributes_mixed() {
         // 测试双引号、无引号和不同属性情况
-        let tokens = collect_tokens("<div id=\"test\" class=foo checked>");
+        let tokens = tokenize("<div id=\"test\" class=foo checked>");
 
         let expected_attrs = vec![
             attr("id", "test"),
@@ -734,33 +1069,41 @@ mod tests {
 
     #[test]
     fn test_attributes_single_quoted() {
-        let tokens = collect_tokens("<div id='test'>");
+        let tokens = tokenize("<div id='test'>");
         assert_eq!(tokens[0], start_tag("div", vec![attr("id", "test")], false));
     }
 
     #[test]
     fn test_self_closing_tag() {
-        let tokens = collect_tokens("<br/>");
+        let tokens = tokenize("<br/>");
         assert_eq!(tokens[0], start_tag("br", vec![], true));
     }
 
     #[test]
     fn test_bogus_comment_xml() {
         // <?xml ...> 应该进入 BogusComment
-        let tokens = collect_tokens("<?xml version='1.0'?>");
+        let tokens = tokenize("<?xml version='1.0'?>");
         assert_eq!(tokens[0], Token::Comment("?xml version='1.0'?".to_string()));
     }
 
     #[test]
-    fn test_bogus_comment_doctype() {
-        // 你的实现将 <!DOCTYPE> 简化处理为 BogusComment
-        let tokens = collect_tokens("<!DOCTYPE>");
-        assert_eq!(tokens[0], Token::Comment("DOCTYPE".to_string()));
+    fn test_doctype_without_name() {
+        let tokens = tokenize("<!DOCTYPE>");
+        assert_eq!(tokens[0], Token::Doctype(Doctype::default()));
+
+        let tokens = tokenize("<!doctype HTML>");
+        assert_eq!(
+            tokens[0],
+            Token::Doctype(Doctype {
+                name: Some("html".to_string()),
+                ..Doctype::default()
+            })
+        );
     }
 
     #[test]
     fn test_empty_comment() {
-        let tokens = collect_tokens("<!>");
+        let tokens = tokenize("<!>");
         assert_eq!(tokens[0], Token::Comment("".to_string()));
     }
 
@@ -768,7 +1111,7 @@ mod tests {
     fn test_eof_in_tag_edge_case() {
         // 测试 State::EndTagOpen 中的 EOF 处理逻辑
         // 输入 "</"，期望输出 Token('<'), Token('/'), Token(EOF)
-        let tokens = collect_tokens("</");
+        let tokens = tokenize("</");
         assert_eq!(
             tokens,
             vec![Token::Character('<'), Token::Character('/'), Token::EOF]
@@ -779,7 +1122,7 @@ mod tests {
     fn test_invalid_tag_name_start() {
         // 测试 State::TagOpen 中非法字符的回退逻辑
         // 输入 "<4"，期望输出 Token('<'), Token('4'), Token(EOF)
-        let tokens = collect_tokens("<4");
+        let tokens = tokenize("<4");
         assert_eq!(
             tokens,
             vec![Token::Character('<'), Token::Character('4'), Token::EOF]
@@ -790,11 +1133,27 @@ mod tests {
     fn test_attribute_value_with_illegal_chars() {
         // 测试 Unquoted Attribute Value 对非法字符的宽容处理
         // <div data=foo"bar> -> value: foo"bar
-        let tokens = collect_tokens("<div data=foo\"bar>");
+        let tokens = tokenize("<div data=foo\"bar>");
 
         assert_eq!(
             tokens[0],
             start_tag("div", vec![attr("data", "foo\"bar")], false)
         );
     }
+
+    #[test]
+    fn test_unquoted_value_keeps_slashes() {
+        let tokens = tokenize("<a href=/a/b>");
+        assert_eq!(tokens[0], start_tag("a", vec![attr("href", "/a/b")], false));
+    }
+
+    #[test]
+    fn test_self_closing_after_unquoted_value() {
+        let tokens = tokenize("<img src=x />");
+        assert_eq!(tokens[0], start_tag("img", vec![attr("src", "x")], true));
+
+        // without the space the slash belongs to the value
+        let tokens = tokenize("<img src=x/>");
+        assert_eq!(tokens[0], start_tag("img", vec![attr("src", "x/")], false));
+    }
 }
@@ -1,17 +1,48 @@
-use crate::constant::layout::{DEFAULT_WIDTH, DEFAULT_X, DEFAULT_Y};
+use crate::constant::common::{CLOSING_PARENTHESIS, COMMA, OPENING_PARENTHESIS};
+use crate::constant::html::{
+    ATTRIBUTE_KEY_ALT, ATTRIBUTE_KEY_COLS, ATTRIBUTE_KEY_COLSPAN, ATTRIBUTE_KEY_DATA,
+    ATTRIBUTE_KEY_DIR, ATTRIBUTE_KEY_HEIGHT, ATTRIBUTE_KEY_HIDDEN, ATTRIBUTE_KEY_ROWS,
+    ATTRIBUTE_KEY_ROWSPAN, ATTRIBUTE_KEY_SIZE, ATTRIBUTE_KEY_SPAN, ATTRIBUTE_KEY_SRC,
+    ATTRIBUTE_KEY_START, ATTRIBUTE_KEY_TYPE, ATTRIBUTE_KEY_VALUE, ATTRIBUTE_KEY_WIDTH, BDO,
+    DIR_RTL, EMBEDDED_ELEMENTS, HR, IMG, INPUT, INPUT_TYPE_CHECKBOX, INPUT_TYPE_HIDDEN,
+    INPUT_TYPE_RADIO, LIST_ITEM, NON_TEXT_INPUT_TYPES, TABLE_CAPTION, TABLE_COLUMN,
+    TABLE_COLUMN_GROUP, TABLE_DATA, TABLE_HEADER, TABLE_ROW, TABLE_ROW_GROUPS, TEMPLATE, TEXTAREA,
+    WBR,
+};
+use crate::constant::layout::{
+    DEFAULT_WIDTH, DEFAULT_X, DEFAULT_Y, ELLIPSIS, EMBED_DEFAULT_HEIGHT, EMBED_DEFAULT_WIDTH,
+    EMBED_PLACEHOLDER_COLOR, HR_DEFAULT_SIZE, HR_MARGIN, INPUT_BORDER_COLOR, INPUT_DEFAULT_SIZE,
+    INPUT_PADDING, LIST_MARKER_GAP, SMALL_CAPS_SCALE, TABLE_CELL_SPACING, TEXTAREA_DEFAULT_COLS,
+    TEXTAREA_DEFAULT_ROWS,
+};
 use crate::constant::style::{
-    BACKGROUND_COLOR_DEFAULT_VALUE, DEFAULT_COLOR_STR, DEFAULT_FONT_SIZE_NUM,
-    STYLE_KEY_BACKGROUND_COLOR, STYLE_KEY_COLOR, STYLE_KEY_FONT_SIZE, STYLE_KEY_FONT_STYLE,
-    STYLE_KEY_FONT_WEIGHT,
+    BACKGROUND_COLOR_DEFAULT_VALUE, BOX_SIZING_BORDER_BOX, DEFAULT_COLOR_STR,
+    DEFAULT_FONT_SIZE_NUM, FONT_VARIANT_SMALL_CAPS, LINE_BOX_ALIGNS, LIST_MARKER_CIRCLE,
+    LIST_MARKER_DECIMAL_SUFFIX, LIST_MARKER_DISC, LIST_MARKER_SQUARE, LIST_STYLE_CIRCLE,
+    LIST_STYLE_DECIMAL, LIST_STYLE_NONE, LIST_STYLE_SQUARE, OVERFLOW_HIDDEN, OVERFLOW_SCROLLABLE,
+    POSITION_FIXED, STYLE_KEY_BACKGROUND_COLOR, STYLE_KEY_BORDER_COLOR, STYLE_KEY_BORDER_WIDTH,
+    STYLE_KEY_BOX_SIZING, STYLE_KEY_COLOR, STYLE_KEY_FONT_SIZE, STYLE_KEY_FONT_STYLE,
+    STYLE_KEY_FONT_VARIANT, STYLE_KEY_FONT_WEIGHT, STYLE_KEY_HEIGHT, STYLE_KEY_LEFT,
+    STYLE_KEY_LETTER_SPACING, STYLE_KEY_LIST_STYLE_TYPE, STYLE_KEY_MARGIN, STYLE_KEY_MARGIN_BOTTOM,
+    STYLE_KEY_MARGIN_LEFT, STYLE_KEY_MARGIN_RIGHT, STYLE_KEY_MARGIN_TOP, STYLE_KEY_MAX_HEIGHT,
+    STYLE_KEY_MAX_WIDTH, STYLE_KEY_MIN_HEIGHT, STYLE_KEY_MIN_WIDTH, STYLE_KEY_OPACITY,
+    STYLE_KEY_OVERFLOW, STYLE_KEY_PADDING, STYLE_KEY_POSITION, STYLE_KEY_TEXT_OVERFLOW,
+    STYLE_KEY_TEXT_TRANSFORM, STYLE_KEY_TOP, STYLE_KEY_TRANSFORM, STYLE_KEY_VERTICAL_ALIGN,
+    STYLE_KEY_VISIBILITY, STYLE_KEY_WIDTH, STYLE_KEY_WORD_BREAK, STYLE_KEY_WORD_SPACING,
+    TEXT_OVERFLOW_ELLIPSIS, TRANSFORM_SCALE, TRANSFORM_TRANSLATE, VERTICAL_ALIGN_MIDDLE,
+    VERTICAL_ALIGN_TOP, VISIBILITY_HIDDEN, WORD_BREAK_BREAK_ALL,
 };
 use crate::layout::draw_command::DrawCommand;
 use crate::layout::font_manager::{
     FontManagerRef, parse_font_size, parse_font_style, parse_font_weight,
 };
-use crate::layout::layout_mode::LayoutMode;
+use crate::layout::layout_mode::{LayoutMode, is_inline_block};
+use crate::layout::length::{LengthContext, parse_length};
+use crate::layout::white_space::WhiteSpace;
 use crate::parser::html_node::{HTMLNode, HTMLNodeData, HTMLNodeRef};
-use skia_safe::Font;
+use crate::parser::html_parser::ancestors;
 use skia_safe::font_style::{Slant, Weight};
+use skia_safe::{Font, Rect};
 use std::cell::RefCell;
 use std::fmt::{Display, Formatter, Result};
 use std::rc::{Rc, Weak};
@@ -24,6 +55,122 @@ pub struct DisplayItem {
     pub text: String,
     pub font: Font,
     pub color: String,
+    pub background_color: String,
+    pub letter_spacing: f32,
+    // the text or input node the word came from, for hit testing
+    pub node: HTMLNodeRef,
+}
+
+#[derive(Debug)]
+pub struct InputItem {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub text: Vec<DisplayItem>,
+    pub node: HTMLNodeRef,
+}
+
+#[derive(Debug)]
+struct InputBox {
+    x: f32,
+    width: f32,
+    height: f32,
+    rows: usize,
+    words: Vec<String>,
+    font: Font,
+    color: String,
+    node: HTMLNodeRef,
+}
+
+/// Per-side lengths of a box: its padding plus border.
+#[derive(Debug, Default, Clone, Copy)]
+struct Edges {
+    top: f32,
+    right: f32,
+    bottom: f32,
+    left: f32,
+}
+
+impl Edges {
+    fn horizontal(&self) -> f32 {
+        self.left + self.right
+    }
+
+    fn vertical(&self) -> f32 {
+        self.top + self.bottom
+    }
+}
+
+/// A table cell placed on the grid, spanning `colspan` columns and `rowspan` rows from its origin.
+#[derive(Debug)]
+struct TableCell {
+    node: HTMLNodeRef,
+    row: usize,
+    column: usize,
+    colspan: usize,
+    rowspan: usize,
+}
+
+/// An `inline-block` on the line, already laid out to know its size.
+#[derive(Debug)]
+struct InlineBlock {
+    x: f32,
+    width: f32,
+    height: f32,
+    block: BlockLayoutRef,
+    node: HTMLNodeRef,
+}
+
+#[derive(Debug)]
+enum LineItem {
+    // (x_start, word, font, color, background_color, node)
+    Word(f32, String, Font, String, String, HTMLNodeRef),
+    Input(InputBox),
+    Block(InlineBlock),
+}
+
+impl LineItem {
+    fn x(&self) -> f32 {
+        match self {
+            Self::Word(x, ..) => *x,
+            Self::Input(input) => input.x,
+            Self::Block(block) => block.x,
+        }
+    }
+
+    fn width(&self) -> f32 {
+        match self {
+            Self::Word(_, word, font, _, _, node) => {
+                let letter_spacing = styled_spacing(node, STYLE_KEY_LETTER_SPACING);
+                font.measure_str(word, None).1.width()
+                    + letter_spacing * word.chars().count() as f32
+            }
+            Self::Input(input) => input.width,
+            Self::Block(block) => block.width,
+        }
+    }
+
+    /// How far the item reaches (above, below) its baseline.
+    fn extent(&self) -> (f32, f32) {
+        match self {
+            Self::Word(_, _, font, ..) => {
+                let metrics = font.metrics().1;
+                (-metrics.ascent, metrics.descent)
+            }
+            // input boxes and inline-blocks sit on the baseline
+            Self::Input(input) => (input.height, 0.0),
+            Self::Block(block) => (block.height, 0.0),
+        }
+    }
+
+    fn node(&self) -> &HTMLNodeRef {
+        match self {
+            Self::Word(.., node) => node,
+            Self::Input(input) => &input.node,
+            Self::Block(block) => &block.node,
+        }
+    }
 }
 
 pub type BlockLayoutRef = Rc<RefCell<BlockLayout>>;
@@ -40,15 +187,22 @@ pub struct BlockLayout {
     y: f32,
     width: f32,
     pub height: f32,
+    // (x, y, width) assigned by the parent table or the document instead of being derived
+    // from the parent
+    pub fixed_geometry: Option<(f32, f32, f32)>,
+    // (x, y, width) of the last layout, moved as is while the node stays clean and as wide
+    laid_out_at: Option<(f32, f32, f32)>,
     mode: LayoutMode,
     cursor_x: f32,
     cursor_y: f32,
     weight: Weight,
     style: Slant,
     size: i32,
-    // (x_start, word, font, color)
-    line: Vec<(f32, String, Font, String)>,
+    line: Vec<LineItem>,
     display_list: Vec<DisplayItem>,
+    inputs: Vec<InputItem>,
+    // how far the content of an `overflow: scroll` or `auto` box is scrolled up
+    scroll: f32,
 }
 
 impl BlockLayout {
@@ -70,6 +224,8 @@ impl BlockLayout {
             y: 0.0,
             width: 0.0,
             height: 0.0,
+            fixed_geometry: None,
+            laid_out_at: None,
             mode,
             cursor_x: 0.0,
             cursor_y: 0.0,
@@ -78,24 +234,55 @@ impl BlockLayout {
             size: DEFAULT_FONT_SIZE_NUM,
             line: Vec::new(),
             display_list: Vec::new(),
+            inputs: Vec::new(),
+            scroll: 0.0,
         }))
     }
 
     // return (x, y, width)
     fn calc_pos_and_width(&self) -> (f32, f32, f32) {
+        if let Some(geometry) = self.fixed_geometry {
+            return geometry;
+        }
+
         if let Some(parent_weak) = &self.parent
             && let Some(parent_rc) = parent_weak.upgrade()
         {
             let parent = &*parent_rc.borrow();
-            let x = parent.x;
-            let mut y = parent.y;
-            let width = parent.width;
+            let x = parent.content_x() + margins(&self.node).left;
+            let mut y = parent.content_y();
+            let width = self.styled_width(parent.content_width());
 
-            if let Some(previous_weak) = &self.previous {
-                if let Some(previous_rc) = previous_weak.upgrade() {
+            if self.is_fixed() {
+                // fixed boxes are placed relative to the viewport, not the flow
+                let node = &*self.node.borrow();
+                let length = |key| node.style.get(key).and_then(|v| node_length(node, v));
+                let (left, top) = (length(STYLE_KEY_LEFT), length(STYLE_KEY_TOP));
+                return (
+                    left.map_or(DEFAULT_X, |left| DEFAULT_X + left),
+                    top.map_or(DEFAULT_Y, |top| DEFAULT_Y + top),
+                    width,
+                );
+            }
+
+            // vertical margins collapse: adjacent ones leave the larger gap, not their sum
+            let top_margin = collapsed_top_margin(&self.node);
+            match self
+                .previous
+                .as_ref()
+                .and_then(|previous| previous.upgrade())
+            {
+                Some(previous_rc) => {
                     let previous = &*previous_rc.borrow();
-                    y = previous.y + previous.height;
+                    let bottom_margin = margins(&previous.node).bottom;
+                    y = previous.y + previous.height + collapse_margins(bottom_margin, top_margin);
                 }
+                // the first child's margin was merged into the parent's, unless that's the root
+                // or a table cell, which are placed without margins
+                None if parent.parent.is_some()
+                    && parent.fixed_geometry.is_none()
+                    && collapses_with_first_child(&parent.node) => {}
+                None => y += top_margin,
             }
 
             (x, y, width)
@@ -105,212 +292,3124 @@ impl BlockLayout {
         }
     }
 
+    /// The computed `height` when it's given in px, `auto` and other units size to the content.
+    /// Like `width`, it's the content height unless `box-sizing` is `border-box`.
+    fn explicit_height(&self) -> Option<f32> {
+        self.styled_height(STYLE_KEY_HEIGHT)
+    }
+
+    /// A px height style, `height` or a bound on it, as the height of the whole box.
+    fn styled_height(&self, key: &str) -> Option<f32> {
+        let height = {
+            let node = self.node.borrow();
+            node_length(&node, node.style.get(key)?)?
+        };
+
+        let insets = self.insets().vertical();
+        Some(if self.is_border_box() {
+            height.max(insets)
+        } else {
+            height + insets
+        })
+    }
+
+    fn insets(&self) -> Edges {
+        node_insets(&self.node)
+    }
+
+    fn border_width(&self) -> f32 {
+        node_border_width(&self.node)
+    }
+
+    fn is_border_box(&self) -> bool {
+        self.node
+            .borrow()
+            .style
+            .get(STYLE_KEY_BOX_SIZING)
+            .is_some_and(|sizing| sizing == BOX_SIZING_BORDER_BOX)
+    }
+
+    /// Where children and text start, inside the padding and border.
+    fn content_x(&self) -> f32 {
+        self.x + self.insets().left
+    }
+
+    fn content_y(&self) -> f32 {
+        self.y + self.insets().top
+    }
+
+    fn content_width(&self) -> f32 {
+        (self.width - self.insets().horizontal()).max(0.0)
+    }
+
+    /// The line thickness of an `hr`, from its `size` attribute in px.
+    fn hr_size(&self) -> Option<f32> {
+        let node = self.node.borrow();
+        match &node.data {
+            HTMLNodeData::Element(e) if e.tag == HR => Some(
+                e.attributes
+                    .get(ATTRIBUTE_KEY_SIZE)
+                    .and_then(|size| parse_length(size, &LengthContext::default()))
+                    .filter(|size| *size > 0.0)
+                    .unwrap_or(HR_DEFAULT_SIZE),
+            ),
+            _ => None,
+        }
+    }
+
+    /// The explicit or content height, kept within `min-height` and `max-height`, the minimum
+    /// winning when they conflict.
     fn calc_height(&self) -> f32 {
-        match &self.mode {
+        let mut height = self
+            .explicit_height()
+            .unwrap_or_else(|| self.content_based_height());
+
+        if let Some(max) = self.styled_height(STYLE_KEY_MAX_HEIGHT) {
+            height = height.min(max);
+        }
+        if let Some(min) = self.styled_height(STYLE_KEY_MIN_HEIGHT) {
+            height = height.max(min);
+        }
+
+        height
+    }
+
+    fn content_based_height(&self) -> f32 {
+        let insets = self.insets().vertical();
+
+        // a rule has no content, it reserves room for its line
+        if let Some(size) = self.hr_size() {
+            return size + 2.0 * HR_MARGIN + insets;
+        }
+
+        let content_height = match &self.mode {
+            // down to the last in-flow child's bottom margin, which stays inside
             LayoutMode::Block => self
                 .children
                 .iter()
-                .map(|child| child.borrow().height)
-                .sum(),
+                .rfind(|child| !child.borrow().is_fixed())
+                .map_or(0.0, |child| {
+                    let child = &*child.borrow();
+                    child.y + child.height + margins(&child.node).bottom - self.content_y()
+                }),
             LayoutMode::Inline => self.cursor_y,
-        }
+            LayoutMode::Table => self
+                .children
+                .iter()
+                .map(|child| {
+                    let child = &*child.borrow();
+                    child.y + child.height + TABLE_CELL_SPACING - self.content_y()
+                })
+                .fold(0.0, f32::max),
+        };
+
+        content_height + insets
     }
 
     fn layout_block(&mut self, self_rc: BlockLayoutRef) {
+        // children from the last layout keep what they cached
+        let mut old_children = std::mem::take(&mut self.children).into_iter();
         let mut previous_rc: Option<BlockLayoutRef> = None;
         for child in &self.node.borrow().children {
-            let next = BlockLayout::new(
-                child.clone(),
-                Some(Rc::downgrade(&self_rc)),
-                match &previous_rc {
-                    Some(p_rc) => Some(Rc::downgrade(&p_rc)),
-                    None => None,
-                },
-                self.font_manager.clone(),
-            );
+            if is_inert(child) {
+                continue;
+            }
+
+            let previous = match &previous_rc {
+                Some(p_rc) => Some(Rc::downgrade(&p_rc)),
+                None => None,
+            };
+            let next = match old_children.next() {
+                Some(old) if Rc::ptr_eq(&old.borrow().node, child) => {
+                    old.borrow_mut().previous = previous;
+                    old
+                }
+                _ => BlockLayout::new(
+                    child.clone(),
+                    Some(Rc::downgrade(&self_rc)),
+                    previous,
+                    self.font_manager.clone(),
+                ),
+            };
             self.children.push(next.clone());
-            previous_rc = Some(next);
+
+            // fixed boxes are out of flow, so the next sibling stacks on the one before
+            if !next.borrow().is_fixed() {
+                previous_rc = Some(next);
+            }
         }
     }
 
-    fn flush(&mut self) {
-        if self.line.is_empty() {
-            return;
+    /// The box's outer width. The computed `width` is px or a percentage of the parent,
+    /// anything else fills the parent. `max-width` then `min-width` clamp it, so the minimum
+    /// wins when they conflict.
+    ///
+    /// Declared widths are of the content, with padding and border added outside, unless
+    /// `box-sizing` is `border-box` where they include them.
+    fn styled_width(&self, parent_width: f32) -> f32 {
+        let insets = self.insets().horizontal();
+        let fill = (parent_width - margins(&self.node).horizontal()).max(0.0);
+        let outer = |width: f32| {
+            if self.is_border_box() {
+                width.max(insets)
+            } else {
+                width + insets
+            }
+        };
+
+        let mut width = self
+            .styled_length(STYLE_KEY_WIDTH, parent_width)
+            .map_or(fill, outer);
+
+        if let Some(max) = self.styled_length(STYLE_KEY_MAX_WIDTH, parent_width) {
+            width = width.min(outer(max));
+        }
+        if let Some(min) = self.styled_length(STYLE_KEY_MIN_WIDTH, parent_width) {
+            width = width.max(outer(min));
         }
 
-        let mut max_ascent: f32 = 0.0;
-        let mut max_spacing: f32 = 0.0;
+        width
+    }
+
+    /// A length style resolved to px, percentages are of the parent's width.
+    fn styled_length(&self, key: &str, parent_width: f32) -> Option<f32> {
+        let node = self.node.borrow();
+        let context = LengthContext {
+            percent_base: Some(parent_width),
+            ..length_context(&node)
+        };
+        parse_length(node.style.get(key)?, &context)
+    }
+
+    fn is_overflow_hidden(&self) -> bool {
+        self.node
+            .borrow()
+            .style
+            .get(STYLE_KEY_OVERFLOW)
+            .is_some_and(|overflow| overflow == OVERFLOW_HIDDEN)
+    }
+
+    fn is_bounded(&self) -> bool {
+        self.explicit_height().is_some() || self.styled_height(STYLE_KEY_MAX_HEIGHT).is_some()
+    }
+
+    /// Whether this is an `overflow: scroll` or `auto` box with a height to scroll within.
+    fn is_scroll_container(&self) -> bool {
+        let scrollable = self
+            .node
+            .borrow()
+            .style
+            .get(STYLE_KEY_OVERFLOW)
+            .is_some_and(|overflow| OVERFLOW_SCROLLABLE.contains(&overflow.as_str()));
+        scrollable && self.is_bounded()
+    }
+
+    /// How far the content can scroll before its bottom meets the box's.
+    fn max_scroll(&self) -> f32 {
+        let children = self
+            .children
+            .iter()
+            .map(|child| child.borrow())
+            .filter(|child| !child.is_fixed())
+            .map(|child| child.y + child.height);
+        let words = self
+            .display_list
+            .iter()
+            .map(|item| item.y + item.font.spacing());
+        let inputs = self.inputs.iter().map(|input| input.y + input.height);
 
-        for (_, _, font, _) in &self.line {
-            max_ascent = max_ascent.max(-font.metrics().1.ascent);
-            max_spacing = max_spacing.max(font.spacing());
+        let bottom = children.chain(words).chain(inputs).fold(self.y, f32::max);
+        (bottom - self.y - self.height).max(0.0)
+    }
+
+    /// How far the content is scrolled, kept in range after a relayout shrank it.
+    fn scroll_offset(&self) -> f32 {
+        if self.is_scroll_container() {
+            self.scroll.min(self.max_scroll())
+        } else {
+            0.0
         }
+    }
 
-        let baseline = self.y + self.cursor_y + max_ascent;
+    /// Whether scrolling by `delta` would move the content.
+    fn can_scroll(&self, delta: f32) -> bool {
+        let scroll = self.scroll_offset();
+        self.is_scroll_container()
+            && ((delta > 0.0 && scroll < self.max_scroll()) || (delta < 0.0 && scroll > 0.0))
+    }
 
-        for (real_x, word, font, color) in self.line.drain(..) {
-            let x = self.x + real_x;
-            let ascent = -font.metrics().1.ascent;
-            let y = baseline - ascent;
-            self.display_list.push(DisplayItem {
-                x,
-                y,
-                baseline,
-                text: word.to_string(),
-                font,
-                color: color.to_string(),
-            })
+    /// Scroll the content of a scroll container, returns whether it moved.
+    pub fn scroll_by(&mut self, delta: f32) -> bool {
+        let scroll = (self.scroll_offset() + delta).clamp(0.0, self.max_scroll());
+        let moved = scroll != self.scroll_offset();
+        self.scroll = scroll;
+        moved
+    }
+
+    /// The innermost scroll container at a point in page coordinates that can still scroll
+    /// by `delta`.
+    pub fn scroll_container_at(
+        block_rc: &BlockLayoutRef,
+        x: f32,
+        y: f32,
+        delta: f32,
+    ) -> Option<BlockLayoutRef> {
+        let block = block_rc.borrow();
+        // the content is painted shifted up by the scroll
+        let content_y = y + block.scroll_offset();
+        let inner = block
+            .children
+            .iter()
+            .rev()
+            .find_map(|child| Self::scroll_container_at(child, x, content_y, delta));
+
+        let inside = x >= block.x
+            && x <= block.x + block.width
+            && y >= block.y
+            && y <= block.y + block.height;
+        inner.or_else(|| (inside && block.can_scroll(delta)).then(|| block_rc.clone()))
+    }
+
+    /// Shifts the content of a scrolled container, the box itself stays in place.
+    pub fn scroll_transform(&self) -> Option<DrawCommand> {
+        let scroll = self.scroll_offset();
+        (scroll > 0.0).then(|| DrawCommand::push_transform(0.0, -scroll, 1.0, self.x, self.y))
+    }
+
+    /// A clip around this block's content, for `overflow: hidden`, `scroll` and `auto` boxes
+    /// with an explicit or maximum height.
+    pub fn clip(&self) -> Option<DrawCommand> {
+        if !(self.is_overflow_hidden() || self.is_scroll_container()) || !self.is_bounded() {
+            return None;
         }
 
-        self.cursor_x = 0.0;
-        self.cursor_y += max_spacing;
+        Some(DrawCommand::push_clip(
+            self.x,
+            self.y,
+            self.x + self.width,
+            self.y + self.height,
+        ))
     }
 
-    fn word(&mut self, word: &str, node: HTMLNodeRef) {
-        let weight = parse_font_weight(node.borrow().style.get(STYLE_KEY_FONT_WEIGHT));
-        let style = parse_font_style(node.borrow().style.get(STYLE_KEY_FONT_STYLE));
-        let size = parse_font_size(node.borrow().style.get(STYLE_KEY_FONT_SIZE));
-        let font = self.font_manager.borrow_mut().get_font(size, weight, style);
+    /// A transform around this block's subtree for `transform: translate(x, y) scale(s)`,
+    /// none when it's unset or has a function we don't support.
+    pub fn transform(&self) -> Option<DrawCommand> {
+        let node = self.node.borrow();
+        let value = node.style.get(STYLE_KEY_TRANSFORM)?;
 
-        // Bounding Box
-        let w = font.measure_str(word, None).1.width();
-        // let space_w = font.measure_str(" ", None).1.width();
+        // the functions apply right to left, so a scale before a translate scales the move too
+        let (mut dx, mut dy, mut scale) = (0.0, 0.0, 1.0);
+        let functions = value.split(CLOSING_PARENTHESIS);
+        for function in functions.filter(|function| !function.trim().is_empty()) {
+            let (name, args) = function.split_once(OPENING_PARENTHESIS)?;
+            let args: Vec<&str> = args.split(COMMA).collect();
+            match (name.trim(), &args[..]) {
+                (TRANSFORM_TRANSLATE, [x]) => dx += scale * node_length(&node, x)?,
+                (TRANSFORM_TRANSLATE, [x, y]) => {
+                    dx += scale * node_length(&node, x)?;
+                    dy += scale * node_length(&node, y)?;
+                }
+                (TRANSFORM_SCALE, [s]) => scale *= s.trim().parse::<f32>().ok()?,
+                _ => return None,
+            }
+        }
 
-        // Advance Width
-        // let w = font.measure_str(word, None).0;
-        let space_w = font.measure_str(" ", None).0;
+        Some(DrawCommand::push_transform(
+            dx,
+            dy,
+            scale,
+            self.x + self.width / 2.0,
+            self.y + self.height / 2.0,
+        ))
+    }
 
-        if self.cursor_x + w > self.width {
-            self.flush();
+    pub fn is_fixed(&self) -> bool {
+        self.node
+            .borrow()
+            .style
+            .get(STYLE_KEY_POSITION)
+            .is_some_and(|position| position == POSITION_FIXED)
+    }
+
+    fn flush(&mut self) {
+        if self.line.is_empty() {
+            return;
         }
 
-        let color = node
+        let ellipsis = self
+            .node
             .borrow()
             .style
-            .get(STYLE_KEY_COLOR)
-            .map_or(DEFAULT_COLOR_STR.to_string(), |c| c.to_string());
+            .get(STYLE_KEY_TEXT_OVERFLOW)
+            .is_some_and(|text_overflow| text_overflow == TEXT_OVERFLOW_ELLIPSIS);
+        if ellipsis && self.is_overflow_hidden() {
+            self.truncate_line();
+        }
 
-        self.line
-            .push((self.cursor_x, word.to_string(), font, color));
+        let mut max_ascent: f32 = 0.0;
+        let mut max_descent: f32 = 0.0;
+        let mut max_spacing: f32 = 0.0;
+        // the tallest item aligned to the line box instead of the baseline
+        let mut max_aligned: f32 = 0.0;
 
-        self.cursor_x += w + space_w;
-    }
+        let aligns: Vec<Option<&str>> = self
+            .line
+            .iter()
+            .map(|item| vertical_align(item.node(), &self.node))
+            .collect();
 
-    fn recurse(&mut self, node_rc: HTMLNodeRef) {
-        let node_data = &node_rc.borrow().data;
-        let children = &node_rc.borrow().children;
-        match node_data {
-            HTMLNodeData::Text(t) => {
-                for word in t.text.split_whitespace() {
-                    self.word(word, node_rc.clone());
-                }
+        for (item, align) in self.line.iter().zip(&aligns) {
+            if let LineItem::Word(_, _, font, ..) = item {
+                max_spacing = max_spacing.max(font.spacing());
             }
-            HTMLNodeData::Element(e) => {
-                if e.tag == "br" {
-                    self.flush();
+
+            let (ascent, descent) = item.extent();
+            if align.is_some() {
+                max_aligned = max_aligned.max(ascent + descent);
+            } else {
+                max_ascent = max_ascent.max(ascent);
+                max_descent = max_descent.max(descent);
+            }
+        }
+
+        let line_top = self.content_y() + self.cursor_y;
+        let line_height = max_spacing.max(max_ascent + max_descent).max(max_aligned);
+        let baseline = line_top + max_ascent;
+        let content_x = self.content_x();
+        let xs = self.visual_order();
+
+        for ((item, real_x), align) in self.line.drain(..).zip(xs).zip(aligns) {
+            let (ascent, descent) = item.extent();
+            let y = match align {
+                Some(VERTICAL_ALIGN_TOP) => line_top,
+                Some(VERTICAL_ALIGN_MIDDLE) => line_top + (line_height - ascent - descent) / 2.0,
+                Some(_) => line_top + line_height - ascent - descent,
+                None => baseline - ascent,
+            };
+
+            match item {
+                LineItem::Word(_, word, font, color, background_color, node) => {
+                    let x = content_x + real_x;
+                    self.display_list.push(DisplayItem {
+                        x,
+                        y,
+                        baseline: y + ascent,
+                        text: word.to_string(),
+                        font,
+                        color: color.to_string(),
+                        background_color,
+                        letter_spacing: styled_spacing(&node, STYLE_KEY_LETTER_SPACING),
+                        node,
+                    })
                 }
-                for child in children {
-                    self.recurse(child.clone());
+                LineItem::Input(input) => {
+                    let x = content_x + real_x;
+                    self.inputs.push(InputItem {
+                        x,
+                        y,
+                        width: input.width,
+                        height: input.height,
+                        text: layout_input_text(&input, x, y),
+                        node: input.node,
+                    })
+                }
+                LineItem::Block(inline_block) => {
+                    let block_rc = inline_block.block;
+                    // laid out at the origin already, so it only has to be moved into place
+                    block_rc.borrow_mut().translate(content_x + real_x, y);
+                    self.children.push(block_rc);
                 }
             }
         }
-    }
 
-    fn layout_inline(&mut self) {
         self.cursor_x = 0.0;
-        self.cursor_y = 0.0;
-        self.weight = Weight::NORMAL;
-        self.style = Slant::Upright;
-        self.size = DEFAULT_FONT_SIZE_NUM;
-        self.line.clear();
-        self.recurse(self.node.clone());
-        self.flush();
+        self.cursor_y += line_height;
     }
 
-    pub fn layout(block_rc: BlockLayoutRef) {
+    /// Cut the line where it overflows the content box, dropping whole items and then
+    /// characters until an ellipsis after the last word fits.
+    fn truncate_line(&mut self) {
+        let limit = self.content_width();
+        if self
+            .line
+            .iter()
+            .all(|item| item.x() + item.width() <= limit)
         {
-            let block = &mut *block_rc.borrow_mut();
-            let (x, y, width) = block.calc_pos_and_width();
-            block.x = x;
-            block.y = y;
-            block.width = width;
+            return;
+        }
 
-            match &block.mode {
-                LayoutMode::Block => block.layout_block(block_rc.clone()),
-                LayoutMode::Inline => block.layout_inline(),
+        while let Some(item) = self.line.pop() {
+            let LineItem::Word(x, mut word, font, color, background_color, node) = item else {
+                continue;
+            };
+            if x >= limit {
+                continue;
+            }
+
+            loop {
+                let truncated = LineItem::Word(
+                    x,
+                    format!("{}{}", word, ELLIPSIS),
+                    font.clone(),
+                    color.clone(),
+                    background_color.clone(),
+                    node.clone(),
+                );
+                // with nothing left of the first word the ellipsis is shown on its own
+                if truncated.x() + truncated.width() <= limit
+                    || (word.is_empty() && self.line.is_empty())
+                {
+                    self.line.push(truncated);
+                    return;
+                }
+                if word.pop().is_none() {
+                    break;
+                }
             }
         }
+    }
 
-        {
-            for child_rc in &block_rc.borrow().children {
-                BlockLayout::layout(child_rc.clone());
+    /// The x of each item on the line once directions are applied. Every character is treated
+    /// as ltr, so only a `bdo dir=rtl` run is reversed, and an rtl paragraph starts at the right.
+    fn visual_order(&self) -> Vec<f32> {
+        let mut xs: Vec<f32> = self.line.iter().map(|item| item.x()).collect();
+        let widths: Vec<f32> = self.line.iter().map(|item| item.width()).collect();
+        let overrides: Vec<Option<HTMLNodeRef>> = self
+            .line
+            .iter()
+            .map(|item| rtl_override(item.node()))
+            .collect();
+
+        // mirror each run of items under the same override within the span it takes up
+        let mut i = 0;
+        while i < xs.len() {
+            let Some(root) = &overrides[i] else {
+                i += 1;
+                continue;
+            };
+
+            let mut j = i + 1;
+            while j < xs.len() && overrides[j].as_ref().is_some_and(|o| Rc::ptr_eq(root, o)) {
+                j += 1;
+            }
+
+            let span = xs[i] + xs[j - 1] + widths[j - 1];
+            for (x, width) in xs[i..j].iter_mut().zip(&widths[i..j]) {
+                *x = span - *x - width;
             }
+            i = j;
         }
 
-        {
-            let block = &mut *block_rc.borrow_mut();
-            block.height = block.calc_height();
+        if is_rtl(&self.node) {
+            let end = xs
+                .iter()
+                .zip(&widths)
+                .map(|(x, w)| x + w)
+                .fold(0.0, f32::max);
+            let shift = (self.content_width() - end).max(0.0);
+            xs.iter_mut().for_each(|x| *x += shift);
         }
+
+        xs
     }
 
-    pub fn paint(&self) -> Vec<DrawCommand> {
-        let mut cmds = Vec::new();
+    fn get_font(&self, node: &HTMLNodeRef) -> Font {
+        self.get_scaled_font(node, 1.0)
+    }
 
-        if let Some(background_color) = self.node.borrow().style.get(STYLE_KEY_BACKGROUND_COLOR)
-            && background_color != BACKGROUND_COLOR_DEFAULT_VALUE
-        {
-            let x2 = self.x + self.width;
-            let y2 = self.y + self.height;
-            cmds.push(DrawCommand::rect(self.x, self.y, x2, y2, background_color));
+    fn get_scaled_font(&self, node: &HTMLNodeRef, scale: f32) -> Font {
+        let (size, weight, style) = font_params(node, scale);
+        self.font_manager.borrow_mut().get_font(size, weight, style)
+    }
+
+    /// The (advance, bounding box) widths of `text` in the node's font scaled by `scale`.
+    fn measure(&self, node: &HTMLNodeRef, scale: f32, text: &str) -> (f32, f32) {
+        let (size, weight, style) = font_params(node, scale);
+        self.font_manager
+            .borrow_mut()
+            .measure(size, weight, style, text)
+    }
+
+    fn get_color(&self, node: &HTMLNodeRef) -> String {
+        node.borrow()
+            .style
+            .get(STYLE_KEY_COLOR)
+            .map_or(DEFAULT_COLOR_STR.to_string(), |c| c.to_string())
+    }
+
+    /// Backgrounds aren't inherited, so a text run takes the first one set on its inline
+    /// ancestors. The block's own background is painted as a rect in `paint`.
+    fn get_background_color(&self, node: &HTMLNodeRef) -> String {
+        let mut current = node.clone();
+
+        while !Rc::ptr_eq(&current, &self.node) {
+            if let Some(background_color) = current.borrow().style.get(STYLE_KEY_BACKGROUND_COLOR)
+                && DrawCommand::is_visible_color(background_color)
+            {
+                return background_color.to_string();
+            }
+
+            let parent = match &current.borrow().parent {
+                Some(parent_weak) => parent_weak.upgrade(),
+                None => None,
+            };
+
+            match parent {
+                Some(parent) => current = parent,
+                None => break,
+            }
         }
 
-        // let node = &*self.node.borrow();
-        // if let HTMLNodeData::Element(e) = &node.data
-        //     && e.tag == "pre"
-        // {
-        //     let x2 = self.x + self.width;
-        //     let y2 = self.y + self.height;
-        //     cmds.push(DrawCommand::rect(self.x, self.y, x2, y2, Color::GRAY));
-        // }
+        BACKGROUND_COLOR_DEFAULT_VALUE.to_string()
+    }
 
-        if let LayoutMode::Inline = self.mode {
-            for item in &self.display_list {
-                cmds.push(DrawCommand::text(
-                    item.x,
-                    item.y,
-                    item.baseline,
-                    item.text.to_string(),
-                    item.font.clone(),
-                    &item.color,
-                ));
+    fn word(&mut self, word: &str, node: HTMLNodeRef) {
+        // transform first, so line breaking measures what is actually drawn
+        let mut word = text_transform(word, &node);
+        // an override draws the characters of each word reversed too
+        if rtl_override(&node).is_some() {
+            word = word.chars().rev().collect();
+        }
+
+        // the line is filled up to the last character that fits, the rest goes on the next
+        if breaks_all(&node) && WhiteSpace::new(&node).wraps() {
+            while let Some(split) = self.break_all_split(&word, &node) {
+                let rest = word.split_off(split);
+                self.place_word(&word, &node);
+                self.flush();
+                word = rest;
             }
         }
 
-        cmds
+        self.place_word(&word, &node);
     }
 
-    pub fn print_tree(&self, depth: usize) {
-        let indent = "  ".repeat(depth);
+    /// Where to break a word that overflows the line under `word-break: break-all`, after at
+    /// least one character. `None` once the rest of it fits.
+    fn break_all_split(&mut self, word: &str, node: &HTMLNodeRef) -> Option<usize> {
+        loop {
+            let available = self.content_width() - self.cursor_x;
+            if self.word_runs(word, node).1.iter().sum::<f32>() <= available {
+                return None;
+            }
 
-        println!("{}{}", indent, self);
+            let mut split = None;
+            for (index, _) in word.char_indices().skip(1) {
+                let width: f32 = self.word_runs(&word[..index], node).1.iter().sum();
+                if width > available {
+                    break;
+                }
+                split = Some(index);
+            }
 
-        for child in &self.children {
-            child.borrow().print_tree(depth + 1);
+            match split {
+                Some(split) => return Some(split),
+                // nothing fits after what's already on the line, so start a new one
+                None if !self.line.is_empty() => self.flush(),
+                // not even one character fits an empty line, it goes on a line of its own
+                None => return word.char_indices().nth(1).map(|(index, _)| index),
+            }
         }
     }
+
+    /// The runs a word is drawn as, each with its font scale, and their widths.
+    fn word_runs(&self, word: &str, node: &HTMLNodeRef) -> (Vec<(String, f32)>, Vec<f32>) {
+        let letter_spacing = styled_spacing(node, STYLE_KEY_LETTER_SPACING);
+
+        // small caps draw the lowercase runs as uppercase in a smaller font, one item each
+        let runs: Vec<(String, f32)> = if is_small_caps(node) {
+            small_caps_runs(word)
+                .into_iter()
+                .map(|(run, lowered)| (run, if lowered { SMALL_CAPS_SCALE } else { 1.0 }))
+                .collect()
+        } else {
+            vec![(word.to_string(), 1.0)]
+        };
+
+        // Bounding Box
+        let widths: Vec<f32> = runs
+            .iter()
+            .map(|(run, scale)| {
+                self.measure(node, *scale, run).1 + letter_spacing * run.chars().count() as f32
+            })
+            .collect();
+        (runs, widths)
+    }
+
+    /// Put an already transformed word on the line, breaking the line before it if it doesn't
+    /// fit, and advance past it and the space after it.
+    fn place_word(&mut self, word: &str, node: &HTMLNodeRef) {
+        let node = node.clone();
+        let letter_spacing = styled_spacing(&node, STYLE_KEY_LETTER_SPACING);
+        let word_spacing = styled_spacing(&node, STYLE_KEY_WORD_SPACING);
+
+        let (runs, widths) = self.word_runs(word, &node);
+        let w: f32 = widths.iter().sum();
+        // let space_w = font.measure_str(" ", None).1.width();
+
+        // Advance Width
+        // let w = font.measure_str(word, None).0;
+        let space_w = self.measure(&node, 1.0, " ").0 + letter_spacing + word_spacing;
+
+        // between preserved spaces there are empty words, only the space is kept
+        if word.is_empty() {
+            self.cursor_x += space_w;
+            return;
+        }
+
+        if self.cursor_x + w > self.content_width() && WhiteSpace::new(&node).wraps() {
+            self.flush();
+        }
+
+        let color = self.get_color(&node);
+        let background_color = self.get_background_color(&node);
+
+        for ((run, scale), width) in runs.into_iter().zip(widths) {
+            self.line.push(LineItem::Word(
+                self.cursor_x,
+                run,
+                self.get_scaled_font(&node, scale),
+                color.clone(),
+                background_color.clone(),
+                node.clone(),
+            ));
+            self.cursor_x += width;
+        }
+
+        self.cursor_x += space_w;
+    }
+
+    /// `input` and `textarea` are laid out atomically as a single box on the line.
+    fn input(&mut self, node: HTMLNodeRef) {
+        let font = self.get_font(&node);
+        let char_w = font.measure_str("0", None).0;
+        let line_h = font.spacing();
+
+        let (content_w, rows, words) = {
+            let node = &*node.borrow();
+            let HTMLNodeData::Element(e) = &node.data else {
+                return;
+            };
+
+            let get_number = |key: &str, default: usize| {
+                e.attributes
+                    .get(key)
+                    .and_then(|v| v.trim().parse::<usize>().ok())
+                    .filter(|v| *v > 0)
+                    .unwrap_or(default)
+            };
+
+            let value_words = || {
+                e.attributes
+                    .get(ATTRIBUTE_KEY_VALUE)
+                    .map_or(Vec::new(), |v| {
+                        v.split_whitespace().map(|w| w.to_string()).collect()
+                    })
+            };
+
+            if e.tag == TEXTAREA {
+                let mut words = Vec::new();
+                for child in &node.children {
+                    if let HTMLNodeData::Text(t) = &child.borrow().data {
+                        words.extend(t.text.split_whitespace().map(|w| w.to_string()));
+                    }
+                }
+
+                let cols = get_number(ATTRIBUTE_KEY_COLS, TEXTAREA_DEFAULT_COLS);
+                let rows = get_number(ATTRIBUTE_KEY_ROWS, TEXTAREA_DEFAULT_ROWS);
+                (cols as f32 * char_w, rows, words)
+            } else {
+                let input_type = e
+                    .attributes
+                    .get(ATTRIBUTE_KEY_TYPE)
+                    .map_or(String::new(), |t| t.trim().to_lowercase());
+
+                match input_type.as_str() {
+                    INPUT_TYPE_HIDDEN => return,
+                    INPUT_TYPE_CHECKBOX | INPUT_TYPE_RADIO => (font.size(), 0, Vec::new()),
+                    // the other non-text types are buttons showing their value
+                    t if NON_TEXT_INPUT_TYPES.contains(&t) => {
+                        let words = value_words();
+                        let w = font.measure_str(words.join(" "), None).0;
+                        (w, 1, words)
+                    }
+                    _ => {
+                        let size = get_number(ATTRIBUTE_KEY_SIZE, INPUT_DEFAULT_SIZE);
+                        (size as f32 * char_w, 1, value_words())
+                    }
+                }
+            }
+        };
+
+        let width = content_w + 2.0 * INPUT_PADDING;
+        let height = if rows == 0 {
+            width
+        } else {
+            rows as f32 * line_h + 2.0 * INPUT_PADDING
+        };
+
+        if self.cursor_x + width > self.content_width() {
+            self.flush();
+        }
+
+        let space_w = font.measure_str(" ", None).0;
+        let color = self.get_color(&node);
+
+        self.line.push(LineItem::Input(InputBox {
+            x: self.cursor_x,
+            width,
+            height,
+            rows,
+            words,
+            font,
+            color,
+            node: node.clone(),
+        }));
+
+        self.cursor_x += width + space_w;
+    }
+
+    /// An `inline-block` is laid out as a block of its own to learn its size, then placed on
+    /// the line whole like a word.
+    fn inline_block(&mut self, node: HTMLNodeRef) {
+        let block_rc = BlockLayout::new(node.clone(), None, None, self.font_manager.clone());
+        let width = block_rc.borrow().inline_block_width(self.content_width());
+        // at the origin for now, the line moves it into place once it is flushed
+        block_rc.borrow_mut().fixed_geometry = Some((0.0, 0.0, width));
+        BlockLayout::layout(block_rc.clone());
+        let height = block_rc.borrow().height;
+
+        if self.cursor_x + width > self.content_width() {
+            self.flush();
+        }
+
+        self.line.push(LineItem::Block(InlineBlock {
+            x: self.cursor_x,
+            width,
+            height,
+            block: block_rc,
+            node: node.clone(),
+        }));
+
+        self.cursor_x += width + self.measure(&node, 1.0, " ").0;
+    }
+
+    /// Moves a laid out block and everything in it by `(dx, dy)`.
+    fn translate(&mut self, dx: f32, dy: f32) {
+        self.x += dx;
+        self.y += dy;
+        if let Some((x, y, width)) = self.fixed_geometry {
+            self.fixed_geometry = Some((x + dx, y + dy, width));
+        }
+
+        let inputs = self.inputs.iter_mut();
+        let input_text = inputs.flat_map(|input| {
+            input.x += dx;
+            input.y += dy;
+            input.text.iter_mut()
+        });
+        for item in self.display_list.iter_mut().chain(input_text) {
+            item.x += dx;
+            item.y += dy;
+            item.baseline += dy;
+        }
+
+        for child_rc in &self.children {
+            child_rc.borrow_mut().translate(dx, dy);
+        }
+    }
+
+    /// An `inline-block`'s outer width: the styled one, else as wide as its content on one
+    /// line, but no wider than the room there is.
+    fn inline_block_width(&self, available: f32) -> f32 {
+        let styled = self.styled_width(available);
+        if self.node.borrow().style.contains_key(STYLE_KEY_WIDTH) {
+            return styled;
+        }
+
+        (self.intrinsic_width(&self.node) + self.insets().horizontal()).min(styled)
+    }
+
+    /// A frame or plugin as a replaced box of its `width`/`height`, labeled with where it points.
+    fn embed(&mut self, node: HTMLNodeRef) {
+        let (width, height, words) = {
+            let node = &*node.borrow();
+            let HTMLNodeData::Element(e) = &node.data else {
+                return;
+            };
+
+            let get_length = |key: &str, default: f32| {
+                e.attributes
+                    .get(key)
+                    .and_then(|v| parse_length(v, &LengthContext::default()))
+                    .filter(|v| *v > 0.0)
+                    .unwrap_or(default)
+            };
+
+            let source = e
+                .attributes
+                .get(ATTRIBUTE_KEY_SRC)
+                .or_else(|| e.attributes.get(ATTRIBUTE_KEY_DATA));
+
+            (
+                get_length(ATTRIBUTE_KEY_WIDTH, EMBED_DEFAULT_WIDTH),
+                get_length(ATTRIBUTE_KEY_HEIGHT, EMBED_DEFAULT_HEIGHT),
+                source.map_or(Vec::new(), |s| vec![s.to_string()]),
+            )
+        };
+
+        if self.cursor_x + width > self.content_width() {
+            self.flush();
+        }
+
+        let font = self.get_font(&node);
+        let space_w = font.measure_str(" ", None).0;
+        let rows = ((height - 2.0 * INPUT_PADDING) / font.spacing()).max(0.0) as usize;
+        let color = self.get_color(&node);
+
+        self.line.push(LineItem::Input(InputBox {
+            x: self.cursor_x,
+            width,
+            height,
+            rows,
+            words,
+            font,
+            color,
+            node: node.clone(),
+        }));
+
+        self.cursor_x += width + space_w;
+    }
+
+    /// Break a text node into words and lines according to its `white-space`.
+    fn text(&mut self, text: &str, node: HTMLNodeRef) {
+        let white_space = WhiteSpace::new(&node);
+
+        if !white_space.preserves_newlines() {
+            for word in text.split_whitespace() {
+                self.word(word, node.clone());
+            }
+            return;
+        }
+
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.newline(&node);
+            }
+
+            let line = line.trim_end_matches('\r');
+            if white_space.preserves_spaces() {
+                // each separator is one space, so runs of spaces keep their width
+                for word in line.split(' ') {
+                    self.word(word, node.clone());
+                }
+            } else {
+                for word in line.split_whitespace() {
+                    self.word(word, node.clone());
+                }
+            }
+        }
+    }
+
+    /// Let the next word continue the last one without a space, so the line may break between
+    /// them but otherwise they read as one word.
+    fn word_break_opportunity(&mut self) {
+        if let Some(item @ LineItem::Word(..)) = self.line.last() {
+            self.cursor_x = item.x() + item.width();
+        }
+    }
+
+    /// A preserved line break, which still takes up a line when nothing is on it.
+    fn newline(&mut self, node: &HTMLNodeRef) {
+        if self.line.is_empty() {
+            self.cursor_x = 0.0;
+            self.cursor_y += self.get_font(node).spacing();
+        } else {
+            self.flush();
+        }
+    }
+
+    fn recurse(&mut self, node_rc: HTMLNodeRef) {
+        let node_data = &node_rc.borrow().data;
+        let children = &node_rc.borrow().children;
+        match node_data {
+            HTMLNodeData::Text(t) => self.text(&t.text, node_rc.clone()),
+            HTMLNodeData::Element(e) => {
+                if is_inert(&node_rc) {
+                    return;
+                }
+                if e.tag == "br" {
+                    self.flush();
+                }
+                if e.tag == WBR {
+                    self.word_break_opportunity();
+                }
+                if e.tag == INPUT || e.tag == TEXTAREA {
+                    self.input(node_rc.clone());
+                    return;
+                }
+                // fallback content inside an `object` is skipped along with the frame's document
+                if EMBEDDED_ELEMENTS.contains(&e.tag.as_str()) {
+                    self.embed(node_rc.clone());
+                    return;
+                }
+                // images aren't decoded, their alt text stands in for them
+                if e.tag == IMG {
+                    if let Some(alt) = e.attributes.get(ATTRIBUTE_KEY_ALT) {
+                        for word in alt.split_whitespace() {
+                            self.word(word, node_rc.clone());
+                        }
+                    }
+                    return;
+                }
+                if is_inline_block(&node_rc) && !Rc::ptr_eq(&node_rc, &self.node) {
+                    self.inline_block(node_rc.clone());
+                    return;
+                }
+                let (before, after) = (
+                    node_rc.borrow().before.clone(),
+                    node_rc.borrow().after.clone(),
+                );
+                if let Some(before) = before {
+                    self.text(&before, node_rc.clone());
+                }
+                for child in children {
+                    self.recurse(child.clone());
+                }
+                if let Some(after) = after {
+                    self.text(&after, node_rc.clone());
+                }
+            }
+        }
+    }
+
+    fn layout_inline(&mut self) {
+        // only inline-blocks, made anew as the line places them
+        self.children.clear();
+        self.cursor_x = 0.0;
+        self.cursor_y = 0.0;
+        self.weight = Weight::NORMAL;
+        self.style = Slant::Upright;
+        self.size = DEFAULT_FONT_SIZE_NUM;
+        self.line.clear();
+        self.recurse(self.node.clone());
+        self.flush();
+    }
+
+    /// Collect the `td`/`th` cells of every `tr`, looking through `thead`/`tbody`/`tfoot`.
+    fn table_rows(&self) -> Vec<Vec<HTMLNodeRef>> {
+        fn collect(node: &HTMLNodeRef, rows: &mut Vec<Vec<HTMLNodeRef>>) {
+            for child in &node.borrow().children {
+                let HTMLNodeData::Element(e) = &child.borrow().data else {
+                    continue;
+                };
+
+                if e.tag == TABLE_ROW {
+                    let cells = child
+                        .borrow()
+                        .children
+                        .iter()
+                        .filter(|cell| match &cell.borrow().data {
+                            HTMLNodeData::Element(e) => {
+                                e.tag == TABLE_DATA || e.tag == TABLE_HEADER
+                            }
+                            _ => false,
+                        })
+                        .cloned()
+                        .collect();
+                    rows.push(cells);
+                } else if TABLE_ROW_GROUPS.contains(&e.tag.as_str()) {
+                    collect(child, rows);
+                }
+            }
+        }
+
+        let mut rows = Vec::new();
+        collect(&self.node, &mut rows);
+        rows
+    }
+
+    /// Place each cell on the grid, skipping slots still covered by a rowspan from above.
+    /// Returns the cells and the number of columns.
+    fn table_cells(rows: &[Vec<HTMLNodeRef>]) -> (Vec<TableCell>, usize) {
+        let get_span = |cell: &HTMLNodeRef, key: &str| match &cell.borrow().data {
+            HTMLNodeData::Element(e) => e
+                .attributes
+                .get(key)
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(1)
+                .max(1),
+            _ => 1,
+        };
+
+        let mut cells = Vec::new();
+        // occupied[row][column]
+        let mut occupied: Vec<Vec<bool>> = vec![Vec::new(); rows.len()];
+
+        for (row, row_cells) in rows.iter().enumerate() {
+            let mut column = 0;
+            for cell in row_cells {
+                while occupied[row].get(column).copied().unwrap_or(false) {
+                    column += 1;
+                }
+
+                let colspan = get_span(cell, ATTRIBUTE_KEY_COLSPAN);
+                // spans past the last row are cut off
+                let rowspan = get_span(cell, ATTRIBUTE_KEY_ROWSPAN).min(rows.len() - row);
+
+                for covered in &mut occupied[row..row + rowspan] {
+                    if covered.len() < column + colspan {
+                        covered.resize(column + colspan, false);
+                    }
+                    covered[column..column + colspan].fill(true);
+                }
+
+                cells.push(TableCell {
+                    node: cell.clone(),
+                    row,
+                    column,
+                    colspan,
+                    rowspan,
+                });
+                column += colspan;
+            }
+        }
+
+        let columns = occupied.iter().map(|row| row.len()).max().unwrap_or(0);
+        (cells, columns)
+    }
+
+    fn table_caption(&self) -> Option<HTMLNodeRef> {
+        self.node
+            .borrow()
+            .children
+            .iter()
+            .find(|child| match &child.borrow().data {
+                HTMLNodeData::Element(e) => e.tag == TABLE_CAPTION,
+                _ => false,
+            })
+            .cloned()
+    }
+
+    /// Width hints per column from `col`/`colgroup`, `None` where a column has no `width`.
+    fn column_hints(&self) -> Vec<Option<f32>> {
+        // (width, span) of a col or colgroup element
+        fn hint(node: &HTMLNodeRef, fallback: Option<f32>) -> (Option<f32>, usize) {
+            let HTMLNodeData::Element(e) = &node.borrow().data else {
+                return (None, 0);
+            };
+            let width = e
+                .attributes
+                .get(ATTRIBUTE_KEY_WIDTH)
+                .and_then(|w| parse_length(w, &LengthContext::default()))
+                .or(fallback);
+            let span = e
+                .attributes
+                .get(ATTRIBUTE_KEY_SPAN)
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(1)
+                .max(1);
+            (width, span)
+        }
+
+        let mut hints = Vec::new();
+
+        for child in &self.node.borrow().children {
+            let tag = match &child.borrow().data {
+                HTMLNodeData::Element(e) => e.tag.clone(),
+                _ => continue,
+            };
+
+            if tag == TABLE_COLUMN {
+                let (width, span) = hint(child, None);
+                hints.extend(std::iter::repeat_n(width, span));
+            } else if tag == TABLE_COLUMN_GROUP {
+                let (group_width, group_span) = hint(child, None);
+                let columns: Vec<HTMLNodeRef> = child
+                    .borrow()
+                    .children
+                    .iter()
+                    .filter(|col| match &col.borrow().data {
+                        HTMLNodeData::Element(e) => e.tag == TABLE_COLUMN,
+                        _ => false,
+                    })
+                    .cloned()
+                    .collect();
+
+                // a colgroup's own span only counts when it has no col children
+                if columns.is_empty() {
+                    hints.extend(std::iter::repeat_n(group_width, group_span));
+                }
+                for col in &columns {
+                    let (width, span) = hint(col, group_width);
+                    hints.extend(std::iter::repeat_n(width, span));
+                }
+            }
+        }
+
+        hints
+    }
+
+    /// The width a node's text needs when laid out on a single line.
+    fn intrinsic_width(&self, node: &HTMLNodeRef) -> f32 {
+        match &node.borrow().data {
+            HTMLNodeData::Text(t) => {
+                let font = self.get_font(node);
+                let space_w = font.measure_str(" ", None).0;
+                t.text
+                    .split_whitespace()
+                    .map(|word| font.measure_str(word, None).1.width() + space_w)
+                    .sum()
+            }
+            HTMLNodeData::Element(_) => node
+                .borrow()
+                .children
+                .iter()
+                .map(|child| self.intrinsic_width(child))
+                .sum(),
+        }
+    }
+
+    /// A cell's preferred width: its `width` attribute in pixels, else its content width.
+    fn cell_width(&self, cell: &HTMLNodeRef) -> f32 {
+        if let HTMLNodeData::Element(e) = &cell.borrow().data
+            && let Some(width) = e.attributes.get(ATTRIBUTE_KEY_WIDTH)
+            && let Some(width) = parse_length(width, &LengthContext::default())
+        {
+            return width;
+        }
+
+        self.intrinsic_width(cell)
+    }
+
+    fn column_widths(&self, cells: &[TableCell], columns: usize) -> Vec<f32> {
+        let hints = self.column_hints();
+        if columns == 0 {
+            return Vec::new();
+        }
+
+        let mut widths = vec![0.0_f32; columns];
+        for (width, hint) in widths.iter_mut().zip(hints.iter()) {
+            *width = hint.unwrap_or(0.0);
+        }
+        for cell in cells.iter().filter(|cell| cell.colspan == 1) {
+            widths[cell.column] = widths[cell.column].max(self.cell_width(&cell.node));
+        }
+
+        // a spanning cell that doesn't fit its columns widens the last one it covers
+        for cell in cells.iter().filter(|cell| cell.colspan > 1) {
+            let spanned = cell.column..cell.column + cell.colspan;
+            let spanned_width = widths[spanned.clone()].iter().sum::<f32>()
+                + TABLE_CELL_SPACING * (cell.colspan - 1) as f32;
+            let needed = self.cell_width(&cell.node) - spanned_width;
+            if needed > 0.0 {
+                widths[spanned.end - 1] += needed;
+            }
+        }
+
+        // shrink proportionally when the columns don't fit, share the width when there's no content
+        let available = self.content_width() - TABLE_CELL_SPACING * (columns + 1) as f32;
+        let total: f32 = widths.iter().sum();
+        if total <= 0.0 {
+            widths.fill(available / columns as f32);
+        } else if total > available {
+            let scale = available / total;
+            widths.iter_mut().for_each(|w| *w *= scale);
+        }
+
+        widths
+    }
+
+    fn layout_table(block_rc: BlockLayoutRef) {
+        let (rows, cells, widths, caption, x, mut y, width, font_manager) = {
+            let block = &*block_rc.borrow();
+            let rows = block.table_rows();
+            let (cells, columns) = BlockLayout::table_cells(&rows);
+            let widths = block.column_widths(&cells, columns);
+            (
+                rows,
+                cells,
+                widths,
+                block.table_caption(),
+                block.content_x(),
+                block.content_y(),
+                block.content_width(),
+                block.font_manager.clone(),
+            )
+        };
+
+        // the caption spans the whole table, above the grid
+        if let Some(caption) = caption {
+            let caption_rc = BlockLayout::new(
+                caption,
+                Some(Rc::downgrade(&block_rc)),
+                None,
+                font_manager.clone(),
+            );
+            caption_rc.borrow_mut().fixed_geometry = Some((x, y, width));
+            BlockLayout::layout(caption_rc.clone());
+
+            y += caption_rc.borrow().height;
+            block_rc.borrow_mut().children.push(caption_rc);
+        }
+
+        // left edge of each column
+        let column_x: Vec<f32> = widths
+            .iter()
+            .scan(x + TABLE_CELL_SPACING, |cell_x, width| {
+                let left = *cell_x;
+                *cell_x += width + TABLE_CELL_SPACING;
+                Some(left)
+            })
+            .collect();
+
+        // cells laid out so far, with the last row they span
+        let mut laid_out: Vec<(BlockLayoutRef, usize)> = Vec::new();
+
+        for row in 0..rows.len() {
+            y += TABLE_CELL_SPACING;
+
+            for cell in cells.iter().filter(|cell| cell.row == row) {
+                let spanned = cell.column..cell.column + cell.colspan;
+                let cell_width = widths[spanned].iter().sum::<f32>()
+                    + TABLE_CELL_SPACING * (cell.colspan - 1) as f32;
+
+                let cell_rc = BlockLayout::new(
+                    cell.node.clone(),
+                    Some(Rc::downgrade(&block_rc)),
+                    None,
+                    font_manager.clone(),
+                );
+                cell_rc.borrow_mut().fixed_geometry = Some((column_x[cell.column], y, cell_width));
+                BlockLayout::layout(cell_rc.clone());
+
+                block_rc.borrow_mut().children.push(cell_rc.clone());
+                laid_out.push((cell_rc, row + cell.rowspan - 1));
+            }
+
+            // the row is as tall as the tallest cell ending in it, spanned cells included
+            let ending: Vec<&BlockLayoutRef> = laid_out
+                .iter()
+                .filter(|(_, last_row)| *last_row == row)
+                .map(|(cell, _)| cell)
+                .collect();
+            let row_bottom = ending
+                .iter()
+                .map(|cell| cell.borrow().y + cell.borrow().height)
+                .fold(y, f32::max);
+
+            // every cell stretches to the bottom of its last row
+            for cell in ending {
+                let top = cell.borrow().y;
+                cell.borrow_mut().height = row_bottom - top;
+            }
+
+            y = row_bottom;
+        }
+    }
+
+    pub fn layout(block_rc: BlockLayoutRef) {
+        {
+            let block = &mut *block_rc.borrow_mut();
+            let (x, y, width) = block.calc_pos_and_width();
+            let laid_out_at = block.laid_out_at.replace((x, y, width));
+
+            // a clean block as wide as before lays out the same, only where it starts can move
+            if let Some((old_x, old_y, old_width)) = laid_out_at
+                && old_width == width
+                && !block.node.borrow().dirty
+            {
+                let fixed_geometry = block.fixed_geometry;
+                block.translate(x - old_x, y - old_y);
+                block.fixed_geometry = fixed_geometry;
+                return;
+            }
+
+            block.x = x;
+            block.y = y;
+            block.width = width;
+            // a restyle can change whether the content is laid out in lines, the children of
+            // another mode aren't the blocks this one reuses
+            let mode = LayoutMode::new(block.node.clone());
+            if std::mem::discriminant(&mode) != std::mem::discriminant(&block.mode) {
+                block.children.clear();
+            }
+            block.mode = mode;
+            block.display_list.clear();
+            block.inputs.clear();
+
+            match &block.mode {
+                LayoutMode::Block => block.layout_block(block_rc.clone()),
+                LayoutMode::Inline => block.layout_inline(),
+                LayoutMode::Table => block.children.clear(),
+            }
+        }
+
+        let is_table = matches!(block_rc.borrow().mode, LayoutMode::Table);
+        let is_inline = matches!(block_rc.borrow().mode, LayoutMode::Inline);
+        if is_table {
+            // cells are laid out row by row, as each row's position depends on the previous one
+            BlockLayout::layout_table(block_rc.clone());
+        } else if !is_inline {
+            // inline layout's only children are inline-blocks, laid out as the lines placed them
+            for child_rc in &block_rc.borrow().children {
+                BlockLayout::layout(child_rc.clone());
+            }
+        }
+
+        {
+            let block = &mut *block_rc.borrow_mut();
+            block.height = block.calc_height();
+        }
+    }
+
+    /// The top of a node's box, or of its first word when it's laid out inline.
+    pub fn node_y(&self, node: &HTMLNodeRef) -> Option<f32> {
+        if Rc::ptr_eq(&self.node, node) {
+            return Some(self.y);
+        }
+
+        if let Some(item) = self
+            .display_list
+            .iter()
+            .find(|item| is_within(&item.node, node))
+        {
+            return Some(item.y);
+        }
+
+        self.children
+            .iter()
+            .find_map(|child| child.borrow().node_y(node))
+    }
+
+    /// The box around `node` in page coordinates, as painted: this block's if it is the node,
+    /// else around the words and inputs laid out for it, moved up by any scroll of the content.
+    pub fn node_rect(&self, node: &HTMLNodeRef) -> Option<Rect> {
+        if Rc::ptr_eq(&self.node, node) {
+            return Some(Rect::from_xywh(self.x, self.y, self.width, self.height));
+        }
+
+        let words = self
+            .display_list
+            .iter()
+            .filter(|item| is_within(&item.node, node))
+            .map(|item| {
+                let width = item.font.measure_str(&item.text, None).0;
+                Rect::from_xywh(item.x, item.y, width, item.font.spacing())
+            });
+        let inputs = self
+            .inputs
+            .iter()
+            .filter(|input| is_within(&input.node, node))
+            .map(|input| Rect::from_xywh(input.x, input.y, input.width, input.height));
+
+        words
+            .chain(inputs)
+            .reduce(|a, b| {
+                Rect::new(
+                    a.left.min(b.left),
+                    a.top.min(b.top),
+                    a.right.max(b.right),
+                    a.bottom.max(b.bottom),
+                )
+            })
+            .or_else(|| {
+                self.children
+                    .iter()
+                    .find_map(|child| child.borrow().node_rect(node))
+            })
+            .map(|rect| rect.with_offset((0.0, -self.scroll_offset())))
+    }
+
+    /// Adds the nodes this block and its children laid out boxes, words or inputs for.
+    pub fn laid_out_nodes(&self, nodes: &mut Vec<HTMLNodeRef>) {
+        nodes.push(self.node.clone());
+        nodes.extend(self.display_list.iter().map(|item| item.node.clone()));
+        nodes.extend(self.inputs.iter().map(|input| input.node.clone()));
+
+        for child in &self.children {
+            child.borrow().laid_out_nodes(nodes);
+        }
+    }
+
+    /// The most specific node at a point in page coordinates: the word under it in an inline
+    /// box, else the deepest block containing it.
+    pub fn hit_test(&self, x: f32, y: f32) -> Option<HTMLNodeRef> {
+        let content_y = y + self.scroll_offset();
+
+        // children can overflow their parent, e.g. fixed boxes
+        for child in self.children.iter().rev() {
+            if let Some(node) = child.borrow().hit_test(x, content_y) {
+                return Some(node);
+            }
+        }
+
+        if x < self.x || x > self.x + self.width || y < self.y || y > self.y + self.height {
+            return None;
+        }
+
+        let item = self.display_list.iter().find(|item| {
+            let width = item.font.measure_str(&item.text, None).0;
+            let bottom = item.y + item.font.spacing();
+            x >= item.x && x <= item.x + width && content_y >= item.y && content_y <= bottom
+        });
+
+        Some(item.map_or(self.node.clone(), |item| item.node.clone()))
+    }
+
+    /// The marker of a list item, numbered by its position among the `li` siblings, counting
+    /// from the list's `start` and jumping to any earlier item's `value`.
+    fn list_marker(&self) -> Option<String> {
+        let is_item = |node: &HTMLNodeRef| match &node.borrow().data {
+            HTMLNodeData::Element(e) => e.tag == LIST_ITEM,
+            _ => false,
+        };
+        if !is_item(&self.node) {
+            return None;
+        }
+
+        let number_attribute = |node: &HTMLNodeRef, key: &str| match &node.borrow().data {
+            HTMLNodeData::Element(e) => e
+                .attributes
+                .get(key)
+                .and_then(|v| v.trim().parse::<i64>().ok()),
+            _ => None,
+        };
+
+        let node = self.node.borrow();
+        let index = node
+            .parent
+            .as_ref()
+            .and_then(|parent| parent.upgrade())
+            .map_or(1, |parent| {
+                let mut counter = number_attribute(&parent, ATTRIBUTE_KEY_START).unwrap_or(1);
+                for sibling in parent.borrow().children.iter().filter(|s| is_item(s)) {
+                    if let Some(value) = number_attribute(sibling, ATTRIBUTE_KEY_VALUE) {
+                        counter = value;
+                    }
+                    if Rc::ptr_eq(sibling, &self.node) {
+                        break;
+                    }
+                    counter = counter.saturating_add(1);
+                }
+                counter
+            });
+
+        // nested lists get their circles and squares from the default stylesheet
+        let list_style_type = node.style.get(STYLE_KEY_LIST_STYLE_TYPE);
+        match list_style_type.map(|t| t.as_str()) {
+            Some(LIST_STYLE_NONE) => None,
+            Some(LIST_STYLE_CIRCLE) => Some(LIST_MARKER_CIRCLE.to_string()),
+            Some(LIST_STYLE_SQUARE) => Some(LIST_MARKER_SQUARE.to_string()),
+            Some(LIST_STYLE_DECIMAL) => Some(format!("{}{}", index, LIST_MARKER_DECIMAL_SUFFIX)),
+            _ => Some(LIST_MARKER_DISC.to_string()),
+        }
+    }
+
+    pub fn paint(&self) -> Vec<DrawCommand> {
+        let (mut cmds, content) = self.paint_parts();
+        cmds.extend(content);
+        cmds
+    }
+
+    /// The box's marker, background and borders, then its inline content, apart so the
+    /// content can scroll inside the box.
+    pub fn paint_parts(&self) -> (Vec<DrawCommand>, Vec<DrawCommand>) {
+        let mut cmds = Vec::new();
+
+        // the marker hangs left of the item, on its first line
+        if let Some(marker) = self.list_marker()
+            && !is_hidden(&self.node)
+        {
+            let font = self.get_font(&self.node);
+            let width = font.measure_str(&marker, None).0;
+            let baseline = self.content_y() - font.metrics().1.ascent;
+            cmds.push(DrawCommand::text(
+                self.x - width - LIST_MARKER_GAP,
+                self.content_y(),
+                baseline,
+                marker,
+                font,
+                &self.get_color(&self.node),
+                BACKGROUND_COLOR_DEFAULT_VALUE,
+            ));
+        }
+
+        if !is_hidden(&self.node)
+            && let Some(background_color) = self.node.borrow().style.get(STYLE_KEY_BACKGROUND_COLOR)
+            && DrawCommand::is_visible_color(background_color)
+        {
+            let x2 = self.x + self.width;
+            let y2 = self.y + self.height;
+            cmds.push(DrawCommand::rect(self.x, self.y, x2, y2, background_color));
+        }
+
+        let border = self.border_width();
+        if border > 0.0 && !is_hidden(&self.node) {
+            let color = self
+                .node
+                .borrow()
+                .style
+                .get(STYLE_KEY_BORDER_COLOR)
+                .cloned()
+                .unwrap_or_else(|| self.get_color(&self.node));
+            // strokes are centered on the rect, so keep the whole border inside the box
+            let half = border / 2.0;
+            cmds.push(DrawCommand::outline(
+                self.x + half,
+                self.y + half,
+                self.x + self.width - half,
+                self.y + self.height - half,
+                border,
+                &color,
+            ));
+        }
+
+        if let Some(size) = self.hr_size()
+            && !is_hidden(&self.node)
+        {
+            let y = self.y + self.height / 2.0;
+            let color = self.get_color(&self.node);
+            cmds.push(DrawCommand::line(
+                self.x,
+                y,
+                self.x + self.width,
+                y,
+                size,
+                &color,
+            ));
+        }
+
+        // let node = &*self.node.borrow();
+        // if let HTMLNodeData::Element(e) = &node.data
+        //     && e.tag == "pre"
+        // {
+        //     let x2 = self.x + self.width;
+        //     let y2 = self.y + self.height;
+        //     cmds.push(DrawCommand::rect(self.x, self.y, x2, y2, Color::GRAY));
+        // }
+
+        let opacity = effective_opacity(&self.node);
+        let cmds: Vec<DrawCommand> = cmds
+            .into_iter()
+            .map(|cmd| cmd.with_opacity(opacity))
+            .collect();
+
+        let mut content = Vec::new();
+        if let LayoutMode::Inline = self.mode {
+            // hidden words and inputs keep their place on the line, they just aren't painted
+            for item in self
+                .display_list
+                .iter()
+                .filter(|item| !is_hidden(&item.node))
+            {
+                content.push(
+                    DrawCommand::text(
+                        item.x,
+                        item.y,
+                        item.baseline,
+                        item.text.to_string(),
+                        item.font.clone(),
+                        &item.color,
+                        &item.background_color,
+                    )
+                    .with_letter_spacing(item.letter_spacing)
+                    .with_opacity(effective_opacity(&item.node)),
+                );
+            }
+
+            for input in self.inputs.iter().filter(|input| !is_hidden(&input.node)) {
+                let opacity = effective_opacity(&input.node);
+                if is_embedded(&input.node) {
+                    content.push(
+                        DrawCommand::rect(
+                            input.x,
+                            input.y,
+                            input.x + input.width,
+                            input.y + input.height,
+                            EMBED_PLACEHOLDER_COLOR,
+                        )
+                        .with_opacity(opacity),
+                    );
+                }
+                content.push(
+                    DrawCommand::outline(
+                        input.x,
+                        input.y,
+                        input.x + input.width,
+                        input.y + input.height,
+                        1.0,
+                        INPUT_BORDER_COLOR,
+                    )
+                    .with_opacity(opacity),
+                );
+
+                for item in &input.text {
+                    content.push(
+                        DrawCommand::text(
+                            item.x,
+                            item.y,
+                            item.baseline,
+                            item.text.to_string(),
+                            item.font.clone(),
+                            &item.color,
+                            &item.background_color,
+                        )
+                        .with_opacity(opacity),
+                    );
+                }
+            }
+        }
+
+        (cmds, content)
+    }
+
+    #[cfg(test)]
+    fn each_item(&self, f: &mut dyn FnMut(&DisplayItem)) {
+        self.display_list.iter().for_each(&mut *f);
+        for child in &self.children {
+            child.borrow().each_item(f);
+        }
+    }
+
+    pub fn print_tree(&self, depth: usize) {
+        let indent = "  ".repeat(depth);
+
+        println!("{}{}", indent, self);
+
+        for child in &self.children {
+            child.borrow().print_tree(depth + 1);
+        }
+    }
+}
+
+impl Display for BlockLayout {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(
+            f,
+            "BlockLayout[{}](x={}, y={}, width={}, height={}, node={})",
+            self.mode,
+            self.x,
+            self.y,
+            self.width,
+            self.height,
+            self.node.borrow()
+        )
+    }
+}
+
+/// Apply the node's computed `text-transform` to a single word.
+fn text_transform(word: &str, node: &HTMLNodeRef) -> String {
+    let node = node.borrow();
+    match node.style.get(STYLE_KEY_TEXT_TRANSFORM).map(|t| t.as_str()) {
+        Some("uppercase") => word.to_uppercase(),
+        Some("lowercase") => word.to_lowercase(),
+        Some("capitalize") => {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+        _ => word.to_string(),
+    }
+}
+
+/// The (size, weight, slant) of the node's font, the size scaled by `scale`.
+fn font_params(node: &HTMLNodeRef, scale: f32) -> (i32, Weight, Slant) {
+    let node = node.borrow();
+    let weight = parse_font_weight(node.style.get(STYLE_KEY_FONT_WEIGHT));
+    let style = parse_font_style(node.style.get(STYLE_KEY_FONT_STYLE));
+    let size = parse_font_size(node.style.get(STYLE_KEY_FONT_SIZE));
+    ((size as f32 * scale).round() as i32, weight, style)
+}
+
+fn breaks_all(node: &HTMLNodeRef) -> bool {
+    node.borrow()
+        .style
+        .get(STYLE_KEY_WORD_BREAK)
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case(WORD_BREAK_BREAK_ALL))
+}
+
+fn is_small_caps(node: &HTMLNodeRef) -> bool {
+    node.borrow()
+        .style
+        .get(STYLE_KEY_FONT_VARIANT)
+        .is_some_and(|variant| variant.trim().eq_ignore_ascii_case(FONT_VARIANT_SMALL_CAPS))
+}
+
+/// Split a word into runs of lowercase and other characters, the lowercase ones uppercased
+/// and flagged so they can be drawn smaller.
+fn small_caps_runs(word: &str) -> Vec<(String, bool)> {
+    let mut runs: Vec<(String, bool)> = Vec::new();
+    for c in word.chars() {
+        let lowered = c.is_lowercase();
+        match runs.last_mut() {
+            Some((run, run_lowered)) if *run_lowered == lowered => run.extend(c.to_uppercase()),
+            _ => runs.push((c.to_uppercase().collect(), lowered)),
+        }
+    }
+    runs
+}
+
+/// Whether `node` is `ancestor` or one of its descendants.
+fn is_within(node: &HTMLNodeRef, ancestor: &HTMLNodeRef) -> bool {
+    ancestors(node.clone()).any(|node| Rc::ptr_eq(&node, ancestor))
+}
+
+fn is_embedded(node: &HTMLNodeRef) -> bool {
+    match &node.borrow().data {
+        HTMLNodeData::Element(e) => EMBEDDED_ELEMENTS.contains(&e.tag.as_str()),
+        HTMLNodeData::Text(_) => false,
+    }
+}
+
+/// One to four values for the top, right, bottom and left edges, as CSS shorthands take them.
+fn edges(values: &[f32]) -> Edges {
+    match values[..] {
+        [all] => Edges {
+            top: all,
+            right: all,
+            bottom: all,
+            left: all,
+        },
+        [vertical, horizontal] => Edges {
+            top: vertical,
+            right: horizontal,
+            bottom: vertical,
+            left: horizontal,
+        },
+        [top, horizontal, bottom] => Edges {
+            top,
+            right: horizontal,
+            bottom,
+            left: horizontal,
+        },
+        [top, right, bottom, left] => Edges {
+            top,
+            right,
+            bottom,
+            left,
+        },
+        _ => Edges::default(),
+    }
+}
+
+/// Padding plus border on each side. `padding` takes one to four px values like CSS,
+/// `border-width` a single one.
+fn node_insets(node: &HTMLNodeRef) -> Edges {
+    let node_ref = node.borrow();
+    let values: Vec<f32> = node_ref
+        .style
+        .get(STYLE_KEY_PADDING)
+        .map(|padding| {
+            padding
+                .split_whitespace()
+                .map(|value| node_length(&node_ref, value).unwrap_or(0.0))
+                .collect()
+        })
+        .unwrap_or_default();
+    let padding = edges(&values);
+
+    let border = node_border_width(node);
+    Edges {
+        top: padding.top + border,
+        right: padding.right + border,
+        bottom: padding.bottom + border,
+        left: padding.left + border,
+    }
+}
+
+fn node_border_width(node: &HTMLNodeRef) -> f32 {
+    let node = node.borrow();
+    node.style
+        .get(STYLE_KEY_BORDER_WIDTH)
+        .and_then(|width| node_length(&node, width))
+        .unwrap_or(0.0)
+        .max(0.0)
+}
+
+/// `margin` in px with its longhands on top, `auto` and other units count as 0.
+fn margins(node: &HTMLNodeRef) -> Edges {
+    let node = node.borrow();
+    let values: Vec<f32> = node
+        .style
+        .get(STYLE_KEY_MARGIN)
+        .map(|margin| {
+            margin
+                .split_whitespace()
+                .map(|value| node_length(&node, value).unwrap_or(0.0))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut margins = edges(&values);
+
+    let side = |key: &str| {
+        node.style
+            .get(key)
+            .map(|value| node_length(&node, value).unwrap_or(0.0))
+    };
+    if let Some(top) = side(STYLE_KEY_MARGIN_TOP) {
+        margins.top = top;
+    }
+    if let Some(right) = side(STYLE_KEY_MARGIN_RIGHT) {
+        margins.right = right;
+    }
+    if let Some(bottom) = side(STYLE_KEY_MARGIN_BOTTOM) {
+        margins.bottom = bottom;
+    }
+    if let Some(left) = side(STYLE_KEY_MARGIN_LEFT) {
+        margins.left = left;
+    }
+    margins
+}
+
+/// Two adjoining margins: the larger when both are positive, the more negative when both are
+/// negative, otherwise they add up.
+fn collapse_margins(a: f32, b: f32) -> f32 {
+    if a >= 0.0 && b >= 0.0 {
+        a.max(b)
+    } else if a < 0.0 && b < 0.0 {
+        a.min(b)
+    } else {
+        a + b
+    }
+}
+
+/// Whether a block's top margin touches its first child's, i.e. no padding or border between.
+fn collapses_with_first_child(node: &HTMLNodeRef) -> bool {
+    matches!(LayoutMode::new(node.clone()), LayoutMode::Block) && node_insets(node).top == 0.0
+}
+
+/// The top margin a block is placed by, merged with those of the first children it collapses with.
+fn collapsed_top_margin(node: &HTMLNodeRef) -> f32 {
+    let top = margins(node).top;
+    if !collapses_with_first_child(node) {
+        return top;
+    }
+
+    let first_child = node
+        .borrow()
+        .children
+        .iter()
+        .find(|child| {
+            !is_inert(child)
+                && child
+                    .borrow()
+                    .style
+                    .get(STYLE_KEY_POSITION)
+                    .is_none_or(|position| position != POSITION_FIXED)
+        })
+        .cloned();
+
+    match first_child {
+        Some(child) => collapse_margins(top, collapsed_top_margin(&child)),
+        None => top,
+    }
+}
+
+/// Elements with `hidden`, and templates with their content, take no room and aren't painted.
+fn is_inert(node: &HTMLNodeRef) -> bool {
+    match &node.borrow().data {
+        HTMLNodeData::Element(e) => {
+            e.attributes.contains_key(ATTRIBUTE_KEY_HIDDEN) || e.tag == TEMPLATE
+        }
+        HTMLNodeData::Text(_) => false,
+    }
+}
+
+/// Whether the nearest `dir` on the node or its ancestors is `rtl`.
+fn is_rtl(node: &HTMLNodeRef) -> bool {
+    ancestors(node.clone())
+        .find_map(|node| match &node.borrow().data {
+            HTMLNodeData::Element(e) => e
+                .attributes
+                .get(ATTRIBUTE_KEY_DIR)
+                .map(|dir| dir.eq_ignore_ascii_case(DIR_RTL)),
+            HTMLNodeData::Text(_) => None,
+        })
+        .unwrap_or(false)
+}
+
+/// The nearest `bdo` the node is in, when it overrides the direction to rtl.
+fn rtl_override(node: &HTMLNodeRef) -> Option<HTMLNodeRef> {
+    ancestors(node.clone())
+        .find(|node| matches!(&node.borrow().data, HTMLNodeData::Element(e) if e.tag == BDO))
+        .filter(is_rtl)
+}
+
+/// The `vertical-align` of the nearest inline box around the node within `block` that isn't
+/// on the baseline, `None` when all of them are.
+fn vertical_align(node: &HTMLNodeRef, block: &HTMLNodeRef) -> Option<&'static str> {
+    ancestors(node.clone())
+        .take_while(|node| !Rc::ptr_eq(node, block))
+        .find_map(|node| {
+            let value = node
+                .borrow()
+                .style
+                .get(STYLE_KEY_VERTICAL_ALIGN)?
+                .trim()
+                .to_lowercase();
+            LINE_BOX_ALIGNS.into_iter().find(|align| *align == value)
+        })
+}
+
+fn is_hidden(node: &HTMLNodeRef) -> bool {
+    node.borrow()
+        .style
+        .get(STYLE_KEY_VISIBILITY)
+        .is_some_and(|visibility| visibility == VISIBILITY_HIDDEN)
+}
+
+/// `opacity` isn't inherited, but a node is painted through every ancestor's, so they multiply.
+fn effective_opacity(node: &HTMLNodeRef) -> f32 {
+    ancestors(node.clone())
+        .filter_map(|node| {
+            let value = node
+                .borrow()
+                .style
+                .get(STYLE_KEY_OPACITY)?
+                .trim()
+                .parse::<f32>();
+            value.ok()
+        })
+        .map(|value| value.clamp(0.0, 1.0))
+        .product()
+}
+
+/// `letter-spacing`/`word-spacing` in px, `normal` and anything unparsable is no extra space.
+fn styled_spacing(node: &HTMLNodeRef, key: &str) -> f32 {
+    let node = node.borrow();
+    node.style
+        .get(key)
+        .and_then(|value| node_length(&node, value))
+        .unwrap_or(0.0)
+}
+
+/// What `node`'s lengths resolve against, `em` being its computed font size.
+fn length_context(node: &HTMLNode) -> LengthContext {
+    LengthContext {
+        font_size: parse_font_size(node.style.get(STYLE_KEY_FONT_SIZE)) as f32,
+        percent_base: None,
+    }
+}
+
+/// A length style of `node` in px, none for keywords like `auto` and junk.
+fn node_length(node: &HTMLNode, value: &str) -> Option<f32> {
+    parse_length(value, &length_context(node))
 }
 
-impl Display for BlockLayout {
-    fn fmt(&self, f: &mut Formatter) -> Result {
-        write!(
-            f,
-            "BlockLayout[{}](x={}, y={}, width={}, height={}, node={})",
-            self.mode,
-            self.x,
-            self.y,
-            self.width,
-            self.height,
-            self.node.borrow()
-        )
+/// Wrap the words of an input box inside its padding, dropping any that overflow the rows.
+fn layout_input_text(input: &InputBox, x: f32, y: f32) -> Vec<DisplayItem> {
+    let mut items = Vec::new();
+
+    let inner_w = input.width - 2.0 * INPUT_PADDING;
+    let ascent = -input.font.metrics().1.ascent;
+    let space_w = input.font.measure_str(" ", None).0;
+
+    let mut cursor_x = 0.0;
+    let mut row = 0;
+
+    for word in &input.words {
+        let w = input.font.measure_str(word, None).1.width();
+
+        if cursor_x > 0.0 && cursor_x + w > inner_w {
+            cursor_x = 0.0;
+            row += 1;
+        }
+
+        if row >= input.rows {
+            break;
+        }
+
+        let top = y + INPUT_PADDING + row as f32 * input.font.spacing();
+        items.push(DisplayItem {
+            x: x + INPUT_PADDING + cursor_x,
+            y: top,
+            baseline: top + ascent,
+            text: word.to_string(),
+            font: input.font.clone(),
+            color: input.color.to_string(),
+            background_color: BACKGROUND_COLOR_DEFAULT_VALUE.to_string(),
+            letter_spacing: 0.0,
+            node: input.node.clone(),
+        });
+
+        cursor_x += w + space_w;
+    }
+
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constant::layout::READER_MAX_WIDTH;
+    use crate::layout::document_layout::{DocumentLayout, DocumentLayoutRef};
+    use crate::parser::css_parser::CSSParser;
+    use crate::parser::html_parser::{HTMLParser, walk_tree};
+    use crate::parser::selector::cascade_priority;
+    use crate::parser::style::style;
+    use std::collections::HashSet;
+
+    fn collect_inputs(block_rc: &BlockLayoutRef, out: &mut Vec<(f32, f32)>) {
+        let block = block_rc.borrow();
+        out.extend(block.inputs.iter().map(|i| (i.width, i.height)));
+        for child in &block.children {
+            collect_inputs(child, out);
+        }
+    }
+
+    fn find_table(block_rc: &BlockLayoutRef) -> Option<BlockLayoutRef> {
+        if let LayoutMode::Table = block_rc.borrow().mode {
+            return Some(block_rc.clone());
+        }
+        block_rc.borrow().children.iter().find_map(find_table)
+    }
+
+    /// Parses `html`, styles it with the rules of `css` in cascade order and lays it out.
+    /// Without `css` the style pass is skipped, leaving every node unstyled.
+    fn layout_document(html: &str, css: Option<&str>) -> DocumentLayoutRef {
+        let node = HTMLParser::new(html.to_string()).parse().unwrap();
+        if let Some(css) = css {
+            let mut rules = CSSParser::new(css).parse().unwrap();
+            rules.sort_by_key(|rule| cascade_priority(rule));
+            style(node.clone(), &rules, &HashSet::new());
+        }
+
+        let document = DocumentLayout::new(node);
+        document.borrow_mut().layout();
+        document
+    }
+
+    fn layout_inputs(html: &str) -> Vec<(f32, f32)> {
+        let document = layout_document(html, None);
+
+        let mut inputs = Vec::new();
+        collect_inputs(document.borrow().child.as_ref().unwrap(), &mut inputs);
+        inputs
+    }
+
+    #[test]
+    fn test_input_reserves_box() {
+        let inputs = layout_inputs("<p>Name: <input size=10></p>");
+        assert_eq!(inputs.len(), 1);
+
+        let (width, height) = inputs[0];
+        assert!(width > 0.0, "input should reserve width");
+        assert!(height > 0.0, "input should reserve height");
+    }
+
+    #[test]
+    fn test_textarea_taller_than_input() {
+        let inputs = layout_inputs("<p><input><textarea rows=4>hello world</textarea></p>");
+        assert_eq!(inputs.len(), 2);
+        assert!(inputs[1].1 > inputs[0].1);
+    }
+
+    #[test]
+    fn test_table_cells_form_grid() {
+        let document = layout_document(
+            "<table><tr><td>a</td><td>b</td></tr><tr><td>c</td><td>d</td></tr></table>",
+            None,
+        );
+
+        let table = find_table(document.borrow().child.as_ref().unwrap()).expect("Missing table");
+        let cells: Vec<(f32, f32)> = table
+            .borrow()
+            .children
+            .iter()
+            .map(|cell| (cell.borrow().x, cell.borrow().y))
+            .collect();
+        assert_eq!(cells.len(), 4);
+
+        // same row shares y, same column shares x
+        assert_eq!(cells[0].1, cells[1].1);
+        assert_eq!(cells[2].1, cells[3].1);
+        assert_eq!(cells[0].0, cells[2].0);
+        assert_eq!(cells[1].0, cells[3].0);
+        assert!(cells[1].0 > cells[0].0);
+        assert!(cells[2].1 > cells[0].1);
+    }
+
+    #[test]
+    fn test_table_colspan_and_rowspan() {
+        let document = layout_document(
+            "<table><tr><th colspan=2>head</th></tr><tr><td rowspan=2>a</td><td>b</td></tr><tr><td>c</td></tr></table>",
+            None,
+        );
+
+        let table = find_table(document.borrow().child.as_ref().unwrap()).expect("Missing table");
+        let table = table.borrow();
+        let cell = |i: usize| {
+            let cell = table.children[i].borrow();
+            (cell.x, cell.y, cell.width, cell.height)
+        };
+        assert_eq!(table.children.len(), 4);
+        let (head, a, b, c) = (cell(0), cell(1), cell(2), cell(3));
+
+        // the header covers both columns and the spacing between them
+        assert_eq!(head.0, a.0);
+        assert_eq!(head.2, a.2 + TABLE_CELL_SPACING + b.2);
+
+        // c shifts into the second column, next to the rowspan
+        assert_eq!(c.0, b.0);
+        assert!(c.1 > b.1);
+        assert_eq!(a.1, b.1);
+        assert_eq!(a.1 + a.3, c.1 + c.3);
+    }
+
+    #[test]
+    fn test_table_caption_above_grid() {
+        let document = layout_document(
+            "<table><caption>Totals</caption><colgroup><col width=100><col></colgroup><tr><td>a</td><td>b</td></tr></table>",
+            None,
+        );
+
+        let table = find_table(document.borrow().child.as_ref().unwrap()).expect("Missing table");
+        let table = table.borrow();
+        assert_eq!(table.children.len(), 3);
+
+        let caption = table.children[0].borrow();
+        let first = table.children[1].borrow();
+        let second = table.children[2].borrow();
+
+        assert_eq!(caption.width, table.width);
+        assert!(caption.height > 0.0);
+        assert!(first.y >= caption.y + caption.height);
+        assert_eq!(first.y, second.y);
+
+        // the col width seeds the first column
+        assert!(first.width >= 100.0);
+        assert!(second.x > first.x + 100.0);
+    }
+
+    #[test]
+    fn test_table_caption_without_rows() {
+        let document = layout_document(
+            "<table><caption>Empty</caption><colgroup></colgroup></table>",
+            None,
+        );
+
+        let table = find_table(document.borrow().child.as_ref().unwrap()).expect("Missing table");
+        assert_eq!(table.borrow().children.len(), 1);
+        assert!(table.borrow().height > 0.0);
+    }
+
+    #[test]
+    fn test_inline_background_propagates() {
+        let document = layout_document(
+            "<p><span style=\"background-color:yellow\">hi</span> there</p>",
+            Some(""),
+        );
+
+        fn collect_items(block_rc: &BlockLayoutRef, out: &mut Vec<(String, String)>) {
+            let block = block_rc.borrow();
+            out.extend(
+                block
+                    .display_list
+                    .iter()
+                    .map(|i| (i.text.to_string(), i.background_color.to_string())),
+            );
+            for child in &block.children {
+                collect_items(child, out);
+            }
+        }
+
+        let mut items = Vec::new();
+        collect_items(document.borrow().child.as_ref().unwrap(), &mut items);
+        assert!(items.contains(&("hi".to_string(), "yellow".to_string())));
+        assert!(items.contains(&(
+            "there".to_string(),
+            BACKGROUND_COLOR_DEFAULT_VALUE.to_string()
+        )));
+    }
+
+    #[test]
+    fn test_percentage_and_px_width() {
+        let document = layout_document(
+            "<div style=\"width:50%\"><p style=\"width:100px\">a</p></div><div style=\"width:auto\">b</div>",
+            Some(""),
+        );
+
+        fn collect_widths(block_rc: &BlockLayoutRef, out: &mut Vec<(String, f32)>) {
+            let block = block_rc.borrow();
+            if let HTMLNodeData::Element(e) = &block.node.borrow().data {
+                out.push((e.tag.clone(), block.width));
+            }
+            for child in &block.children {
+                collect_widths(child, out);
+            }
+        }
+
+        let mut widths = Vec::new();
+        collect_widths(document.borrow().child.as_ref().unwrap(), &mut widths);
+        let divs: Vec<f32> = widths
+            .iter()
+            .filter(|(t, _)| t == "div")
+            .map(|(_, w)| *w)
+            .collect();
+
+        assert_eq!(DEFAULT_WIDTH, 760.0);
+        assert_eq!(divs, vec![380.0, DEFAULT_WIDTH]);
+        assert!(widths.contains(&("p".to_string(), 100.0)));
+    }
+
+    #[test]
+    fn test_visibility_hidden_reserves_space() {
+        let document = layout_document(
+            "<p>a</p><p style=\"visibility:hidden;background-color:red\">hidden <span style=\"visibility:visible\">shown</span></p><p>c</p>",
+            Some(""),
+        );
+
+        let document = document.borrow();
+        let body = document.child.as_ref().unwrap().borrow().children[0].clone();
+        let body = body.borrow();
+        let (a, hidden, c) = (
+            body.children[0].borrow(),
+            body.children[1].borrow(),
+            body.children[2].borrow(),
+        );
+
+        assert!(hidden.height > 0.0);
+        assert_eq!(c.y, a.y + a.height + hidden.height);
+
+        // only the visible descendant is painted
+        let painted: Vec<String> = hidden.paint().iter().map(|cmd| cmd.to_string()).collect();
+        assert_eq!(painted.len(), 1);
+        assert!(painted[0].contains("text=shown"));
+    }
+
+    fn layout_white_space(white_space: &str, text: &str) -> Vec<(String, f32, f32)> {
+        let html = format!("<p style=\"white-space:{}\">{}</p>", white_space, text);
+        let document = layout_document(&html, Some(""));
+
+        let mut items = Vec::new();
+        document
+            .borrow()
+            .child
+            .as_ref()
+            .unwrap()
+            .borrow()
+            .each_item(&mut |item| items.push((item.text.to_string(), item.x, item.y)));
+        items
+    }
+
+    #[test]
+    fn test_text_overflow_ellipsis() {
+        let document = layout_document(
+            &format!("<p>{}</p>", "word ".repeat(50)),
+            Some(
+                "p { width: 100px; overflow: hidden; white-space: nowrap; text-overflow: ellipsis }",
+            ),
+        );
+
+        let mut items = Vec::new();
+        document
+            .borrow()
+            .child
+            .as_ref()
+            .unwrap()
+            .borrow()
+            .each_item(&mut |item| {
+                let right = item.x + item.font.measure_str(&item.text, None).1.width();
+                items.push((item.text.to_string(), item.y, right))
+            });
+
+        // one line, cut short inside the box
+        assert!(items.len() < 50);
+        assert!(items.iter().all(|(_, y, _)| *y == items[0].1));
+        let (last, _, right) = items.last().unwrap();
+        assert!(last.ends_with(ELLIPSIS), "{}", last);
+        assert!(*right <= DEFAULT_X + 100.0);
+    }
+
+    #[test]
+    fn test_wbr_breaks_only_when_needed() {
+        let layout = |width: &str| {
+            let html = format!("<p style=\"width:{}\">foo<wbr>barbaz</p>", width);
+            let document = layout_document(&html, Some(""));
+
+            let mut items = Vec::new();
+            document
+                .borrow()
+                .child
+                .as_ref()
+                .unwrap()
+                .borrow()
+                .each_item(&mut |item| {
+                    let width = item.font.measure_str(&item.text, None).1.width();
+                    items.push((item.text.to_string(), item.x, item.y, width))
+                });
+            items
+        };
+
+        // with room the halves join up without a space
+        let wide = layout("300px");
+        assert_eq!(wide.len(), 2);
+        assert_eq!(wide[1].1, wide[0].1 + wide[0].3);
+        assert_eq!(wide[1].2, wide[0].2);
+
+        // too narrow, the line breaks at the wbr
+        let narrow = layout("1px");
+        assert_eq!(narrow[0].0, "foo");
+        assert_eq!(narrow[1].0, "barbaz");
+        assert_eq!(narrow[1].1, narrow[0].1);
+        assert!(narrow[1].2 > narrow[0].2);
+    }
+
+    #[test]
+    fn test_word_break_break_all() {
+        let layout = |word_break: &str| {
+            let html = format!(
+                "<p style=\"width:60px;word-break:{}\">see averyveryverylongidentifier</p>",
+                word_break
+            );
+            let document = layout_document(&html, Some(""));
+
+            let mut items = Vec::new();
+            document
+                .borrow()
+                .child
+                .as_ref()
+                .unwrap()
+                .borrow()
+                .each_item(&mut |item| {
+                    let width = item.font.measure_str(&item.text, None).1.width();
+                    items.push((item.text.to_string(), item.x, item.y, item.x + width))
+                });
+            items
+        };
+
+        // normally the long word overflows on a line of its own
+        let normal = layout("normal");
+        assert_eq!(normal.len(), 2);
+        assert!(normal[1].2 > normal[0].2);
+
+        // break-all fills the first line up and splits the word over several
+        let broken = layout("break-all");
+        assert!(broken.len() > 3, "{:?}", broken);
+        assert_eq!(broken[0].0, "see");
+        assert_eq!(broken[1].2, broken[0].2);
+        let rest: String = broken[1..].iter().map(|(text, ..)| text.as_str()).collect();
+        assert_eq!(rest, "averyveryverylongidentifier");
+        for pair in broken[1..].windows(2) {
+            assert_eq!(pair[1].1, broken[0].1);
+            assert!(pair[1].2 > pair[0].2);
+        }
+        let left = broken[0].1;
+        assert!(broken.iter().all(|(.., right)| *right <= left + 60.0));
+    }
+
+    #[test]
+    fn test_before_and_after_content() {
+        let document = layout_document(
+            "<p>hello</p><div>plain</div>",
+            Some("p::before { content: \"X\" } p:after { content: '!' }"),
+        );
+
+        let mut words = Vec::new();
+        document
+            .borrow()
+            .child
+            .as_ref()
+            .unwrap()
+            .borrow()
+            .each_item(&mut |item| words.push(item.text.to_string()));
+        assert_eq!(words, vec!["X", "hello", "!", "plain"]);
+    }
+
+    #[test]
+    fn test_background_shorthand_color() {
+        let document = layout_document(
+            "<p>x</p>",
+            Some("p { background: url(dots.png) no-repeat top left red }"),
+        );
+
+        let html = document.borrow().child.clone().unwrap();
+        let body = html.borrow().children[0].clone();
+        let p = body.borrow().children[0].clone();
+        let rects: Vec<String> = p
+            .borrow()
+            .paint()
+            .iter()
+            .filter(|cmd| matches!(cmd, DrawCommand::Rect(_)))
+            .map(|cmd| cmd.to_string())
+            .collect();
+        assert_eq!(rects.len(), 1);
+        assert!(rects[0].ends_with("color=red)"), "{}", rects[0]);
+    }
+
+    #[test]
+    fn test_reader_mode_centers_the_root() {
+        let document = layout_document("<p>x</p>", Some(""));
+        let root = document.borrow().child.clone().unwrap();
+        assert_eq!(
+            (root.borrow().x, root.borrow().width),
+            (DEFAULT_X, DEFAULT_WIDTH)
+        );
+
+        // the viewport is wider than the cap, so the margins are split evenly
+        document.borrow_mut().set_max_width(Some(READER_MAX_WIDTH));
+        document.borrow_mut().layout();
+        let root = document.borrow().child.clone().unwrap();
+        let root = root.borrow();
+        assert_eq!(root.width, READER_MAX_WIDTH);
+        assert_eq!(
+            root.x - DEFAULT_X,
+            DEFAULT_X + DEFAULT_WIDTH - (root.x + root.width)
+        );
+        assert_eq!(root.y, DEFAULT_Y);
+        let body = root.children[0].borrow();
+        assert_eq!((body.x, body.width), (root.x, READER_MAX_WIDTH));
+    }
+
+    #[test]
+    fn test_small_caps() {
+        assert_eq!(
+            small_caps_runs("McDonald"),
+            vec![
+                ("M".to_string(), false),
+                ("C".to_string(), true),
+                ("D".to_string(), false),
+                ("ONALD".to_string(), true),
+            ]
+        );
+
+        let document = layout_document("<p style=font-variant:small-caps>Hello</p>", Some(""));
+
+        let mut items = Vec::new();
+        document
+            .borrow()
+            .child
+            .as_ref()
+            .unwrap()
+            .borrow()
+            .each_item(&mut |item| items.push((item.text.to_string(), item.font.size(), item.x)));
+        assert_eq!(items.len(), 2);
+        assert_eq!((items[0].0.as_str(), items[1].0.as_str()), ("H", "ELLO"));
+        assert!(items[1].1 < items[0].1);
+        // the runs sit next to each other as one word
+        assert!(items[1].2 > items[0].2);
+    }
+
+    #[test]
+    fn test_bdo_reverses_its_run() {
+        let html = "<p>one <bdo dir=rtl>two three</bdo> four</p><p dir=rtl>five</p>";
+        let document = layout_document(html, Some(""));
+
+        let mut items = Vec::new();
+        document
+            .borrow()
+            .child
+            .as_ref()
+            .unwrap()
+            .borrow()
+            .each_item(&mut |item| items.push((item.text.to_string(), item.x)));
+        let x = |word: &str| items.iter().find(|(text, _)| text == word).unwrap().1;
+
+        // the override reverses the words and their characters, the rest keeps its order
+        assert!(x("one") < x("eerht"));
+        assert!(x("eerht") < x("owt"));
+        assert!(x("owt") < x("four"));
+
+        // an rtl paragraph starts at the right edge
+        assert!(x("five") > DEFAULT_X + DEFAULT_WIDTH / 2.0);
+    }
+
+    #[test]
+    fn test_vertical_align_top() {
+        let items = |html: &str| {
+            let document = layout_document(html, Some(""));
+
+            let mut items = Vec::new();
+            document
+                .borrow()
+                .child
+                .as_ref()
+                .unwrap()
+                .borrow()
+                .each_item(&mut |item| items.push((item.text.to_string(), item.y)));
+            items
+        };
+        let y = |items: &[(String, f32)], word: &str| {
+            items.iter().find(|(text, _)| text == word).unwrap().1
+        };
+
+        // on the baseline the small word starts lower than the big one
+        let baseline = items(
+            "<p><span style=font-size:40px>big</span> <span style=font-size:10px>small</span></p>",
+        );
+        assert!(y(&baseline, "small") > y(&baseline, "big") + 10.0);
+
+        let top = items(
+            "<p><span style=font-size:40px;vertical-align:top>big</span> \
+             <span style=font-size:10px;vertical-align:top>small</span></p>",
+        );
+        assert_eq!(y(&top, "small"), y(&top, "big"));
+    }
+
+    #[test]
+    fn test_white_space_pre_line() {
+        let collapsed = layout_white_space("normal", "a b\nc d");
+        let items = layout_white_space("pre-line", "a    b\nc  d");
+        let words: Vec<&str> = items.iter().map(|(text, _, _)| text.as_str()).collect();
+        assert_eq!(words, vec!["a", "b", "c", "d"]);
+
+        // spaces collapse to one
+        assert_eq!(items[1].1, collapsed[1].1);
+        assert_eq!(items[1].2, items[0].2);
+        // the newline is kept
+        assert!(items[2].2 > items[1].2);
+        assert_eq!(items[2].1, items[0].1);
+        assert_eq!(collapsed[2].2, collapsed[0].2);
+    }
+
+    #[test]
+    fn test_white_space_pre_wrap() {
+        let items = layout_white_space("pre-wrap", "a  b\n\nc");
+        let words: Vec<&str> = items.iter().map(|(text, _, _)| text.as_str()).collect();
+        assert_eq!(words, vec!["a", "b", "c"]);
+
+        // every space is kept
+        let gap = |spaces: usize| {
+            let items = layout_white_space("pre-wrap", &format!("a{}b", " ".repeat(spaces)));
+            items[1].1 - items[0].1
+        };
+        let space = gap(2) - gap(1);
+        assert!(space > 0.0);
+        assert!((gap(4) - gap(1) - 3.0 * space).abs() < 0.01);
+
+        // the blank line still takes up room
+        let single = layout_white_space("pre-wrap", "a\nc");
+        assert!(items[2].2 - items[0].2 > 1.5 * (single[1].2 - single[0].2));
+
+        // long lines still wrap, unlike `pre`
+        let long = "word ".repeat(500);
+        let lines = |items: Vec<(String, f32, f32)>| {
+            let mut ys: Vec<f32> = items.iter().map(|(_, _, y)| *y).collect();
+            ys.dedup();
+            ys.len()
+        };
+        assert!(lines(layout_white_space("pre-wrap", &long)) > 1);
+        assert_eq!(lines(layout_white_space("pre", &long)), 1);
+    }
+
+    #[test]
+    fn test_iframe_placeholder() {
+        let document = layout_document(
+            "<p>a <iframe src=\"https://example.org/\" width=200 height=100></iframe> b</p><p>c</p>",
+            Some(""),
+        );
+
+        let document = document.borrow();
+        let body = document.child.as_ref().unwrap().borrow().children[0].clone();
+        let body = body.borrow();
+        let (p, next) = (body.children[0].borrow(), body.children[1].borrow());
+
+        // the frame's box sets the line's height
+        assert_eq!(p.inputs.len(), 1);
+        let frame = &p.inputs[0];
+        assert_eq!((frame.width, frame.height), (200.0, 100.0));
+        assert!(p.height >= 100.0);
+        assert!(next.y >= frame.y + 100.0);
+
+        let painted: Vec<String> = p.paint().iter().map(|cmd| cmd.to_string()).collect();
+        assert!(painted.iter().any(|cmd| cmd.starts_with("DrawRect(")
+            && cmd.contains(&format!("left={} ", frame.x))
+            && cmd.contains("color=lightgray")));
+        assert!(
+            painted
+                .iter()
+                .any(|cmd| cmd.contains("text=https://example.org/)"))
+        );
+    }
+
+    #[test]
+    fn test_hidden_attribute() {
+        let document = layout_document(
+            "<p>a</p><p hidden>x</p><p>b <span hidden>y</span>c</p>",
+            Some(""),
+        );
+
+        let mut words = Vec::new();
+        let document = document.borrow();
+        let html = document.child.as_ref().unwrap().borrow();
+        html.each_item(&mut |item| words.push(item.text.to_string()));
+        assert_eq!(words, vec!["a", "b", "c"]);
+
+        // the hidden paragraph has no box, so the next one follows the first directly
+        let body = html.children[0].borrow();
+        assert_eq!(body.children.len(), 2);
+        let (a, b) = (body.children[0].borrow(), body.children[1].borrow());
+        assert_eq!(b.y, a.y + a.height);
+    }
+
+    #[test]
+    fn test_opacity_multiplies_alpha() {
+        let document = layout_document(
+            "<p style=\"opacity:0.5;background-color:red\">a <span style=\"opacity:0.5\">b</span></p><p style=\"opacity:2\">c</p>",
+            Some(""),
+        );
+
+        let document = document.borrow();
+        let body = document.child.as_ref().unwrap().borrow().children[0].clone();
+        let body = body.borrow();
+        let alphas = |block: &BlockLayout| -> Vec<u8> {
+            block
+                .paint()
+                .iter()
+                .map(|cmd| cmd.painted_color().unwrap().a())
+                .collect()
+        };
+
+        // background, then "a" at half alpha and the nested "b" at a quarter
+        assert_eq!(alphas(&body.children[0].borrow()), vec![128, 128, 64]);
+        // out of range values clamp to fully opaque
+        assert_eq!(alphas(&body.children[1].borrow()), vec![255]);
+    }
+
+    #[test]
+    fn test_hr_paints_a_rule() {
+        let document = layout_document(
+            "<p>a</p><hr><hr size=5 style=\"color:red\"><p>b</p>",
+            Some(""),
+        );
+
+        let document = document.borrow();
+        let body = document.child.as_ref().unwrap().borrow().children[0].clone();
+        let body = body.borrow();
+
+        let rule = |i: usize| {
+            let hr = body.children[i].borrow();
+            let cmds = hr.paint();
+            assert_eq!(cmds.len(), 1);
+            let cmd = &cmds[0];
+            assert!(matches!(cmd, DrawCommand::Line(_)));
+            assert!(cmd.get_top() > hr.y && cmd.get_bottom() < hr.y + hr.height);
+            (cmd.get_bottom() - cmd.get_top(), cmd.to_string())
+        };
+
+        let (size, _) = rule(1);
+        assert_eq!(size, HR_DEFAULT_SIZE);
+        assert_eq!(body.children[1].borrow().width, DEFAULT_WIDTH);
+
+        let (size, line) = rule(2);
+        assert_eq!(size, 5.0);
+        assert!(line.contains("color=red"));
+
+        // the paragraph after the rules is pushed down by their reserved height
+        assert!(body.children[3].borrow().y > body.children[0].borrow().y + 2.0 * HR_MARGIN);
+    }
+
+    #[test]
+    fn test_box_sizing() {
+        let document = layout_document(
+            "<div style=\"width:200px;padding:10px;border-width:2px\"><p>a</p></div><div style=\"width:200px;padding:10px;border-width:2px;box-sizing:border-box\"><p>b</p></div>",
+            Some(""),
+        );
+
+        let document = document.borrow();
+        let body = document.child.as_ref().unwrap().borrow().children[0].clone();
+        let body = body.borrow();
+        let (content_box, border_box) = (body.children[0].borrow(), body.children[1].borrow());
+
+        // content-box grows the outer box around the declared width
+        assert_eq!(content_box.width, 224.0);
+        assert_eq!(content_box.content_width(), 200.0);
+        // border-box keeps the declared width and shrinks the content
+        assert_eq!(border_box.width, 200.0);
+        assert_eq!(border_box.content_width(), 176.0);
+
+        // children sit inside the padding and border
+        let p = border_box.children[0].borrow();
+        assert_eq!((p.x, p.width), (border_box.x + 12.0, 176.0));
+        assert_eq!(p.y, border_box.y + 12.0);
+        assert_eq!(border_box.height, p.height + 24.0);
+    }
+
+    #[test]
+    fn test_height_bounds() {
+        let document = layout_document(
+            "<p style=\"height:100px\">a</p><div style=\"min-height:50px\">b</div><div style=\"max-height:5px\">c</div><div style=\"height:100px;max-height:40px\">d</div><div style=\"max-height:10px;min-height:30px\">e</div>",
+            Some(""),
+        );
+
+        let document = document.borrow();
+        let body = document.child.as_ref().unwrap().borrow().children[0].clone();
+        let heights: Vec<f32> = body
+            .borrow()
+            .children
+            .iter()
+            .map(|child| child.borrow().height)
+            .collect();
+        assert_eq!(heights, vec![100.0, 50.0, 5.0, 40.0, 30.0]);
+    }
+
+    #[test]
+    fn test_vertical_margins_collapse() {
+        let document = layout_document(
+            "<div style=\"margin-bottom:10px\">a</div><div style=\"margin-top:20px\">b</div><div style=\"margin:5px;margin-left:30px;margin-right:30px\"><p style=\"margin-top:15px\">c</p></div><div style=\"padding:1px\"><p style=\"margin-top:15px\">d</p></div>",
+            Some(""),
+        );
+
+        let document = document.borrow();
+        let body = document.child.as_ref().unwrap().borrow().children[0].clone();
+        let body = body.borrow();
+        let block = |i: usize| body.children[i].borrow();
+
+        // 10px and 20px collapse to 20px instead of adding up to 30px
+        assert_eq!(block(1).y - (block(0).y + block(0).height), 20.0);
+
+        // the child's larger top margin moves its parent instead of opening a gap inside it
+        let (parent, child) = (block(2), block(2).children[0].clone());
+        assert_eq!(parent.y - (block(1).y + block(1).height), 15.0);
+        assert_eq!(child.borrow().y, parent.y);
+        assert_eq!((parent.x, parent.width), (body.x + 30.0, body.width - 60.0));
+
+        // padding keeps them apart, the bottom margin above still collapses with 0
+        let (parent, child) = (block(3), block(3).children[0].clone());
+        assert_eq!(parent.y - (block(2).y + block(2).height), 5.0);
+        assert_eq!(child.borrow().y, parent.y + 1.0 + 15.0);
+        assert_eq!(parent.height, 1.0 + 15.0 + child.borrow().height + 1.0);
+    }
+
+    #[test]
+    fn test_invalid_padding_keeps_its_place() {
+        let node = HTMLParser::new("<div></div>".to_string()).parse().unwrap();
+        let rules = CSSParser::new("div { padding: 4px auto }").parse().unwrap();
+        style(node.clone(), &rules, &HashSet::new());
+        let div = walk_tree(node)
+            .find(|node| matches!(&node.borrow().data, HTMLNodeData::Element(e) if e.tag == "div"))
+            .unwrap();
+
+        // `auto` is 0 on the left and right instead of shifting `4px` onto them
+        let insets = node_insets(&div);
+        assert_eq!((insets.top, insets.bottom), (4.0, 4.0));
+        assert_eq!((insets.left, insets.right), (0.0, 0.0));
+    }
+
+    // every text each item of the first list paints, the marker comes first
+    fn list_markers(html: &str) -> Vec<Vec<String>> {
+        let document = layout_document(html, Some(""));
+
+        let document = document.borrow();
+        let body = document.child.as_ref().unwrap().borrow().children[0].clone();
+        let list = body.borrow().children[0].clone();
+        let list = list.borrow();
+        list.children
+            .iter()
+            .map(|item| {
+                item.borrow()
+                    .paint()
+                    .iter()
+                    .filter(|cmd| matches!(cmd, DrawCommand::Text(_)))
+                    .map(|cmd| cmd.to_string())
+                    .collect::<Vec<String>>()
+            })
+            .collect::<Vec<Vec<String>>>()
+    }
+
+    #[test]
+    fn test_list_style_type() {
+        let square = list_markers("<ul style=\"list-style-type:square\"><li>a</li></ul>");
+        assert_eq!(square[0].len(), 2);
+        assert!(square[0][0].ends_with("text=▪)"));
+
+        let decimal =
+            list_markers("<ol style=\"list-style-type:decimal\"><li>a</li><li>b</li></ol>");
+        assert!(decimal[1][0].ends_with("text=2.)"));
+
+        // disc is the default
+        let disc = list_markers("<ul><li>a</li></ul>");
+        assert!(disc[0][0].ends_with("text=•)"));
+
+        let none = list_markers("<ul style=\"list-style-type:none\"><li>a</li></ul>");
+        assert_eq!(none[0].len(), 1);
+        assert!(none[0][0].ends_with("text=a)"));
+    }
+
+    #[test]
+    fn test_nested_list_markers_alternate() {
+        // the defaults come from the browser's stylesheet, so a page can set them back
+        let document = layout_document(
+            "<ul><li>a<ul><li>b<ol><li>c<ul><li>d</li></ul></li></ol></li></ul></li></ul>\
+             <ul class=flat><li>e<ul><li>f</li></ul></li></ul>",
+            Some(concat!(
+                include_str!("../asset/browser.css"),
+                ".flat ul { list-style-type: disc }"
+            )),
+        );
+
+        // every marker in document order
+        let document = document.borrow();
+        let mut markers = Vec::new();
+        let mut stack = vec![document.child.clone().unwrap()];
+        while let Some(block) = stack.pop() {
+            markers.extend(block.borrow().list_marker());
+            stack.extend(block.borrow().children.iter().rev().cloned());
+        }
+        assert_eq!(markers, vec!["•", "◦", "1.", "▪", "•", "•"]);
+    }
+
+    #[test]
+    fn test_ordered_list_start_and_value() {
+        let markers = list_markers(
+            "<ol start=3 style=\"list-style-type:decimal\"><li>a</li><li>b</li><li value=10>c</li><li>d</li></ol>",
+        );
+        let numbers: Vec<&str> = markers
+            .iter()
+            .map(|item| item[0].rsplit_once("text=").unwrap().1)
+            .collect();
+        assert_eq!(numbers, vec!["3.)", "4.)", "10.)", "11.)"]);
+
+        // counting on from the largest value stays there instead of overflowing
+        let markers = list_markers(&format!(
+            "<ol style=\"list-style-type:decimal\"><li value={}>a</li><li>b</li></ol>",
+            i64::MAX
+        ));
+        assert!(markers[1][0].ends_with(&format!("text={}.)", i64::MAX)));
+    }
+
+    #[test]
+    fn test_min_and_max_width() {
+        let document = layout_document(
+            "<div style=\"max-width:200px\">a</div><div style=\"width:100px;min-width:50%\">b</div><div style=\"min-width:300px;max-width:200px\">c</div>",
+            Some(""),
+        );
+
+        let document = document.borrow();
+        let body = document.child.as_ref().unwrap().borrow().children[0].clone();
+        let widths: Vec<f32> = body
+            .borrow()
+            .children
+            .iter()
+            .map(|div| div.borrow().width)
+            .collect();
+
+        assert_eq!(widths, vec![200.0, DEFAULT_WIDTH / 2.0, 300.0]);
+    }
+
+    #[test]
+    fn test_img_alt_text_is_laid_out() {
+        let document = layout_document("<p>see <img src=a.png alt=\"Cat\"> here</p>", None);
+
+        let mut words = Vec::new();
+        document
+            .borrow()
+            .child
+            .as_ref()
+            .unwrap()
+            .borrow()
+            .each_item(&mut |item| words.push(item.text.to_string()));
+        assert_eq!(words, vec!["see", "Cat", "here"]);
+    }
+
+    #[test]
+    fn test_word_and_letter_spacing() {
+        let word_x = |html: &str| {
+            let document = layout_document(html, Some(""));
+
+            let mut xs = Vec::new();
+            document
+                .borrow()
+                .child
+                .as_ref()
+                .unwrap()
+                .borrow()
+                .each_item(&mut |item| xs.push(item.x));
+            xs
+        };
+
+        let normal = word_x("<p>ab cd</p>");
+        let word_spaced = word_x("<p style=\"word-spacing:10px\">ab cd</p>");
+        let letter_spaced = word_x("<p style=\"letter-spacing:2px\">ab cd</p>");
+
+        assert_eq!(normal[0], word_spaced[0]);
+        assert_eq!(word_spaced[1] - normal[1], 10.0);
+        // two letters and the space each get 2px
+        assert_eq!(letter_spaced[1] - normal[1], 6.0);
+    }
+
+    #[test]
+    fn test_title_at_hovered_word() {
+        let document = layout_document(
+            "<p>plain <span title=\"More\">hover</span></p><div title=\"Outer\"><p>inner</p></div>",
+            None,
+        );
+
+        let mut positions = Vec::new();
+        document
+            .borrow()
+            .child
+            .as_ref()
+            .unwrap()
+            .borrow()
+            .each_item(&mut |item| {
+                positions.push((item.text.to_string(), item.x + 1.0, item.y + 1.0))
+            });
+        let at = |word: &str| {
+            let (_, x, y) = positions.iter().find(|(text, _, _)| text == word).unwrap();
+            document.borrow().title_at(*x, *y)
+        };
+
+        assert_eq!(at("hover"), Some("More".to_string()));
+        assert_eq!(at("plain"), None);
+        // titles are found on ancestors too
+        assert_eq!(at("inner"), Some("Outer".to_string()));
+        assert_eq!(document.borrow().title_at(-100.0, -100.0), None);
+    }
+
+    #[test]
+    fn test_text_transform() {
+        let document = layout_document(
+            "<p style=\"text-transform:uppercase\">hello <span style=\"text-transform:capitalize\">big world</span></p>",
+            Some(""),
+        );
+
+        fn collect_words(block_rc: &BlockLayoutRef, out: &mut Vec<String>) {
+            let block = block_rc.borrow();
+            out.extend(block.display_list.iter().map(|i| i.text.to_string()));
+            for child in &block.children {
+                collect_words(child, out);
+            }
+        }
+
+        let mut words = Vec::new();
+        collect_words(document.borrow().child.as_ref().unwrap(), &mut words);
+        assert_eq!(words, vec!["HELLO", "Big", "World"]);
+    }
+
+    #[test]
+    fn test_transparent_background_has_no_rect() {
+        let document = layout_document(
+            "<div style=\"background-color:rgba(255,0,0,0)\">x</div><p style=\"background-color:red\">y</p>",
+            Some(""),
+        );
+
+        fn count_rects(block_rc: &BlockLayoutRef) -> usize {
+            let block = block_rc.borrow();
+            let own = block
+                .paint()
+                .iter()
+                .filter(|cmd| matches!(cmd, DrawCommand::Rect(_)))
+                .count();
+            own + block.children.iter().map(count_rects).sum::<usize>()
+        }
+
+        // only the red paragraph paints a background
+        assert_eq!(count_rects(document.borrow().child.as_ref().unwrap()), 1);
+    }
+
+    #[test]
+    fn test_inline_blocks_sit_side_by_side() {
+        let document = layout_document(
+            "<div><div class=box>one</div> <div class=box>two two</div></div><p>after</p>",
+            Some(".box { display: inline-block; width: 100px; }"),
+        );
+
+        fn collect_boxes(block_rc: &BlockLayoutRef, out: &mut Vec<(f32, f32, f32, f32)>) {
+            let block = block_rc.borrow();
+            if is_inline_block(&block.node) {
+                out.push((block.x, block.y, block.width, block.height));
+            }
+            for child in &block.children {
+                collect_boxes(child, out);
+            }
+        }
+        let mut boxes = Vec::new();
+        collect_boxes(document.borrow().child.as_ref().unwrap(), &mut boxes);
+
+        assert_eq!(boxes.len(), 2);
+        let ((x1, y1, w1, h1), (x2, y2, w2, _)) = (boxes[0], boxes[1]);
+        assert_eq!((w1, w2), (100.0, 100.0));
+        assert_eq!(y1, y2);
+        assert!(x2 >= x1 + w1);
+        assert!(h1 > 0.0);
+    }
+
+    #[test]
+    fn test_nested_inline_blocks_are_moved_with_their_parent() {
+        // laying each level out again where it's placed would take 2^depth layouts
+        let depth = 32;
+        let html = format!(
+            "<p>before {}word{}</p>",
+            "<span class=box>".repeat(depth),
+            "</span>".repeat(depth)
+        );
+        let document =
+            layout_document(&html, Some(".box { display: inline-block; padding: 1px; }"));
+
+        fn innermost(block_rc: &BlockLayoutRef) -> BlockLayoutRef {
+            let child = block_rc.borrow().children.last().cloned();
+            child.map_or(block_rc.clone(), |child| innermost(&child))
+        }
+        let root = document.borrow().child.clone().unwrap();
+        let inner = innermost(&root);
+        let inner = inner.borrow();
+        let word = &inner.display_list[0];
+        assert_eq!(word.text, "word");
+        // the word sits inside its box, after the text before the boxes
+        assert!(word.x >= inner.x);
+        assert!(word.y >= inner.y && word.y < inner.y + inner.height);
+        assert!(inner.x > root.borrow().x + depth as f32);
+    }
+
+    #[test]
+    fn test_hidden_input_reserves_nothing() {
+        let inputs = layout_inputs("<p><input type=hidden></p>");
+        assert!(inputs.is_empty());
+    }
+
+    #[test]
+    fn test_relayout_skips_clean_blocks() {
+        let html = "<div><p>first</p></div><div class=warn><p>second</p></div>";
+        let document = layout_document(html, Some(""));
+        let root = document.borrow().child.clone().unwrap();
+        let node = root.borrow().node.clone();
+
+        // marks the words of every block, laying a block out again drops its marks
+        fn mark(block_rc: &BlockLayoutRef) {
+            let block = &mut *block_rc.borrow_mut();
+            for item in &mut block.display_list {
+                item.text = "cached".to_string();
+            }
+            block.children.iter().for_each(mark);
+        }
+        fn cached(block_rc: &BlockLayoutRef) -> usize {
+            let block = block_rc.borrow();
+            let kept = block
+                .display_list
+                .first()
+                .is_some_and(|item| item.text == "cached");
+            usize::from(kept) + block.children.iter().map(cached).sum::<usize>()
+        }
+        mark(&root);
+
+        // nothing changed, both paragraphs are skipped
+        style(node.clone(), &Vec::new(), &HashSet::new());
+        document.borrow_mut().layout();
+        assert_eq!(cached(&root), 2);
+
+        // only the second paragraph is laid out again
+        let rules = CSSParser::new(".warn { color: red; }").parse().unwrap();
+        style(node.clone(), &rules, &HashSet::new());
+        document.borrow_mut().layout();
+        assert_eq!(cached(&root), 1);
+
+        fn words(block_rc: &BlockLayoutRef, out: &mut Vec<(String, String)>) {
+            let block = block_rc.borrow();
+            out.extend(
+                block
+                    .display_list
+                    .iter()
+                    .map(|i| (i.text.clone(), i.color.clone())),
+            );
+            block.children.iter().for_each(|child| words(child, out));
+        }
+        let mut painted = Vec::new();
+        words(&root, &mut painted);
+        assert!(painted.contains(&("second".to_string(), "red".to_string())));
+        assert!(!node.borrow().dirty);
     }
 }
@@ -1,8 +1,13 @@
+use crate::constant::html::{ANCHOR, ATTRIBUTE_KEY_HREF, ATTRIBUTE_KEY_TITLE};
 use crate::constant::layout::{DEFAULT_WIDTH, DEFAULT_X, DEFAULT_Y};
+use crate::constant::style::{CURSOR_POINTER, STYLE_KEY_CURSOR};
 use crate::layout::block_layout::{BlockLayout, BlockLayoutRef};
 use crate::layout::font_manager::{FontManager, FontManagerRef};
-use crate::parser::html_node::HTMLNodeRef;
+use crate::parser::html_node::{HTMLNodeData, HTMLNodeRef};
+use crate::parser::html_parser::{ancestors, get_focusable, walk_tree};
+use skia_safe::Rect;
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::fmt::{Display, Formatter, Result};
 use std::rc::Rc;
 
@@ -17,6 +22,8 @@ pub struct DocumentLayout {
     y: f32,
     pub height: f32,
     width: f32,
+    // caps the content width, centering the root block in what is left
+    max_width: Option<f32>,
 }
 
 impl DocumentLayout {
@@ -29,17 +36,101 @@ impl DocumentLayout {
             y: DEFAULT_Y,
             height: 0.0,
             width: DEFAULT_WIDTH,
+            max_width: None,
         }))
     }
 
+    pub fn set_max_width(&mut self, max_width: Option<f32>) {
+        self.max_width = max_width;
+    }
+
+    /// Lay out the page, keeping the previous layout of subtrees the style pass left clean.
     pub fn layout(&mut self) {
-        let child_rc = BlockLayout::new(self.node.clone(), None, None, self.font_manager.clone());
+        let child_rc = match &self.child {
+            Some(child_rc) => child_rc.clone(),
+            None => BlockLayout::new(self.node.clone(), None, None, self.font_manager.clone()),
+        };
         self.child = Some(child_rc.clone());
+        child_rc.borrow_mut().fixed_geometry = self.max_width.map(|max_width| {
+            let width = self.width.min(max_width);
+            (self.x + (self.width - width) / 2.0, self.y, width)
+        });
         BlockLayout::layout(child_rc.clone());
         self.height = child_rc.borrow().height;
+
+        for node in walk_tree(self.node.clone()) {
+            node.borrow_mut().dirty = false;
+        }
+    }
+
+    /// The `title` of the element at a point in page coordinates, or of its nearest ancestor
+    /// that has one.
+    pub fn title_at(&self, x: f32, y: f32) -> Option<String> {
+        self.attribute_at(x, y, None, ATTRIBUTE_KEY_TITLE)
+    }
+
+    /// The `href` of the link at a point in page coordinates.
+    pub fn link_at(&self, x: f32, y: f32) -> Option<String> {
+        self.attribute_at(x, y, Some(ANCHOR), ATTRIBUTE_KEY_HREF)
+    }
+
+    /// The attribute `key` of the nearest element at or around a point, optionally only `tag`s.
+    fn attribute_at(&self, x: f32, y: f32, tag: Option<&str>, key: &str) -> Option<String> {
+        let node = self.child.as_ref()?.borrow().hit_test(x, y)?;
+
+        ancestors(node).find_map(|node| match &node.borrow().data {
+            HTMLNodeData::Element(e) if tag.is_none_or(|tag| e.tag == tag) => {
+                e.attributes.get(key).cloned()
+            }
+            _ => None,
+        })
+    }
+
+    /// Whether the element at a point in page coordinates has `cursor: pointer`, as links do.
+    pub fn is_pointer_at(&self, x: f32, y: f32) -> bool {
+        self.child
+            .as_ref()
+            .and_then(|child| child.borrow().hit_test(x, y))
+            .is_some_and(|node| {
+                node.borrow()
+                    .style
+                    .get(STYLE_KEY_CURSOR)
+                    .is_some_and(|cursor| cursor == CURSOR_POINTER)
+            })
+    }
+
+    pub fn node_y(&self, node: &HTMLNodeRef) -> Option<f32> {
+        self.child.as_ref()?.borrow().node_y(node)
+    }
+
+    pub fn node_rect(&self, node: &HTMLNodeRef) -> Option<Rect> {
+        self.child.as_ref()?.borrow().node_rect(node)
+    }
+
+    /// What Tab moves focus between: the focusable elements that were laid out, in document
+    /// order.
+    pub fn focus_order(&self) -> Vec<HTMLNodeRef> {
+        let mut nodes = Vec::new();
+        if let Some(child) = &self.child {
+            child.borrow().laid_out_nodes(&mut nodes);
+        }
+
+        // a node has a box when it or anything inside it was laid out
+        let mut laid_out = HashSet::new();
+        for node in nodes {
+            for node in ancestors(node) {
+                if !laid_out.insert(Rc::as_ptr(&node)) {
+                    break;
+                }
+            }
+        }
+
+        get_focusable(self.node.clone())
+            .into_iter()
+            .filter(|node| laid_out.contains(&Rc::as_ptr(node)))
+            .collect()
     }
 
-    #[cfg(debug_assertions)]
     pub fn print_tree(&self, depth: usize) {
         let indent = "  ".repeat(depth);
 
@@ -1,3 +1,5 @@
+use crate::layout::render_options::RenderOptions;
+use skia_safe::paint::Style;
 use skia_safe::{Canvas, Color, Font, Paint, Point, Rect};
 use std::fmt::{Display, Formatter, Result};
 
@@ -11,21 +13,167 @@ pub struct DrawText {
     font: Font,
     color_str: String,
     color: Color,
+    background_str: String,
+    background: Option<Color>,
+    letter_spacing: f32,
+    opacity: f32,
+    fixed: bool,
 }
 
 impl DrawText {
-    pub fn execute(&self, scroll: f32, canvas: &Canvas, paint: &mut Paint) {
+    pub fn execute(
+        &self,
+        scroll: f32,
+        canvas: &Canvas,
+        paint: &mut Paint,
+        options: &RenderOptions,
+    ) {
+        if let Some(background) = self.background {
+            let width = self.text_width(&self.text);
+            let rect = Rect::new(
+                self.left,
+                self.top - scroll,
+                self.left + width,
+                self.bottom - scroll,
+            );
+            paint.set_color(apply_opacity(background, self.opacity));
+            canvas.draw_rect(rect, paint);
+        }
+
         let point = Point::new(self.left, self.baseline - scroll);
-        paint.set_color(self.color);
-        canvas.draw_str(&self.text, point, &self.font, paint);
+        paint.set_color(apply_opacity(self.color, self.opacity));
+        let mut font = self.font.clone();
+        options.apply_to_font(&mut font);
+
+        if self.letter_spacing == 0.0 {
+            canvas.draw_str(&self.text, point, &font, paint);
+            return;
+        }
+
+        // spaced text is drawn a character at a time
+        let mut x = point.x;
+        for c in self.text.chars() {
+            let c = c.to_string();
+            canvas.draw_str(&c, Point::new(x, point.y), &font, paint);
+            x += self.text_width(&c);
+        }
+    }
+
+    /// Advance width of `text`, including the letter spacing after each character.
+    fn text_width(&self, text: &str) -> f32 {
+        self.font.measure_str(text, None).0 + self.letter_spacing * text.chars().count() as f32
     }
 }
 
+impl DrawText {
+    /// The characters whose middle lies between `from_x` and `to_x`, with the rect they cover.
+    pub fn select(&self, from_x: f32, to_x: f32) -> Option<(Rect, String)> {
+        let mut selected = String::new();
+        let mut left = None;
+        let mut right = 0.0;
+
+        let mut x = self.left;
+        for c in self.text.chars() {
+            let width = self.text_width(&c.to_string());
+            let middle = x + width / 2.0;
+            if from_x <= middle && middle <= to_x {
+                left.get_or_insert(x);
+                right = x + width;
+                selected.push(c);
+            }
+            x += width;
+        }
+
+        left.map(|left| (Rect::new(left, self.top, right, self.bottom), selected))
+    }
+}
+
+/// Case-insensitive, non-overlapping matches of `query` in the text items, as rects in page
+/// coordinates. Neighboring items on a line are searched as one text, with a space where
+/// there's a gap between them, so a match can span several words.
+pub fn find_in_lines(texts: &[&DrawText], query: &str) -> Vec<Rect> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let mut rects = Vec::new();
+    if query.is_empty() {
+        return rects;
+    }
+
+    let mut start = 0;
+    while start < texts.len() {
+        let mut end = start + 1;
+        while end < texts.len() && same_line(texts[end - 1], texts[end]) {
+            end += 1;
+        }
+        rects.extend(find_in_line(&texts[start..end], &query));
+        start = end;
+    }
+
+    rects
+}
+
+fn same_line(previous: &DrawText, next: &DrawText) -> bool {
+    next.baseline == previous.baseline && next.fixed == previous.fixed && next.left > previous.left
+}
+
+fn find_in_line(line: &[&DrawText], query: &[char]) -> Vec<Rect> {
+    // each character of the line's text, with the item and the index in it it comes from,
+    // `None` for the spaces between items
+    let mut chars: Vec<(char, Option<(usize, usize)>)> = Vec::new();
+    for (item_index, item) in line.iter().enumerate() {
+        if let Some(previous) = item_index.checked_sub(1).map(|i| line[i])
+            && item.left > previous.left + previous.text_width(&previous.text) + 0.5
+        {
+            chars.push((' ', None));
+        }
+        // lowercasing may expand a character, each part maps back to the original
+        for (char_index, c) in item.text.chars().enumerate() {
+            for lower in c.to_lowercase() {
+                chars.push((lower, Some((item_index, char_index))));
+            }
+        }
+    }
+
+    let mut rects = Vec::new();
+    let mut i = 0;
+    while i + query.len() <= chars.len() {
+        let matched = &chars[i..i + query.len()];
+        if !matched.iter().map(|(c, _)| c).eq(query.iter()) {
+            i += 1;
+            continue;
+        }
+
+        let mut sources = matched.iter().filter_map(|(_, source)| *source);
+        if let Some(first) = sources.next() {
+            let last = sources.last().unwrap_or(first);
+            let edge = |(item_index, char_index): (usize, usize)| {
+                let item = line[item_index];
+                let prefix: String = item.text.chars().take(char_index).collect();
+                (item, item.left + item.text_width(&prefix))
+            };
+            let (first_item, left) = edge(first);
+            let (last_item, right) = edge((last.0, last.1 + 1));
+            let top = line[first.0..=last.0]
+                .iter()
+                .map(|item| item.top)
+                .fold(f32::MAX, f32::min);
+            let bottom = line[first.0..=last.0]
+                .iter()
+                .map(|item| item.bottom)
+                .fold(first_item.bottom.max(last_item.bottom), f32::max);
+            rects.push(Rect::new(left, top, right, bottom));
+        }
+
+        i += query.len();
+    }
+
+    rects
+}
+
 impl Display for DrawText {
     fn fmt(&self, f: &mut Formatter) -> Result {
         write!(
             f,
-            "DrawText(top={} left={} baseline={} bottom={} font_family={} font_size={} color={} text={})",
+            "DrawText(top={} left={} baseline={} bottom={} font_family={} font_size={} color={} background={} text={})",
             self.top,
             self.left,
             self.baseline,
@@ -33,6 +181,7 @@ impl Display for DrawText {
             self.font.typeface().family_name(),
             self.font.size(),
             self.color_str,
+            self.background_str,
             self.text,
         )
     }
@@ -46,6 +195,8 @@ pub struct DrawRect {
     right: f32,
     color_str: String,
     color: Option<Color>,
+    opacity: f32,
+    fixed: bool,
 }
 
 impl DrawRect {
@@ -58,7 +209,7 @@ impl DrawRect {
         );
 
         if let Some(color) = self.color {
-            paint.set_color(color);
+            paint.set_color(apply_opacity(color, self.opacity));
         }
 
         canvas.draw_rect(rect, paint);
@@ -75,15 +226,205 @@ impl Display for DrawRect {
     }
 }
 
+#[derive(Debug)]
+pub struct DrawOutline {
+    top: f32,
+    left: f32,
+    bottom: f32,
+    right: f32,
+    thickness: f32,
+    color_str: String,
+    color: Option<Color>,
+    opacity: f32,
+    fixed: bool,
+}
+
+impl DrawOutline {
+    pub fn execute(&self, scroll: f32, canvas: &Canvas, paint: &mut Paint) {
+        let rect = Rect::new(
+            self.left,
+            self.top - scroll,
+            self.right,
+            self.bottom - scroll,
+        );
+
+        if let Some(color) = self.color {
+            paint.set_color(apply_opacity(color, self.opacity));
+        }
+
+        paint.set_style(Style::Stroke);
+        paint.set_stroke_width(self.thickness);
+        canvas.draw_rect(rect, paint);
+        paint.set_style(Style::Fill);
+    }
+}
+
+impl Display for DrawOutline {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(
+            f,
+            "DrawOutline(top={} left={} bottom={} right={} thickness={} color={})",
+            self.top, self.left, self.bottom, self.right, self.thickness, self.color_str
+        )
+    }
+}
+
+/// A straight stroke between two points, for rules and underlines.
+#[derive(Debug)]
+pub struct DrawLine {
+    x1: f32,
+    y1: f32,
+    x2: f32,
+    y2: f32,
+    thickness: f32,
+    color_str: String,
+    color: Option<Color>,
+    opacity: f32,
+    fixed: bool,
+}
+
+impl DrawLine {
+    pub fn execute(&self, scroll: f32, canvas: &Canvas, paint: &mut Paint) {
+        if let Some(color) = self.color {
+            paint.set_color(apply_opacity(color, self.opacity));
+        }
+
+        paint.set_style(Style::Stroke);
+        paint.set_stroke_width(self.thickness);
+        canvas.draw_line(
+            Point::new(self.x1, self.y1 - scroll),
+            Point::new(self.x2, self.y2 - scroll),
+            paint,
+        );
+        paint.set_style(Style::Fill);
+    }
+
+    // the stroke extends half its thickness either side of the line
+    fn top(&self) -> f32 {
+        self.y1.min(self.y2) - self.thickness / 2.0
+    }
+
+    fn bottom(&self) -> f32 {
+        self.y1.max(self.y2) + self.thickness / 2.0
+    }
+}
+
+impl Display for DrawLine {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(
+            f,
+            "DrawLine(x1={} y1={} x2={} y2={} thickness={} color={})",
+            self.x1, self.y1, self.x2, self.y2, self.thickness, self.color_str
+        )
+    }
+}
+
+/// Clips everything drawn until the matching `PopClip` to a rect.
+#[derive(Debug)]
+pub struct DrawClip {
+    top: f32,
+    left: f32,
+    bottom: f32,
+    right: f32,
+    fixed: bool,
+}
+
+impl DrawClip {
+    pub fn execute(&self, scroll: f32, canvas: &Canvas) {
+        let rect = Rect::new(
+            self.left,
+            self.top - scroll,
+            self.right,
+            self.bottom - scroll,
+        );
+
+        canvas.save();
+        canvas.clip_rect(rect, None, None);
+    }
+}
+
+impl Display for DrawClip {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(
+            f,
+            "DrawClip(top={} left={} bottom={} right={})",
+            self.top, self.left, self.bottom, self.right
+        )
+    }
+}
+
+/// Moves and scales everything drawn until the matching `PopTransform`, scaling about an origin
+/// in page coordinates.
+#[derive(Debug)]
+pub struct DrawTransform {
+    dx: f32,
+    dy: f32,
+    scale: f32,
+    origin_x: f32,
+    origin_y: f32,
+    fixed: bool,
+}
+
+impl DrawTransform {
+    pub fn execute(&self, scroll: f32, canvas: &Canvas) {
+        let origin_y = self.origin_y - scroll;
+
+        canvas.save();
+        canvas.translate((self.origin_x + self.dx, origin_y + self.dy));
+        canvas.scale((self.scale, self.scale));
+        canvas.translate((-self.origin_x, -origin_y));
+    }
+
+    /// Where a point of the transformed subtree ends up, in page coordinates.
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.origin_x + self.dx + self.scale * (x - self.origin_x),
+            self.origin_y + self.dy + self.scale * (y - self.origin_y),
+        )
+    }
+
+    /// Where a rect of the transformed subtree ends up, in page coordinates.
+    pub fn apply_rect(&self, rect: Rect) -> Rect {
+        let (left, top) = self.apply(rect.left, rect.top);
+        let (right, bottom) = self.apply(rect.right, rect.bottom);
+        Rect::new(left, top, right, bottom)
+    }
+}
+
+impl Display for DrawTransform {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(
+            f,
+            "DrawTransform(dx={} dy={} scale={} origin_x={} origin_y={})",
+            self.dx, self.dy, self.scale, self.origin_x, self.origin_y
+        )
+    }
+}
+
 #[derive(Debug)]
 pub enum DrawCommand {
     Text(DrawText),
     Rect(DrawRect),
+    Outline(DrawOutline),
+    Line(DrawLine),
+    PushClip(DrawClip),
+    PopClip,
+    PushTransform(DrawTransform),
+    PopTransform,
 }
 
 impl DrawCommand {
-    pub fn text(x1: f32, y1: f32, baseline: f32, text: String, font: Font, color: &str) -> Self {
+    pub fn text(
+        x1: f32,
+        y1: f32,
+        baseline: f32,
+        text: String,
+        font: Font,
+        color: &str,
+        background_color: &str,
+    ) -> Self {
         let bottom = y1 + font.spacing();
+        let background = Self::parse_css_color(background_color).filter(|color| color.a() > 0);
 
         Self::Text(DrawText {
             top: y1,
@@ -94,9 +435,48 @@ impl DrawCommand {
             font,
             color_str: color.to_string(),
             color: Self::parse_css_color(color).unwrap_or(Color::BLACK),
+            background_str: background_color.to_string(),
+            background,
+            letter_spacing: 0.0,
+            opacity: 1.0,
+            fixed: false,
         })
     }
 
+    pub fn with_letter_spacing(mut self, spacing: f32) -> Self {
+        if let Self::Text(text) = &mut self {
+            text.letter_spacing = spacing;
+        }
+        self
+    }
+
+    /// Multiplies `opacity` into the command's, so nested opacities compound.
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        let opacity = opacity.clamp(0.0, 1.0);
+        match &mut self {
+            Self::Text(text) => text.opacity *= opacity,
+            Self::Rect(rect) => rect.opacity *= opacity,
+            Self::Outline(outline) => outline.opacity *= opacity,
+            Self::Line(line) => line.opacity *= opacity,
+            Self::PushClip(_) | Self::PopClip | Self::PushTransform(_) | Self::PopTransform => {}
+        }
+        self
+    }
+
+    /// The color the command paints with, after opacity.
+    #[cfg(test)]
+    pub fn painted_color(&self) -> Option<Color> {
+        match self {
+            Self::Text(text) => Some(apply_opacity(text.color, text.opacity)),
+            Self::Rect(rect) => rect.color.map(|color| apply_opacity(color, rect.opacity)),
+            Self::Outline(outline) => outline
+                .color
+                .map(|color| apply_opacity(color, outline.opacity)),
+            Self::Line(line) => line.color.map(|color| apply_opacity(color, line.opacity)),
+            Self::PushClip(_) | Self::PopClip | Self::PushTransform(_) | Self::PopTransform => None,
+        }
+    }
+
     fn parse_css_color(color_str: &str) -> Option<Color> {
         match csscolorparser::parse(color_str) {
             Ok(color) => {
@@ -110,6 +490,12 @@ impl DrawCommand {
         }
     }
 
+    /// Whether painting with this color would show anything, i.e. it parses with a non-zero alpha.
+    /// `transparent`, `rgba(0,0,0,0)` and `#00000000` are all invisible.
+    pub fn is_visible_color(color_str: &str) -> bool {
+        Self::parse_css_color(color_str).is_some_and(|color| color.a() > 0)
+    }
+
     pub fn rect(x1: f32, y1: f32, x2: f32, y2: f32, color: &str) -> Self {
         Self::Rect(DrawRect {
             top: y1,
@@ -118,13 +504,89 @@ impl DrawCommand {
             right: x2,
             color: Self::parse_css_color(color),
             color_str: color.to_string(),
+            opacity: 1.0,
+            fixed: false,
         })
     }
 
-    pub fn execute(&self, scroll: f32, canvas: &Canvas, paint: &mut Paint) {
+    pub fn outline(x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: &str) -> Self {
+        Self::Outline(DrawOutline {
+            top: y1,
+            left: x1,
+            bottom: y2,
+            right: x2,
+            thickness,
+            color: Self::parse_css_color(color),
+            color_str: color.to_string(),
+            opacity: 1.0,
+            fixed: false,
+        })
+    }
+
+    pub fn line(x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32, color: &str) -> Self {
+        Self::Line(DrawLine {
+            x1,
+            y1,
+            x2,
+            y2,
+            thickness,
+            color: Self::parse_css_color(color),
+            color_str: color.to_string(),
+            opacity: 1.0,
+            fixed: false,
+        })
+    }
+
+    pub fn push_clip(x1: f32, y1: f32, x2: f32, y2: f32) -> Self {
+        Self::PushClip(DrawClip {
+            top: y1,
+            left: x1,
+            bottom: y2,
+            right: x2,
+            fixed: false,
+        })
+    }
+
+    /// `(dx, dy)` moves the subtree, `scale` grows it about `(origin_x, origin_y)`.
+    pub fn push_transform(dx: f32, dy: f32, scale: f32, origin_x: f32, origin_y: f32) -> Self {
+        Self::PushTransform(DrawTransform {
+            dx,
+            dy,
+            scale,
+            origin_x,
+            origin_y,
+            fixed: false,
+        })
+    }
+
+    /// Clip and transform markers must always run, even off screen, so saves and restores stay
+    /// balanced.
+    pub fn is_marker(&self) -> bool {
+        matches!(
+            self,
+            Self::PushClip(_) | Self::PopClip | Self::PushTransform(_) | Self::PopTransform
+        )
+    }
+
+    pub fn execute(
+        &self,
+        scroll: f32,
+        canvas: &Canvas,
+        paint: &mut Paint,
+        options: &RenderOptions,
+    ) {
+        let scroll = self.scroll_offset(scroll);
+
         match self {
-            Self::Text(text) => text.execute(scroll, canvas, paint),
+            Self::Text(text) => text.execute(scroll, canvas, paint, options),
             Self::Rect(rect) => rect.execute(scroll, canvas, paint),
+            Self::Outline(outline) => outline.execute(scroll, canvas, paint),
+            Self::Line(line) => line.execute(scroll, canvas, paint),
+            Self::PushClip(clip) => clip.execute(scroll, canvas),
+            Self::PushTransform(transform) => transform.execute(scroll, canvas),
+            Self::PopClip | Self::PopTransform => {
+                canvas.restore();
+            }
         }
     }
 
@@ -132,6 +594,10 @@ impl DrawCommand {
         match self {
             Self::Text(text) => text.bottom,
             Self::Rect(rect) => rect.bottom,
+            Self::Outline(outline) => outline.bottom,
+            Self::Line(line) => line.bottom(),
+            Self::PushClip(clip) => clip.bottom,
+            Self::PopClip | Self::PushTransform(_) | Self::PopTransform => 0.0,
         }
     }
 
@@ -139,8 +605,71 @@ impl DrawCommand {
         match self {
             Self::Text(text) => text.top,
             Self::Rect(rect) => rect.top,
+            Self::Outline(outline) => outline.top,
+            Self::Line(line) => line.top(),
+            Self::PushClip(clip) => clip.top,
+            Self::PopClip | Self::PushTransform(_) | Self::PopTransform => 0.0,
         }
     }
+
+    /// Marks the command as part of a `position: fixed` box, so it is painted relative to the viewport.
+    pub fn set_fixed(&mut self, fixed: bool) {
+        match self {
+            Self::Text(text) => text.fixed = fixed,
+            Self::Rect(rect) => rect.fixed = fixed,
+            Self::Outline(outline) => outline.fixed = fixed,
+            Self::Line(line) => line.fixed = fixed,
+            Self::PushClip(clip) => clip.fixed = fixed,
+            Self::PushTransform(transform) => transform.fixed = fixed,
+            Self::PopClip | Self::PopTransform => {}
+        }
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        match self {
+            Self::Text(text) => text.fixed,
+            Self::Rect(rect) => rect.fixed,
+            Self::Outline(outline) => outline.fixed,
+            Self::Line(line) => line.fixed,
+            Self::PushClip(clip) => clip.fixed,
+            Self::PushTransform(transform) => transform.fixed,
+            Self::PopClip | Self::PopTransform => false,
+        }
+    }
+
+    // fixed commands ignore the page scroll
+    fn scroll_offset(&self, scroll: f32) -> f32 {
+        if self.is_fixed() { 0.0 } else { scroll }
+    }
+
+    /// Top edge on screen for the given scroll.
+    pub fn painted_top(&self, scroll: f32) -> f32 {
+        self.get_top() - self.scroll_offset(scroll)
+    }
+
+    /// Bottom edge on screen for the given scroll.
+    pub fn painted_bottom(&self, scroll: f32) -> f32 {
+        self.get_bottom() - self.scroll_offset(scroll)
+    }
+
+    /// Top and bottom edges on screen for the given scroll, drawn inside `transforms`, the
+    /// outermost first.
+    pub fn transformed_span(&self, scroll: f32, transforms: &[&DrawTransform]) -> (f32, f32) {
+        let (top, bottom) = transforms.iter().rev().fold(
+            (self.get_top(), self.get_bottom()),
+            |(top, bottom), transform| {
+                let top = transform.apply(0.0, top).1;
+                let bottom = transform.apply(0.0, bottom).1;
+                (top.min(bottom), top.max(bottom))
+            },
+        );
+        let scroll = self.scroll_offset(scroll);
+        (top - scroll, bottom - scroll)
+    }
+}
+
+fn apply_opacity(color: Color, opacity: f32) -> Color {
+    color.with_a((color.a() as f32 * opacity).round() as u8)
 }
 
 impl Display for DrawCommand {
@@ -148,6 +677,74 @@ impl Display for DrawCommand {
         match self {
             Self::Text(text) => write!(f, "{}", text),
             Self::Rect(rect) => write!(f, "{}", rect),
+            Self::Outline(outline) => write!(f, "{}", outline),
+            Self::Line(line) => write!(f, "{}", line),
+            Self::PushClip(clip) => write!(f, "{}", clip),
+            Self::PopClip => write!(f, "PopClip"),
+            Self::PushTransform(transform) => write!(f, "{}", transform),
+            Self::PopTransform => write!(f, "PopTransform"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constant::style::BACKGROUND_COLOR_DEFAULT_VALUE;
+    use crate::layout::font_manager::FontManager;
+    use skia_safe::font_style::{Slant, Weight};
+
+    fn font() -> Font {
+        FontManager::new()
+            .borrow_mut()
+            .get_font(12, Weight::NORMAL, Slant::Upright)
+    }
+
+    #[test]
+    fn test_text_background_color() {
+        let cmd = DrawCommand::text(0.0, 0.0, 10.0, "hi".to_string(), font(), "black", "yellow");
+        let DrawCommand::Text(text) = cmd else {
+            panic!("expected a text command");
+        };
+        assert!(text.background.is_some());
+        assert_eq!(text.background_str, "yellow");
+    }
+
+    #[test]
+    fn test_text_transparent_background() {
+        let cmd = DrawCommand::text(
+            0.0,
+            0.0,
+            10.0,
+            "hi".to_string(),
+            font(),
+            "black",
+            BACKGROUND_COLOR_DEFAULT_VALUE,
+        );
+        let DrawCommand::Text(text) = cmd else {
+            panic!("expected a text command");
+        };
+        assert!(text.background.is_none());
+    }
+
+    #[test]
+    fn test_is_visible_color() {
+        assert!(DrawCommand::is_visible_color("red"));
+        assert!(DrawCommand::is_visible_color("rgba(255, 0, 0, 0.5)"));
+        assert!(!DrawCommand::is_visible_color("transparent"));
+        assert!(!DrawCommand::is_visible_color("rgba(255,0,0,0)"));
+        assert!(!DrawCommand::is_visible_color("#00000000"));
+    }
+
+    #[test]
+    fn test_line_bounds() {
+        let mut line = DrawCommand::line(0.0, 20.0, 100.0, 10.0, 4.0, "red");
+        assert_eq!(line.get_top(), 8.0);
+        assert_eq!(line.get_bottom(), 22.0);
+        assert_eq!(line.painted_top(5.0), 3.0);
+        assert!(line.to_string().contains("color=red"));
+
+        line.set_fixed(true);
+        assert_eq!(line.painted_top(5.0), 8.0);
+    }
+}
@@ -1,4 +1,5 @@
-use crate::constant::style::{DEFAULT_FONT_SIZE_NUM, UNIT_PIXEL};
+use crate::constant::style::DEFAULT_FONT_SIZE_NUM;
+use crate::layout::length::{LengthContext, parse_length};
 use skia_safe::font_style::{Slant, Weight, Width};
 use skia_safe::{Font, FontMgr, FontStyle};
 use std::cell::RefCell;
@@ -10,20 +11,19 @@ const FAMILY_NAMES: [&str; 3] = ["PingFang SC", "Microsoft YaHei UI", "Noto Sans
 pub type FontManagerRef = Rc<RefCell<FontManager>>;
 
 #[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
-struct FontKey(i32, bool, bool); // (font size, is bold, is italic)
+struct FontKey(i32, i32, i32); // (font size, numeric weight, slant)
 
 impl FontKey {
     fn new(size: i32, weight: Weight, slant: Slant) -> Self {
-        let is_bold = weight == Weight::BOLD;
-        let is_italic = slant == Slant::Italic;
-
-        Self(size, is_bold, is_italic)
+        Self(size, *weight, slant as i32)
     }
 }
 
 #[derive(Debug)]
 pub struct FontManager {
     font_cache: HashMap<FontKey, Font>,
+    // (advance, bounding box) widths of each text measured in a font
+    measure_cache: HashMap<FontKey, HashMap<String, (f32, f32)>>,
     font_mgr: FontMgr,
 }
 
@@ -31,6 +31,7 @@ impl FontManager {
     pub fn new() -> FontManagerRef {
         Rc::new(RefCell::new(Self {
             font_cache: HashMap::new(),
+            measure_cache: HashMap::new(),
             font_mgr: FontMgr::new(),
         }))
     }
@@ -60,6 +61,29 @@ impl FontManager {
 
         font
     }
+
+    /// The (advance, bounding box) widths of `text`, cached as the same words are measured
+    /// again on every layout.
+    pub fn measure(&mut self, size: i32, weight: Weight, slant: Slant, text: &str) -> (f32, f32) {
+        let key = FontKey::new(size, weight, slant);
+
+        if let Some(widths) = self
+            .measure_cache
+            .get(&key)
+            .and_then(|texts| texts.get(text))
+        {
+            return *widths;
+        }
+
+        let (advance, bounds) = self.get_font(size, weight, slant).measure_str(text, None);
+        let widths = (advance, bounds.width());
+        self.measure_cache
+            .entry(key)
+            .or_default()
+            .insert(text.to_string(), widths);
+
+        widths
+    }
 }
 
 /// https://drafts.csswg.org/css-fonts/#font-weight-prop
@@ -97,8 +121,70 @@ pub fn parse_font_style(style: Option<&String>) -> Slant {
 }
 
 pub fn parse_font_size(size: Option<&String>) -> i32 {
-    size.map(|s| s.trim_end_matches(UNIT_PIXEL))
-        .and_then(|s| s.parse::<f32>().ok())
+    size.and_then(|s| parse_length(s, &LengthContext::default()))
         .map(|f| f.round() as i32)
         .unwrap_or(DEFAULT_FONT_SIZE_NUM)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_font_key_keeps_weight_and_slant() {
+        let light = FontKey::new(16, Weight::LIGHT, Slant::Upright);
+        let bold = FontKey::new(16, Weight::BOLD, Slant::Upright);
+        let semi_bold = FontKey::new(16, Weight::SEMI_BOLD, Slant::Upright);
+        assert_ne!(light, bold);
+        assert_ne!(bold, semi_bold);
+        assert_eq!(
+            light,
+            FontKey::new(
+                16,
+                parse_font_weight(Some(&"300".to_string())),
+                Slant::Upright
+            )
+        );
+
+        let italic = FontKey::new(16, Weight::NORMAL, Slant::Italic);
+        let oblique = FontKey::new(
+            16,
+            Weight::NORMAL,
+            parse_font_style(Some(&"oblique".to_string())),
+        );
+        assert_ne!(italic, oblique);
+
+        let manager = FontManager::new();
+        let mut manager = manager.borrow_mut();
+        manager.get_font(16, Weight::LIGHT, Slant::Upright);
+        manager.get_font(16, Weight::BOLD, Slant::Upright);
+        manager.get_font(16, Weight::BOLD, Slant::Upright);
+        assert_eq!(manager.font_cache.len(), 2);
+    }
+
+    #[test]
+    fn test_measure_is_cached_per_font() {
+        let manager = FontManager::new();
+        let mut manager = manager.borrow_mut();
+        let measured = manager.measure(16, Weight::NORMAL, Slant::Upright, "hello");
+
+        // a planted width is returned as is, so the font isn't asked again
+        let key = FontKey::new(16, Weight::NORMAL, Slant::Upright);
+        manager
+            .measure_cache
+            .get_mut(&key)
+            .unwrap()
+            .insert("hello".to_string(), (1.0, 2.0));
+        assert_eq!(
+            manager.measure(16, Weight::NORMAL, Slant::Upright, "hello"),
+            (1.0, 2.0)
+        );
+
+        // another font measures on its own
+        assert_eq!(
+            manager.measure(16, Weight::BOLD, Slant::Upright, "hello"),
+            measured
+        );
+        assert_eq!(manager.measure_cache.len(), 2);
+    }
+}
@@ -1,4 +1,5 @@
-use crate::constant::html::BLOCK_ELEMENTS;
+use crate::constant::html::{BLOCK_ELEMENTS, EMBEDDED_ELEMENTS, IMG, INPUT, TABLE, TEXTAREA};
+use crate::constant::style::{DISPLAY_INLINE_BLOCK, STYLE_KEY_DISPLAY};
 use crate::parser::html_node::{HTMLNodeData, HTMLNodeRef};
 use std::fmt::{Display, Formatter, Result};
 
@@ -6,6 +7,7 @@ use std::fmt::{Display, Formatter, Result};
 pub enum LayoutMode {
     Inline,
     Block,
+    Table,
 }
 
 impl LayoutMode {
@@ -13,11 +15,20 @@ impl LayoutMode {
         let html_node = html_node.borrow();
         match &html_node.data {
             HTMLNodeData::Text(_) => LayoutMode::Inline,
+            HTMLNodeData::Element(e) if e.tag == TABLE => LayoutMode::Table,
+            HTMLNodeData::Element(e) if e.tag == INPUT || e.tag == TEXTAREA || e.tag == IMG => {
+                LayoutMode::Inline
+            }
+            HTMLNodeData::Element(e) if EMBEDDED_ELEMENTS.contains(&e.tag.as_str()) => {
+                LayoutMode::Inline
+            }
             _ => {
                 if html_node.children.iter().any(|child_rc| {
                     let child = &*child_rc.borrow();
                     match &child.data {
-                        HTMLNodeData::Element(e) => BLOCK_ELEMENTS.contains(&e.tag.as_str()),
+                        HTMLNodeData::Element(e) => {
+                            BLOCK_ELEMENTS.contains(&e.tag.as_str()) && !is_inline_block(child_rc)
+                        }
                         _ => false,
                     }
                 }) {
@@ -32,6 +43,14 @@ impl LayoutMode {
     }
 }
 
+/// Whether the element flows inline as one box while laying out its content like a block.
+pub fn is_inline_block(node: &HTMLNodeRef) -> bool {
+    node.borrow()
+        .style
+        .get(STYLE_KEY_DISPLAY)
+        .is_some_and(|display| display == DISPLAY_INLINE_BLOCK)
+}
+
 impl Display for LayoutMode {
     fn fmt(&self, f: &mut Formatter) -> Result {
         write!(
@@ -40,6 +59,7 @@ impl Display for LayoutMode {
             match self {
                 LayoutMode::Inline => "inline",
                 LayoutMode::Block => "block",
+                LayoutMode::Table => "table",
             }
         )
     }
@@ -0,0 +1,66 @@
+use crate::constant::common::PERCENT;
+use crate::constant::style::{DEFAULT_FONT_SIZE_NUM, UNIT_EM, UNIT_PIXEL};
+
+/// What relative lengths resolve against: `em` the font size, `%` the percent base when
+/// there is one.
+#[derive(Debug, Clone, Copy)]
+pub struct LengthContext {
+    pub font_size: f32,
+    pub percent_base: Option<f32>,
+}
+
+impl Default for LengthContext {
+    fn default() -> Self {
+        Self {
+            font_size: DEFAULT_FONT_SIZE_NUM as f32,
+            percent_base: None,
+        }
+    }
+}
+
+/// Parse a length like `10px`, `10`, `1.5em` or `50%` to px. Keywords like `normal` or
+/// `auto`, other units and junk are `None`, so callers fall back to their default.
+pub fn parse_length(value: &str, context: &LengthContext) -> Option<f32> {
+    let value = value.trim().to_lowercase();
+
+    let (number, scale) = if let Some(px) = value.strip_suffix(UNIT_PIXEL) {
+        (px, 1.0)
+    } else if let Some(em) = value.strip_suffix(UNIT_EM) {
+        (em, context.font_size)
+    } else if let Some(percent) = value.strip_suffix(PERCENT) {
+        (percent, context.percent_base? / 100.0)
+    } else {
+        (value.as_str(), 1.0)
+    };
+
+    // `inf` and `nan` parse as floats but aren't lengths
+    number
+        .parse::<f32>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .map(|number| number * scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_length() {
+        let context = LengthContext {
+            font_size: 20.0,
+            percent_base: Some(200.0),
+        };
+
+        assert_eq!(parse_length("10px", &context), Some(10.0));
+        assert_eq!(parse_length(" -5px ", &context), Some(-5.0));
+        assert_eq!(parse_length("0", &context), Some(0.0));
+        assert_eq!(parse_length("1.5em", &context), Some(30.0));
+        assert_eq!(parse_length("25%", &context), Some(50.0));
+        assert_eq!(parse_length("25%", &LengthContext::default()), None);
+
+        for junk in ["normal", "auto", "", "px", "10pt", "ten", "inf", "1e999px"] {
+            assert_eq!(parse_length(junk, &context), None, "{}", junk);
+        }
+    }
+}
@@ -1,5 +1,8 @@
 pub mod block_layout;
 pub mod document_layout;
 pub mod draw_command;
-mod font_manager;
+pub mod font_manager;
 mod layout_mode;
+pub mod length;
+pub mod render_options;
+mod white_space;
@@ -0,0 +1,78 @@
+use skia_safe::font::Edging;
+use skia_safe::{AlphaType, Color, ColorSpace, ColorType, Font, ISize, ImageInfo, Paint};
+
+/// Rasterization settings applied when the display list is drawn.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub anti_alias: bool,
+    pub subpixel_text: bool,
+    pub clear_color: Color,
+    // blend in linear light and convert into an sRGB surface, off blends the encoded values
+    pub srgb: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            anti_alias: true,
+            subpixel_text: false,
+            clear_color: Color::WHITE,
+            srgb: false,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// The color space the window surface is created with, `None` for the device's own.
+    pub fn color_space(&self) -> Option<ColorSpace> {
+        self.srgb.then(ColorSpace::new_srgb)
+    }
+
+    /// The layer of `size` painted into before the surface when `srgb` is on, in linear light
+    /// with the precision to keep dark shades apart.
+    pub fn linear_layer_info(&self, size: ISize) -> Option<ImageInfo> {
+        self.srgb.then(|| {
+            let color_space = ColorSpace::new_srgb_linear();
+            ImageInfo::new(size, ColorType::RGBAF16, AlphaType::Premul, color_space)
+        })
+    }
+
+    pub fn apply_to_paint(&self, paint: &mut Paint) {
+        paint.set_anti_alias(self.anti_alias);
+    }
+
+    pub fn apply_to_font(&self, font: &mut Font) {
+        let edging = if !self.anti_alias {
+            Edging::Alias
+        } else if self.subpixel_text {
+            Edging::SubpixelAntiAlias
+        } else {
+            Edging::AntiAlias
+        };
+
+        font.set_edging(edging);
+        font.set_subpixel(self.subpixel_text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srgb_is_opt_in() {
+        assert!(RenderOptions::default().color_space().is_none());
+
+        let options = RenderOptions {
+            srgb: true,
+            ..RenderOptions::default()
+        };
+        assert!(options.color_space().is_some());
+        assert!(
+            RenderOptions::default()
+                .linear_layer_info((8, 8).into())
+                .is_none()
+        );
+        assert!(options.linear_layer_info((8, 8).into()).is_some());
+    }
+}
@@ -0,0 +1,41 @@
+use crate::constant::style::STYLE_KEY_WHITE_SPACE;
+use crate::parser::html_node::HTMLNodeRef;
+
+/// How a text node's spaces and newlines are laid out, from its computed `white-space`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WhiteSpace {
+    Normal,
+    Nowrap,
+    Pre,
+    PreWrap,
+    PreLine,
+}
+
+impl WhiteSpace {
+    pub fn new(node: &HTMLNodeRef) -> Self {
+        match node
+            .borrow()
+            .style
+            .get(STYLE_KEY_WHITE_SPACE)
+            .map(|value| value.trim())
+        {
+            Some("nowrap") => Self::Nowrap,
+            Some("pre") => Self::Pre,
+            Some("pre-wrap") => Self::PreWrap,
+            Some("pre-line") => Self::PreLine,
+            _ => Self::Normal,
+        }
+    }
+
+    pub fn preserves_spaces(self) -> bool {
+        matches!(self, Self::Pre | Self::PreWrap)
+    }
+
+    pub fn preserves_newlines(self) -> bool {
+        !matches!(self, Self::Normal | Self::Nowrap)
+    }
+
+    pub fn wraps(self) -> bool {
+        !matches!(self, Self::Nowrap | Self::Pre)
+    }
+}
@@ -1,6 +1,14 @@
 use crate::browser::Browser;
-use net::url::Url;
+use crate::constant::browser::DEFAULT_URL;
+use crate::constant::common::{COLON, EQUALS};
+use crate::layout::render_options::RenderOptions;
+use crate::parser::html_parse::SpecHtmlParser;
+use net::url::{Url, override_host, set_no_network};
+use std::io::Read;
+use std::path::Path;
 
+#[cfg(feature = "bench")]
+mod bench;
 mod browser;
 mod constant;
 mod dom;
@@ -9,10 +17,172 @@ mod layout;
 mod net;
 mod parser;
 
+const USAGE: &str = "Usage: even_browser [options] [url]
+
+Options:
+  --dump-dom              Print the parsed DOM tree and exit
+  --dump-tokens           Print the HTML tokenizer's output and exit
+  --dump-layout           Print the layout tree and exit
+  --dump-headers          Send a HEAD request, print the response headers and exit
+  --dump-rules <tag>      Print the default stylesheet's rules for a tag and exit
+  --dump-preloads         Print the page's preload and prefetch links and exit
+  --accept-invalid-certs  Skip TLS certificate verification
+  --user-agent <string>   Send a custom User-Agent header
+  --host-override <host:port=address:port>
+                          Connect to address:port whenever host:port is requested
+  --no-network            Refuse every request that would go over the network
+  --no-anti-alias         Render without anti-aliasing
+  --srgb                  Blend in linear light and convert to sRGB, for gamma-correct edges
+  --smooth-scroll         Animate scrolling instead of jumping
+  --reader-mode           Cap the content width and center it in the window
+  --stylesheet <path>     Load the default stylesheet from a file
+  --spec-parser           Parse HTML with the spec-based tree builder
+  --stdin                 Show HTML read from stdin instead of fetching the url, which
+                          relative links and stylesheets still resolve against
+  --bench                 Time the HTML and CSS parsers and exit, needs the bench feature";
+
+enum Command {
+    Run,
+    DumpDom,
+    DumpTokens,
+    DumpLayout,
+    DumpHeaders,
+    DumpRules(String),
+    DumpPreloads,
+    #[cfg(feature = "bench")]
+    Bench,
+}
+
 fn main() {
-    let url_str = "https://browser.engineering/styles.html";
-    let url = Url::new(url_str);
-    let mut browser = Browser::new();
-    browser.load(&url);
-    browser.run();
+    let mut command = Command::Run;
+    let mut url_str = DEFAULT_URL.to_string();
+    let mut accept_invalid_certs = false;
+    let mut user_agent = None;
+    let mut render_options = RenderOptions::default();
+    let mut stylesheet = None;
+    let mut smooth_scroll = false;
+    let mut reader_mode = false;
+    let mut spec_parser = false;
+    let mut from_stdin = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dump-dom" => command = Command::DumpDom,
+            "--dump-tokens" => command = Command::DumpTokens,
+            "--dump-layout" => command = Command::DumpLayout,
+            "--dump-headers" => command = Command::DumpHeaders,
+            "--dump-preloads" => command = Command::DumpPreloads,
+            #[cfg(feature = "bench")]
+            "--bench" => command = Command::Bench,
+            "--dump-rules" => match args.next() {
+                Some(tag) => command = Command::DumpRules(tag),
+                None => {
+                    eprintln!("Missing tag for --dump-rules");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--accept-invalid-certs" => accept_invalid_certs = true,
+            "--no-anti-alias" => render_options.anti_alias = false,
+            "--srgb" => render_options.srgb = true,
+            "--smooth-scroll" => smooth_scroll = true,
+            "--reader-mode" => reader_mode = true,
+            "--spec-parser" => spec_parser = true,
+            "--stdin" => from_stdin = true,
+            "--no-network" => set_no_network(true),
+            "--user-agent" => match args.next() {
+                Some(value) => user_agent = Some(value),
+                None => {
+                    eprintln!("Missing value for --user-agent");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--host-override" => match args.next().as_deref().and_then(parse_host_override) {
+                Some((authority, target)) => override_host(&authority, target),
+                None => {
+                    eprintln!("Expected host:port=address:port for --host-override");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            "--stylesheet" => match args.next() {
+                Some(path) => stylesheet = Some(path),
+                None => {
+                    eprintln!("Missing path for --stylesheet");
+                    eprintln!("{}", USAGE);
+                    std::process::exit(1);
+                }
+            },
+            _ if arg.starts_with("--") => {
+                eprintln!("Unknown flag: {}", arg);
+                eprintln!("{}", USAGE);
+                std::process::exit(1);
+            }
+            _ => url_str = arg,
+        }
+    }
+
+    let mut url = Url::new(&url_str);
+    url.accept_invalid_certs = accept_invalid_certs;
+    if let Some(user_agent) = user_agent {
+        url.user_agent = user_agent;
+    }
+    let mut browser = match &stylesheet {
+        Some(path) => Browser::with_stylesheet(Path::new(path)),
+        None => Browser::new(),
+    };
+    browser.set_render_options(render_options);
+    browser.set_smooth_scroll(smooth_scroll);
+    browser.set_reader_mode(reader_mode);
+    if spec_parser {
+        browser.set_html_parser(Box::new(SpecHtmlParser));
+    }
+
+    match command {
+        Command::Run => {
+            if from_stdin {
+                let mut html = String::new();
+                if let Err(err) = std::io::stdin().read_to_string(&mut html) {
+                    eprintln!("Failed to read HTML from stdin: {}", err);
+                    std::process::exit(1);
+                }
+                browser.load_html(&html, &url);
+            } else {
+                browser.navigate(&url);
+            }
+            browser.run();
+        }
+        Command::DumpDom => browser.dump_dom(&url),
+        Command::DumpTokens => browser.dump_tokens(&url),
+        Command::DumpLayout => browser.dump_layout(&url),
+        Command::DumpRules(tag) => browser.dump_rules(&tag),
+        Command::DumpPreloads => browser.dump_preloads(&url),
+        #[cfg(feature = "bench")]
+        Command::Bench => bench::run(),
+        Command::DumpHeaders => match url.request_head(&[]) {
+            Ok(headers) => {
+                let mut headers: Vec<_> = headers.into_iter().collect();
+                headers.sort();
+                for (name, value) in headers {
+                    println!("{}: {}", name, value);
+                }
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+/// `host:port=address:port`, as `("host:port", ("address", port))`.
+fn parse_host_override(value: &str) -> Option<(String, (String, u16))> {
+    let (authority, target) = value.split_once(EQUALS)?;
+    let (host, port) = target.rsplit_once(COLON)?;
+    Some((
+        authority.to_string(),
+        (host.to_string(), port.parse().ok()?),
+    ))
 }
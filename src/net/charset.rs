@@ -0,0 +1,144 @@
+use crate::constant::common::{DOUBLE_QUOTE, EQUALS, GREATER_THAN, SEMICOLON, SINGLE_QUOTE, SLASH};
+use crate::constant::html::{
+    ATTRIBUTE_KEY_CHARSET, ATTRIBUTE_KEY_CONTENT, ATTRIBUTE_KEY_HTTP_EQUIV, HTTP_EQUIV_CONTENT_TYPE,
+};
+use crate::constant::net::{
+    CHARSET_UTF_8_LABELS, CHARSET_WINDOWS_1252_LABELS, CONTENT_TYPE_CHARSET,
+    META_CHARSET_PRESCAN_BYTES,
+};
+use std::collections::HashMap;
+
+// what windows-1252 puts in 0x80..=0x9F, the C1 controls in latin-1, unassigned bytes kept as is
+const WINDOWS_1252_C1: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+/// The `charset` parameter of a `Content-Type` value, lowercase and unquoted.
+pub fn content_type_charset(content_type: &str) -> Option<String> {
+    content_type.split(SEMICOLON).skip(1).find_map(|parameter| {
+        let (key, value) = parameter.split_once(EQUALS)?;
+        (key.trim().eq_ignore_ascii_case(CONTENT_TYPE_CHARSET)).then(|| {
+            value
+                .trim()
+                .trim_matches([DOUBLE_QUOTE, SINGLE_QUOTE])
+                .to_lowercase()
+        })
+    })
+}
+
+/// The charset a `<meta charset>` or `<meta http-equiv content>` near the start of a page
+/// declares, for when the server didn't send one.
+fn meta_charset(bytes: &[u8]) -> Option<String> {
+    let head = &bytes[..bytes.len().min(META_CHARSET_PRESCAN_BYTES)];
+    let head = String::from_utf8_lossy(head).to_lowercase();
+
+    head.match_indices("<meta").find_map(|(start, _)| {
+        let tag = &head[start..];
+        let tag = &tag[..tag.find(GREATER_THAN).unwrap_or(tag.len())];
+        let (_, value) = tag.split_once(CONTENT_TYPE_CHARSET)?;
+        let value = value.trim_start().strip_prefix(EQUALS)?;
+        let value = value
+            .trim_start()
+            .trim_start_matches([DOUBLE_QUOTE, SINGLE_QUOTE]);
+        let end = value
+            .find(|c: char| {
+                c.is_whitespace() || [DOUBLE_QUOTE, SINGLE_QUOTE, SEMICOLON, SLASH].contains(&c)
+            })
+            .unwrap_or(value.len());
+        Some(value[..end].to_string()).filter(|value| !value.is_empty())
+    })
+}
+
+/// The charset a `meta` element declares with `charset`, or with `http-equiv=Content-Type`
+/// and a `content` naming one.
+pub fn meta_element_charset(attributes: &HashMap<String, String>) -> Option<String> {
+    if let Some(charset) = attributes.get(ATTRIBUTE_KEY_CHARSET) {
+        return Some(charset.trim().to_lowercase());
+    }
+
+    let http_equiv = attributes.get(ATTRIBUTE_KEY_HTTP_EQUIV)?;
+    if !http_equiv
+        .trim()
+        .eq_ignore_ascii_case(HTTP_EQUIV_CONTENT_TYPE)
+    {
+        return None;
+    }
+    content_type_charset(attributes.get(ATTRIBUTE_KEY_CONTENT)?)
+}
+
+/// The charset to decode a body in: the server's, else the one a `<meta>` near the start
+/// declares, else UTF-8.
+pub fn detect_charset(bytes: &[u8], header_charset: Option<&str>) -> String {
+    header_charset
+        .map(|charset| charset.to_string())
+        .or_else(|| meta_charset(bytes))
+        .unwrap_or_else(|| CHARSET_UTF_8_LABELS[0].to_string())
+}
+
+/// The encoding a charset label stands for, `None` if it isn't supported.
+fn encoding_name(label: &str) -> Option<&'static str> {
+    if CHARSET_WINDOWS_1252_LABELS.contains(&label) {
+        Some(CHARSET_WINDOWS_1252_LABELS[0])
+    } else if CHARSET_UTF_8_LABELS.contains(&label) {
+        Some(CHARSET_UTF_8_LABELS[0])
+    } else {
+        None
+    }
+}
+
+/// Whether two labels decode the same, e.g. `latin1` and `windows-1252`.
+pub fn same_encoding(a: &str, b: &str) -> bool {
+    let name = |label| encoding_name(label).unwrap_or(CHARSET_UTF_8_LABELS[0]);
+    name(a) == name(b)
+}
+
+/// Decode a body in `charset`, unsupported ones fall back to UTF-8.
+pub fn decode(bytes: &[u8], charset: &str) -> String {
+    match encoding_name(charset) {
+        Some(name) if name == CHARSET_WINDOWS_1252_LABELS[0] => bytes
+            .iter()
+            .map(|&byte| match byte {
+                0x80..=0x9F => WINDOWS_1252_C1[usize::from(byte - 0x80)],
+                _ => char::from(byte),
+            })
+            .collect(),
+        Some(_) => String::from_utf8_lossy(bytes).to_string(),
+        None => {
+            eprintln!("Unsupported charset {}, decoding as utf-8", charset);
+            String::from_utf8_lossy(bytes).to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_latin1() {
+        let latin1 = b"caf\xe9 na\xefve \x93quoted\x94";
+        let charset = content_type_charset("text/html; charset=\"ISO-8859-1\"");
+        assert_eq!(charset.as_deref(), Some("iso-8859-1"));
+        assert_eq!(
+            decode(latin1, &detect_charset(latin1, charset.as_deref())),
+            "caf\u{e9} na\u{ef}ve \u{201C}quoted\u{201D}"
+        );
+
+        // without a header the page's own declaration is used
+        let page = b"<head><meta charset=latin1></head>\xc0 bient\xf4t";
+        assert_eq!(
+            decode(page, &detect_charset(page, None)),
+            "<head><meta charset=latin1></head>\u{c0} bient\u{f4}t"
+        );
+        let page = b"<meta http-equiv=Content-Type content='text/html; charset=windows-1252'>\xe9";
+        assert_eq!(detect_charset(page, None), "windows-1252");
+        assert!(same_encoding("latin1", "windows-1252"));
+
+        // utf-8 stays the default
+        let page = "caf\u{e9}".as_bytes();
+        assert_eq!(decode(page, &detect_charset(page, None)), "caf\u{e9}");
+    }
+}
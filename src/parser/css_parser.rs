@@ -1,7 +1,9 @@
 use crate::constant::common::{
-    CLOSING_BRACE, COLON, DASH, DOT, HASH, OPENING_BRACE, PERCENT, SEMICOLON, SLASH,
+    AT, CLOSING_BRACE, CLOSING_PARENTHESIS, COLON, DASH, DOT, DOUBLE_QUOTE, HASH, OPENING_BRACE,
+    OPENING_PARENTHESIS, PERCENT, SEMICOLON, SINGLE_QUOTE, SLASH,
 };
-use crate::parser::selector::Selector;
+use crate::constant::style::{AT_RULE_IMPORT, URL_FUNCTION};
+use crate::parser::selector::{PseudoClass, PseudoElement, Selector};
 use std::collections::HashMap;
 
 pub type CSSParserError = String;
@@ -13,12 +15,20 @@ pub type CSSRules = Vec<CSSRule>;
 pub struct CSSParser {
     chars: Vec<char>,
     idx: usize,
+    imports: Vec<String>,
+    // what was skipped while parsing, for `Stylesheet::errors`
+    errors: Vec<CSSParserError>,
 }
 
 impl CSSParser {
     pub fn new(s: &str) -> Self {
         let chars: Vec<char> = s.chars().collect();
-        Self { chars, idx: 0 }
+        Self {
+            chars,
+            idx: 0,
+            imports: Vec::new(),
+            errors: Vec::new(),
+        }
     }
 
     fn whitespace(&mut self) -> bool {
@@ -104,13 +114,52 @@ impl CSSParser {
         Ok(self.chars[start..self.idx].iter().collect())
     }
 
+    /// Read a declaration value up to `;` or `}`, keeping internal spaces (e.g. `1px solid black`).
+    /// Semicolons and braces inside quotes or parentheses don't end the value.
+    fn value(&mut self) -> Result<String, CSSParserError> {
+        let start = self.idx;
+        let mut quote: Option<char> = None;
+        let mut depth = 0;
+
+        while self.idx < self.chars.len() {
+            let c = self.chars[self.idx];
+
+            if let Some(q) = quote {
+                if c == q {
+                    quote = None;
+                }
+            } else if c == SINGLE_QUOTE || c == DOUBLE_QUOTE {
+                quote = Some(c);
+            } else if c == OPENING_PARENTHESIS {
+                depth += 1;
+            } else if c == CLOSING_PARENTHESIS && depth > 0 {
+                depth -= 1;
+            } else if depth == 0 && (c == SEMICOLON || c == CLOSING_BRACE) {
+                break;
+            }
+
+            self.idx += 1;
+        }
+
+        let value: String = self.chars[start..self.idx].iter().collect();
+        // `!important` isn't supported, the declaration is treated as a normal one
+        let value = value.trim();
+        let value = value.strip_suffix("!important").unwrap_or(value).trim_end();
+
+        if value.is_empty() {
+            return Err(format!("Error: value idx={} is empty", self.idx));
+        }
+
+        Ok(value.to_string())
+    }
+
     // (property, value)
     fn pair(&mut self) -> Result<(String, String), CSSParserError> {
         let property = self.word()?;
         self.comment_and_whitespace();
         self.literal(COLON)?;
         self.comment_and_whitespace();
-        let value = self.word()?;
+        let value = self.value()?;
         Ok((property.to_lowercase(), value))
     }
 
@@ -131,6 +180,10 @@ impl CSSParser {
         let (property, value) = self.pair()?;
         pairs.insert(property, value);
         self.comment_and_whitespace();
+        // the last declaration may leave out its semicolon
+        if self.chars.get(self.idx) == Some(&CLOSING_BRACE) {
+            return Ok(());
+        }
         self.literal(SEMICOLON)?;
         self.comment_and_whitespace();
         Ok(())
@@ -144,6 +197,7 @@ impl CSSParser {
                 Ok(()) => {}
                 Err(msg) => {
                     println!("{}", msg);
+                    self.errors.push(msg);
 
                     if let Some(why) = self.ignore_until(&[SEMICOLON, CLOSING_BRACE])
                         && why == SEMICOLON
@@ -160,21 +214,119 @@ impl CSSParser {
         Ok(pairs)
     }
 
+    /// A tag and/or `.class`es written together, optionally followed by a `:pseudo-class`
+    /// or `:pseudo-class(argument)`.
+    fn simple_selector(&mut self) -> Result<Selector, CSSParserError> {
+        let word = self.word()?;
+        let mut parts = word.split(DOT);
+        let tag = parts.next().unwrap_or_default();
+
+        let mut selectors = Vec::new();
+        if !tag.is_empty() {
+            selectors.push(Selector::new_tag(tag.to_lowercase()));
+        }
+        for class in parts {
+            if class.is_empty() {
+                return Err(format!("Error: empty class in selector {}", word));
+            }
+            selectors.push(Selector::new_class(class.to_string()));
+        }
+
+        let selector = if selectors.len() == 1 {
+            selectors.remove(0)
+        } else {
+            Selector::new_compound(selectors)
+        };
+
+        if self.idx < self.chars.len() && self.chars[self.idx] == COLON {
+            self.literal(COLON)?;
+            let double_colon = self.idx < self.chars.len() && self.chars[self.idx] == COLON;
+            if double_colon {
+                self.literal(COLON)?;
+            }
+            let name = self.word()?.to_lowercase();
+
+            // `:before` and `:after` are the older spelling of the pseudo-elements
+            if let Some(pseudo_element) = PseudoElement::parse(&name) {
+                return Ok(Selector::new_pseudo_element(selector, pseudo_element));
+            }
+            if double_colon {
+                return Err(format!("Error: unsupported pseudo-element ::{}", name));
+            }
+
+            // `:not()` holds a selector rather than an argument
+            if name == "not" {
+                self.literal(OPENING_PARENTHESIS)?;
+                self.comment_and_whitespace();
+                let inner = self.simple_selector()?;
+                self.comment_and_whitespace();
+                self.literal(CLOSING_PARENTHESIS)?;
+                return Ok(Selector::new_compound(vec![
+                    selector,
+                    Selector::new_not(inner),
+                ]));
+            }
+
+            let mut argument = None;
+            if self.idx < self.chars.len() && self.chars[self.idx] == OPENING_PARENTHESIS {
+                self.literal(OPENING_PARENTHESIS)?;
+                let start = self.idx;
+                self.ignore_until(&[CLOSING_PARENTHESIS, OPENING_BRACE]);
+                argument = Some(self.chars[start..self.idx].iter().collect::<String>());
+                self.literal(CLOSING_PARENTHESIS)?;
+            }
+
+            return match PseudoClass::parse(&name, argument.as_deref()) {
+                Some(pseudo_class) => Ok(Selector::new_pseudo_class(selector, pseudo_class)),
+                None => Err(format!("Error: unsupported pseudo-class :{}", name)),
+            };
+        }
+
+        Ok(selector)
+    }
+
     fn selector(&mut self) -> Result<Selector, CSSParserError> {
-        let mut out = Selector::new_tag(self.word()?.to_lowercase());
+        let mut out = self.simple_selector()?;
 
         self.comment_and_whitespace();
 
         while self.idx < self.chars.len() && self.chars[self.idx] != OPENING_BRACE {
-            let tag = self.word()?;
-            let descendant = Selector::new_tag(tag.to_lowercase());
-            out = Selector::new_descendant(out, descendant);
+            out = match self.simple_selector()? {
+                // the pseudo-element is generated by what the whole selector matches
+                Selector::PseudoElement(selector) => {
+                    let (descendant, pseudo_element) = selector.into_parts();
+                    Selector::new_pseudo_element(
+                        Selector::new_descendant(out, descendant),
+                        pseudo_element,
+                    )
+                }
+                descendant => Selector::new_descendant(out, descendant),
+            };
             self.comment_and_whitespace();
         }
 
         Ok(out)
     }
 
+    /// A statement at-rule like `@import "base.css";`, returns the URL when it's an import.
+    fn at_rule(&mut self) -> Result<Option<String>, CSSParserError> {
+        self.literal(AT)?;
+        let name = self.word()?.to_lowercase();
+        self.comment_and_whitespace();
+        let prelude = self.value()?;
+        self.literal(SEMICOLON)?;
+        self.comment_and_whitespace();
+
+        if name != AT_RULE_IMPORT {
+            return Ok(None);
+        }
+
+        match import_url(&prelude) {
+            Some(url) => Ok(Some(url)),
+            None => Err(format!("Error: invalid @import {}", prelude)),
+        }
+    }
+
     fn parse_sequence(&mut self) -> Result<CSSRule, CSSParserError> {
         self.comment_and_whitespace();
         let selector = self.selector()?;
@@ -189,12 +341,35 @@ impl CSSParser {
         let mut rules = vec![];
 
         while self.idx < self.chars.len() {
+            self.comment_and_whitespace();
+            // whitespace or a comment after the last rule
+            if self.idx >= self.chars.len() {
+                break;
+            }
+
+            if self.chars[self.idx] == AT {
+                match self.at_rule() {
+                    // imports after a style rule are invalid and ignored
+                    Ok(Some(url)) if rules.is_empty() => self.imports.push(url),
+                    Ok(_) => {}
+                    Err(msg) => {
+                        self.errors.push(msg);
+                        if let Some(why) = self.ignore_until(&[SEMICOLON, CLOSING_BRACE]) {
+                            self.literal(why)?;
+                            self.comment_and_whitespace();
+                        }
+                    }
+                }
+                continue;
+            }
+
             match self.parse_sequence() {
                 Ok((selector, body)) => {
                     rules.push((selector, body));
                 }
                 Err(msg) => {
                     println!("{}", msg);
+                    self.errors.push(msg);
                     if let Some(why) = self.ignore_until(&[CLOSING_BRACE])
                         && why == CLOSING_BRACE
                     {
@@ -210,3 +385,166 @@ impl CSSParser {
         Ok(rules)
     }
 }
+
+/// A parsed style sheet: its rules in source order, the `@import`s it asks for and the errors
+/// skipped over parsing it.
+#[derive(Debug, Clone, Default)]
+pub struct Stylesheet {
+    rules: CSSRules,
+    imports: Vec<String>,
+    errors: Vec<CSSParserError>,
+}
+
+impl Stylesheet {
+    pub fn rules(&self) -> &CSSRules {
+        &self.rules
+    }
+
+    /// The URLs of the top-level `@import`s, in order, unresolved.
+    pub fn imports(&self) -> &[String] {
+        &self.imports
+    }
+
+    pub fn errors(&self) -> &[CSSParserError] {
+        &self.errors
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The rules whose selector requires the styled element to be a `tag`, e.g. `p`, `p.intro`
+    /// and `div p` for `p`, but not `.intro`.
+    pub fn rules_for_tag(&self, tag: &str) -> Vec<&CSSRule> {
+        self.rules
+            .iter()
+            .filter(|(selector, _)| selector.subject_tag() == Some(tag))
+            .collect()
+    }
+}
+
+/// Parse a whole style sheet, keeping going past invalid rules and declarations.
+pub fn parse_stylesheet(css: &str) -> Stylesheet {
+    let mut parser = CSSParser::new(css);
+    let rules = parser.parse().unwrap_or_else(|err| {
+        parser.errors.push(err);
+        Vec::new()
+    });
+
+    Stylesheet {
+        rules,
+        imports: parser.imports,
+        errors: parser.errors,
+    }
+}
+
+/// The URL of an `@import` prelude, written as a string or `url(...)`, media queries ignored.
+fn import_url(prelude: &str) -> Option<String> {
+    let (url, quoted) = match prelude.strip_prefix(URL_FUNCTION) {
+        Some(rest) => (&rest[..rest.find(CLOSING_PARENTHESIS)?], false),
+        None => (prelude, true),
+    };
+    let url = url.trim();
+
+    let unquoted = url
+        .strip_prefix(DOUBLE_QUOTE)
+        .and_then(|url| url.split(DOUBLE_QUOTE).next())
+        .or_else(|| {
+            url.strip_prefix(SINGLE_QUOTE)
+                .and_then(|url| url.split(SINGLE_QUOTE).next())
+        });
+
+    match unquoted {
+        Some(url) => Some(url.to_string()),
+        None if !quoted && !url.is_empty() => Some(url.to_string()),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(s: &str) -> CSSRuleBody {
+        CSSParser::new(s).body().unwrap()
+    }
+
+    #[test]
+    fn test_multi_token_value() {
+        let pairs = body("margin: 0 auto;");
+        assert_eq!(pairs.get("margin"), Some(&"0 auto".to_string()));
+    }
+
+    #[test]
+    fn test_font_shorthand_value() {
+        let pairs = body("font: italic 12px; color: red");
+        assert_eq!(pairs.get("font"), Some(&"italic 12px".to_string()));
+        assert_eq!(pairs.get("color"), Some(&"red".to_string()));
+    }
+
+    #[test]
+    fn test_value_ends_at_closing_brace() {
+        let rules = CSSParser::new("p { border: 1px solid black }")
+            .parse()
+            .unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(
+            rules[0].1.get("border"),
+            Some(&"1px solid black".to_string())
+        );
+    }
+
+    #[test]
+    fn test_value_drops_important() {
+        let pairs = body("color: red !important;");
+        assert_eq!(pairs.get("color"), Some(&"red".to_string()));
+    }
+
+    #[test]
+    fn test_value_keeps_quoted_semicolon() {
+        let pairs = body("content: \"a;b\"; color: red;");
+        assert_eq!(pairs.get("content"), Some(&"\"a;b\"".to_string()));
+        assert_eq!(pairs.get("color"), Some(&"red".to_string()));
+    }
+
+    #[test]
+    fn test_import() {
+        let sheet = parse_stylesheet(
+            "@import \"base.css\";\n@import url(print.css) print;\np { color: red; }\n@import 'late.css';",
+        );
+        assert_eq!(sheet.len(), 1);
+        assert_eq!(sheet.imports(), ["base.css", "print.css"]);
+    }
+
+    #[test]
+    fn test_trailing_whitespace_is_not_a_rule() {
+        let sheet = parse_stylesheet("p { color: red }\n");
+        assert_eq!(sheet.len(), 1);
+        assert!(sheet.errors().is_empty());
+
+        let sheet = parse_stylesheet(include_str!("../asset/browser.css"));
+        assert!(sheet.errors().is_empty());
+    }
+
+    #[test]
+    fn test_stylesheet_rules_for_tag() {
+        let sheet = parse_stylesheet(
+            "p { color: red } .intro { color: blue } div p.intro { margin: 0 } h1 { color red; } li::before { content: '-' }",
+        );
+        assert_eq!(sheet.len(), 5);
+        assert!(!sheet.errors().is_empty());
+
+        let bodies: Vec<&CSSRuleBody> =
+            sheet.rules_for_tag("p").into_iter().map(|r| &r.1).collect();
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[0].get("color"), Some(&"red".to_string()));
+        assert_eq!(bodies[1].get("margin"), Some(&"0".to_string()));
+
+        assert_eq!(sheet.rules_for_tag("li").len(), 1);
+        assert!(sheet.rules_for_tag("div").is_empty());
+    }
+}
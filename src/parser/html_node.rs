@@ -32,6 +32,11 @@ pub struct HTMLNode {
     pub children: Vec<HTMLNodeRef>,
     pub is_self_closing_tag: bool,
     pub style: HTMLNodeStyle,
+    // text generated by `::before` and `::after` rules, set by the style pass
+    pub before: Option<String>,
+    pub after: Option<String>,
+    // the style pass changed the node or something in it since it was last laid out
+    pub dirty: bool,
 }
 
 impl HTMLNode {
@@ -42,6 +47,9 @@ impl HTMLNode {
             children: Vec::new(),
             is_self_closing_tag: false,
             style: HashMap::new(),
+            before: None,
+            after: None,
+            dirty: true,
         }))
     }
 
@@ -57,10 +65,12 @@ impl HTMLNode {
             children: Vec::new(),
             is_self_closing_tag,
             style: HashMap::new(),
+            before: None,
+            after: None,
+            dirty: true,
         }))
     }
 
-    #[cfg(debug_assertions)]
     pub fn print_tree(&self, depth: usize) {
         let indent = "  ".repeat(depth);
 
@@ -0,0 +1,141 @@
+use crate::constant::html::SELF_CLOSING_ELEMENTS;
+use crate::dom::node::{Node, NodeSubtype};
+use crate::html_parser::parser::HtmlParser;
+use crate::parser::html_node::{HTMLNode, HTMLNodeRef, HTMLNodeWeakRef};
+use crate::parser::html_parser::HTMLParser;
+use std::rc::Rc;
+
+/// Turns markup into the `HTMLNode` tree that styling and layout work on, so the browser
+/// isn't tied to one parser implementation.
+pub trait HtmlParse {
+    /// Returns the root `html` element, or `None` when nothing could be parsed.
+    fn parse(&self, html: &str) -> Option<HTMLNodeRef>;
+}
+
+/// The parser in `parser::html_parser`, used by default.
+#[derive(Debug, Default)]
+pub struct SimpleHtmlParser;
+
+impl HtmlParse for SimpleHtmlParser {
+    fn parse(&self, html: &str) -> Option<HTMLNodeRef> {
+        HTMLParser::new(html.to_string()).parse()
+    }
+}
+
+/// The spec-based tree construction in `html_parser`, adapted to `HTMLNode`.
+#[derive(Debug, Default)]
+pub struct SpecHtmlParser;
+
+impl HtmlParse for SpecHtmlParser {
+    fn parse(&self, html: &str) -> Option<HTMLNodeRef> {
+        let document = HtmlParser::new(html).parse();
+
+        // the document node itself has no HTMLNode counterpart, its html element is the root
+        document
+            .child_nodes()
+            .iter()
+            .find(|child| child.get_element().is_some())
+            .and_then(|html| to_html_node(html))
+    }
+}
+
+/// Copy a `dom::Node` subtree into `HTMLNode`s, comments are dropped like the simple parser does.
+/// Misnested markup can nest thousands deep, so the subtree is walked with a stack of our own
+/// instead of recursing.
+fn to_html_node(root: &Node) -> Option<HTMLNodeRef> {
+    let html_root = new_html_node(root, None)?;
+    let mut stack = vec![(root, html_root.clone())];
+
+    while let Some((node, html_node)) = stack.pop() {
+        for child in node.child_nodes() {
+            if let Some(html_child) = new_html_node(child, Some(Rc::downgrade(&html_node))) {
+                html_node.borrow_mut().children.push(html_child.clone());
+                stack.push((child, html_child));
+            }
+        }
+    }
+
+    Some(html_root)
+}
+
+/// The `HTMLNode` for a single `dom::Node`, without its children.
+fn new_html_node(node: &Node, parent: Option<HTMLNodeWeakRef>) -> Option<HTMLNodeRef> {
+    let html_node = match node.subtype() {
+        NodeSubtype::Element(e) => {
+            let tag = e.tag_name().to_lowercase();
+            let attributes = e
+                .attributes()
+                .iter()
+                .map(|attr| (attr.name.to_lowercase(), attr.value.to_string()))
+                .collect();
+            let is_self_closing = SELF_CLOSING_ELEMENTS.contains(&tag.as_str());
+            HTMLNode::new_element(parent, tag, attributes, is_self_closing)
+        }
+        NodeSubtype::Text(t) => HTMLNode::new_text(parent, t.data().to_string()),
+        NodeSubtype::Document(_) | NodeSubtype::DocumentType(_) | NodeSubtype::Comment(_) => {
+            return None;
+        }
+    };
+
+    Some(html_node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::html_node::HTMLNodeData;
+    use crate::parser::html_parser::walk_tree;
+
+    fn outline(parser: &dyn HtmlParse, html: &str) -> Vec<String> {
+        let root = parser.parse(html).unwrap();
+        let nodes: Vec<_> = walk_tree(root).collect();
+
+        nodes
+            .iter()
+            .map(|node| match &node.borrow().data {
+                HTMLNodeData::Element(e) => e.tag.clone(),
+                HTMLNodeData::Text(t) => format!("#{}", t.text),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parsers_agree_on_simple_markup() {
+        let html = "<html><head></head><body><div><p>text</p></div></body></html>";
+        let expected = vec!["html", "head", "body", "div", "p", "#text"];
+
+        assert_eq!(outline(&SimpleHtmlParser, html), expected);
+        assert_eq!(outline(&SpecHtmlParser, html), expected);
+    }
+
+    #[test]
+    fn test_spec_adapter_keeps_parents_and_drops_comments() {
+        let root = SpecHtmlParser
+            .parse("<!-- c --><p>a<!-- c -->b</p>")
+            .unwrap();
+        let nodes: Vec<_> = walk_tree(root.clone()).collect();
+
+        let text = nodes
+            .iter()
+            .find(|node| matches!(node.borrow().data, HTMLNodeData::Text(_)))
+            .unwrap();
+        let parent = text.borrow().parent.as_ref().unwrap().upgrade().unwrap();
+        assert!(matches!(&parent.borrow().data, HTMLNodeData::Element(e) if e.tag == "p"));
+
+        assert!(
+            outline(&SpecHtmlParser, "<p>a<!-- c -->b</p>")
+                .iter()
+                .all(|n| !n.contains('c'))
+        );
+    }
+
+    #[test]
+    fn test_spec_adapter_copies_deeply_nested_markup() {
+        let depth = 5000;
+        let html = format!("{}deep", "<span>".repeat(depth));
+        let root = SpecHtmlParser.parse(&html).unwrap();
+
+        // html, head, body, the spans and the text
+        assert_eq!(walk_tree(root.clone()).count(), depth + 4);
+    }
+}
@@ -1,8 +1,12 @@
 use crate::constant::common::{DOUBLE_QUOTE, EQUALS, EXCLAMATION_MARK, SINGLE_QUOTE, SLASH};
 use crate::constant::html::{
-    ATTRIBUTE_KEY_HREF, ATTRIBUTE_KEY_REL, ATTRIBUTE_REL_VALUE_STYLESHEET, BODY, HEAD,
-    HEAD_ELEMENTS, HTML, LINK, SELF_CLOSING_ELEMENTS, SLASH_HEAD, SLASH_HTML,
+    ANCHOR, ATTRIBUTE_KEY_AS, ATTRIBUTE_KEY_HREF, ATTRIBUTE_KEY_ID, ATTRIBUTE_KEY_NAME,
+    ATTRIBUTE_KEY_REL, ATTRIBUTE_REL_VALUE_ICON, ATTRIBUTE_REL_VALUE_STYLESHEET, BASE, BODY,
+    COMMENT_END, COMMENT_START, HEAD, HEAD_ELEMENTS, HTML, INPUT, LINK, META, NOSCRIPT,
+    NOSCRIPT_HEAD_ELEMENTS, PRELOAD_REL_VALUES, SELF_CLOSING_ELEMENTS, SLASH_HEAD, SLASH_HTML,
+    SLASH_NOSCRIPT, TEMPLATE, TEXTAREA, TITLE,
 };
+use crate::net::charset::meta_element_charset;
 use crate::parser::html_node::HTMLNodeRef;
 use crate::parser::html_node::{HTMLNode, HTMLNodeData};
 use std::collections::HashMap;
@@ -33,6 +37,19 @@ impl HTMLParser {
 
         while right < len {
             match chars[right] {
+                '<' if starts_at(&chars, right, COMMENT_START) => {
+                    let text: String = chars[left..right].iter().collect();
+                    if !text.is_empty() {
+                        self.add_text(text);
+                    }
+
+                    // skipped whole, so a `<` or `>` inside isn't taken for a tag and can't
+                    // imply an html or body before the real one
+                    right = (right + COMMENT_START.len()..len)
+                        .find(|&i| starts_at(&chars, i, COMMENT_END))
+                        .map_or(len, |end| end + COMMENT_END.len());
+                    left = right;
+                }
                 '<' => {
                     in_tag = true;
 
@@ -95,11 +112,20 @@ impl HTMLParser {
 
         self.implicit_tags(None);
 
-        let node = HTMLNode::new_text(self.get_parent_weak(), text);
+        let Some(parent_rc) = self.unfinished.last() else {
+            return;
+        };
 
-        if let Some(parent_rc) = self.unfinished.last() {
-            parent_rc.borrow_mut().children.push(node);
+        // merge into the preceding text node, e.g. when an ignored comment split the text
+        if let Some(last_child) = parent_rc.borrow().children.last()
+            && let HTMLNodeData::Text(t) = &mut last_child.borrow_mut().data
+        {
+            t.text.push_str(&text);
+            return;
         }
+
+        let node = HTMLNode::new_text(self.get_parent_weak(), text);
+        parent_rc.borrow_mut().children.push(node);
     }
 
     fn get_parent_weak(&self) -> Option<Weak<RefCell<HTMLNode>>> {
@@ -170,6 +196,14 @@ impl HTMLParser {
                     _ => false,
                 });
 
+            let is_noscript_third =
+                self.unfinished
+                    .get(2)
+                    .map_or(false, |n| match &n.borrow().data {
+                        HTMLNodeData::Element(e) => e.tag == NOSCRIPT,
+                        _ => false,
+                    });
+
             if self.unfinished.is_empty() && tag != HTML {
                 self.add_tag(HTML);
             } else if is_html_root
@@ -188,6 +222,14 @@ impl HTMLParser {
                 && tag != SLASH_HEAD
             {
                 self.add_tag(SLASH_HEAD);
+            } else if is_head_second
+                && is_noscript_third
+                && self.unfinished.len() == 3
+                && !NOSCRIPT_HEAD_ELEMENTS.contains(&tag)
+                && tag != SLASH_NOSCRIPT
+            {
+                // scripting is disabled, so body content in a head noscript is rendered as body content
+                self.add_tag(SLASH_NOSCRIPT);
             } else {
                 break;
             }
@@ -228,20 +270,66 @@ impl HTMLParser {
     }
 }
 
-pub fn tree_to_list(tree: HTMLNodeRef, list: &mut Vec<HTMLNodeRef>) {
-    list.push(tree.clone());
+/// Whether `pattern` appears in `chars` at `index`.
+fn starts_at(chars: &[char], index: usize, pattern: &str) -> bool {
+    pattern
+        .chars()
+        .enumerate()
+        .all(|(offset, c)| chars.get(index + offset) == Some(&c))
+}
+
+/// Visits a subtree depth-first in document order, its root first. Nodes are reached lazily,
+/// so stopping early, e.g. with `find`, leaves the rest of the tree untouched. `template`
+/// contents aren't part of the document, so they're passed over.
+pub struct TreeWalker {
+    // the nodes still to visit, the next one last
+    stack: Vec<HTMLNodeRef>,
+}
+
+impl Iterator for TreeWalker {
+    type Item = HTMLNodeRef;
 
-    for child in tree.borrow().children.iter() {
-        tree_to_list(child.clone(), list);
+    fn next(&mut self) -> Option<HTMLNodeRef> {
+        let node = self.stack.pop()?;
+        let is_template =
+            matches!(&node.borrow().data, HTMLNodeData::Element(e) if e.tag == TEMPLATE);
+        if !is_template {
+            self.stack
+                .extend(node.borrow().children.iter().rev().cloned());
+        }
+        Some(node)
     }
 }
 
-pub fn get_links(node: HTMLNodeRef) -> Vec<String> {
-    let mut node_list = vec![];
-    tree_to_list(node.clone(), &mut node_list);
+pub fn walk_tree(node: HTMLNodeRef) -> TreeWalker {
+    TreeWalker { stack: vec![node] }
+}
 
-    node_list
-        .iter()
+/// Visits a node and then each of its ancestors, up to the root.
+pub struct Ancestors {
+    next: Option<HTMLNodeRef>,
+}
+
+impl Iterator for Ancestors {
+    type Item = HTMLNodeRef;
+
+    fn next(&mut self) -> Option<HTMLNodeRef> {
+        let node = self.next.take()?;
+        self.next = node
+            .borrow()
+            .parent
+            .as_ref()
+            .and_then(|parent| parent.upgrade());
+        Some(node)
+    }
+}
+
+pub fn ancestors(node: HTMLNodeRef) -> Ancestors {
+    Ancestors { next: Some(node) }
+}
+
+pub fn get_links(node: HTMLNodeRef) -> Vec<String> {
+    walk_tree(node)
         .filter_map(|node| match &node.borrow().data {
             HTMLNodeData::Element(e) => {
                 if e.tag == LINK
@@ -258,3 +346,310 @@ pub fn get_links(node: HTMLNodeRef) -> Vec<String> {
         })
         .collect()
 }
+
+/// The `href` and `as` type of every `link` whose `rel` includes `preload` or `prefetch`, the
+/// type empty when `as` is missing.
+pub fn get_preloads(node: HTMLNodeRef) -> Vec<(String, String)> {
+    walk_tree(node)
+        .filter_map(|node| match &node.borrow().data {
+            HTMLNodeData::Element(e)
+                if e.tag == LINK
+                    && e.attributes.get(ATTRIBUTE_KEY_REL).is_some_and(|rel| {
+                        rel.split_whitespace()
+                            .any(|v| PRELOAD_REL_VALUES.iter().any(|p| v.eq_ignore_ascii_case(p)))
+                    }) =>
+            {
+                let href = e.attributes.get(ATTRIBUTE_KEY_HREF)?.clone();
+                let as_type = e.attributes.get(ATTRIBUTE_KEY_AS).cloned();
+                Some((href, as_type.unwrap_or_default()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The `href` of the first `link` whose `rel` includes `icon`, like `icon` or `shortcut icon`.
+pub fn get_favicon(node: HTMLNodeRef) -> Option<String> {
+    walk_tree(node).find_map(|node| match &node.borrow().data {
+        HTMLNodeData::Element(e)
+            if e.tag == LINK
+                && e.attributes.get(ATTRIBUTE_KEY_REL).is_some_and(|rel| {
+                    rel.split_whitespace()
+                        .any(|v| v.eq_ignore_ascii_case(ATTRIBUTE_REL_VALUE_ICON))
+                }) =>
+        {
+            e.attributes.get(ATTRIBUTE_KEY_HREF).cloned()
+        }
+        _ => None,
+    })
+}
+
+/// Links with an `href`, inputs and textareas, in document order, as Tab moves between them.
+pub fn get_focusable(node: HTMLNodeRef) -> Vec<HTMLNodeRef> {
+    walk_tree(node)
+        .filter(|node| match &node.borrow().data {
+            HTMLNodeData::Element(e) => {
+                (e.tag == ANCHOR && e.attributes.contains_key(ATTRIBUTE_KEY_HREF))
+                    || e.tag == INPUT
+                    || e.tag == TEXTAREA
+            }
+            HTMLNodeData::Text(_) => false,
+        })
+        .collect()
+}
+
+/// The `href` of every `a` element, in document order.
+pub fn get_hrefs(node: HTMLNodeRef) -> Vec<String> {
+    walk_tree(node)
+        .filter_map(|node| match &node.borrow().data {
+            HTMLNodeData::Element(e) if e.tag == ANCHOR => {
+                e.attributes.get(ATTRIBUTE_KEY_HREF).cloned()
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The charset the first `meta` declaring one names.
+pub fn get_meta_charset(node: HTMLNodeRef) -> Option<String> {
+    walk_tree(node).find_map(|node| match &node.borrow().data {
+        HTMLNodeData::Element(e) if e.tag == META => meta_element_charset(&e.attributes),
+        _ => None,
+    })
+}
+
+/// What a fragment can point at: elements with an `id`, and `a` elements with a `name`.
+pub fn get_anchors(node: HTMLNodeRef) -> Vec<(String, HTMLNodeRef)> {
+    walk_tree(node)
+        .filter_map(|node| {
+            let name = match &node.borrow().data {
+                HTMLNodeData::Element(e) if e.attributes.contains_key(ATTRIBUTE_KEY_ID) => {
+                    e.attributes.get(ATTRIBUTE_KEY_ID).cloned()
+                }
+                HTMLNodeData::Element(e) if e.tag == ANCHOR => {
+                    e.attributes.get(ATTRIBUTE_KEY_NAME).cloned()
+                }
+                _ => None,
+            };
+            name.map(|name| (name, node))
+        })
+        .collect()
+}
+
+/// The text of the first `title` element, with whitespace collapsed.
+/// `None` if it's missing or blank.
+pub fn document_title(node: HTMLNodeRef) -> Option<String> {
+    let title = walk_tree(node).find(|node| match &node.borrow().data {
+        HTMLNodeData::Element(e) => e.tag == TITLE,
+        HTMLNodeData::Text(_) => false,
+    })?;
+
+    let mut words = Vec::new();
+    for child in &title.borrow().children {
+        if let HTMLNodeData::Text(t) = &child.borrow().data {
+            words.extend(t.text.split_whitespace().map(|w| w.to_string()));
+        }
+    }
+
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" "))
+    }
+}
+
+/// The `href` of the first `base` element, which relative URLs resolve against.
+pub fn get_base_href(node: HTMLNodeRef) -> Option<String> {
+    walk_tree(node).find_map(|node| match &node.borrow().data {
+        HTMLNodeData::Element(e) if e.tag == BASE => e.attributes.get(ATTRIBUTE_KEY_HREF).cloned(),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body_of(root: &HTMLNodeRef) -> HTMLNodeRef {
+        root.borrow()
+            .children
+            .iter()
+            .find(|n| matches!(&n.borrow().data, HTMLNodeData::Element(e) if e.tag == BODY))
+            .cloned()
+            .expect("Missing body")
+    }
+
+    #[test]
+    fn test_text_split_by_comment_is_merged() {
+        let root = HTMLParser::new("<p>hello <!-- ignored --> world</p>".to_string())
+            .parse()
+            .unwrap();
+        let body = body_of(&root);
+        let p = body.borrow().children[0].clone();
+
+        let p = p.borrow();
+        assert_eq!(p.children.len(), 1, "adjacent text should be a single node");
+        match &p.children[0].borrow().data {
+            HTMLNodeData::Text(t) => assert_eq!(t.text, "hello  world"),
+            _ => panic!("expected a text node"),
+        }
+    }
+
+    #[test]
+    fn test_leading_comment_keeps_a_single_html_root() {
+        let html = "<!-- <b>not a tag</b> -> --><html><head><title>t</title></head><body><!-- > --><p>x</p></body></html>";
+        let root = HTMLParser::new(html.to_string()).parse().unwrap();
+
+        let tags: Vec<String> = root
+            .borrow()
+            .children
+            .iter()
+            .map(|child| match &child.borrow().data {
+                HTMLNodeData::Element(e) => e.tag.clone(),
+                HTMLNodeData::Text(t) => t.text.clone(),
+            })
+            .collect();
+        assert!(matches!(&root.borrow().data, HTMLNodeData::Element(e) if e.tag == HTML));
+        assert_eq!(tags, vec![HEAD, BODY]);
+
+        let body = body_of(&root);
+        assert_eq!(body.borrow().children.len(), 1);
+        assert!(
+            matches!(&body.borrow().children[0].borrow().data, HTMLNodeData::Element(e) if e.tag == "p")
+        );
+    }
+
+    #[test]
+    fn test_noscript_in_head_content_moves_to_body() {
+        let root = HTMLParser::new(
+            "<noscript><link rel=stylesheet href=a.css></noscript><noscript><p>No JS</p></noscript>"
+                .to_string(),
+        )
+        .parse()
+        .unwrap();
+
+        let head = root.borrow().children[0].clone();
+        assert!(matches!(&head.borrow().data, HTMLNodeData::Element(e) if e.tag == HEAD));
+        assert_eq!(head.borrow().children.len(), 2);
+        let noscript = head.borrow().children[0].clone();
+        assert!(matches!(
+            &noscript.borrow().children[0].borrow().data,
+            HTMLNodeData::Element(e) if e.tag == LINK
+        ));
+
+        let body = body_of(&root);
+        let p = body.borrow().children[0].clone();
+        assert!(matches!(&p.borrow().data, HTMLNodeData::Element(e) if e.tag == "p"));
+    }
+
+    #[test]
+    fn test_text_around_element_is_not_merged() {
+        let root = HTMLParser::new("<p>a<b>b</b>c</p>".to_string())
+            .parse()
+            .unwrap();
+        let body = body_of(&root);
+        let p = body.borrow().children[0].clone();
+        assert_eq!(p.borrow().children.len(), 3);
+    }
+
+    #[test]
+    fn test_get_preloads() {
+        let root = HTMLParser::new(
+            "<head><link rel=preload href=/font.woff2 as=font><link rel=stylesheet href=a.css><link rel=dns-prefetch href=/dns><link rel=PREFETCH href=/next.html><link rel=preload as=script></head>"
+                .to_string(),
+        )
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            get_preloads(root),
+            vec![
+                ("/font.woff2".to_string(), "font".to_string()),
+                ("/next.html".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_walk_tree_stops_early() {
+        let root = HTMLParser::new("<p>a<b>b</b></p><div>c<i>d</i></div>".to_string())
+            .parse()
+            .unwrap();
+
+        let tags: Vec<String> = walk_tree(root.clone())
+            .filter_map(|node| match &node.borrow().data {
+                HTMLNodeData::Element(e) => Some(e.tag.clone()),
+                HTMLNodeData::Text(_) => None,
+            })
+            .collect();
+        assert_eq!(tags, vec!["html", "body", "p", "b", "div", "i"]);
+
+        let mut visited = 0;
+        let bold = walk_tree(root.clone())
+            .inspect(|_| visited += 1)
+            .find(|node| match &node.borrow().data {
+                HTMLNodeData::Element(e) => e.tag == "b",
+                HTMLNodeData::Text(_) => false,
+            });
+        assert!(bold.is_some());
+        // html, body, p, "a" and b, nothing of the div
+        assert_eq!(visited, 5);
+        assert_eq!(walk_tree(root).count(), 10);
+    }
+
+    #[test]
+    fn test_document_title() {
+        let title = |html: &str| document_title(HTMLParser::new(html.to_string()).parse().unwrap());
+
+        assert_eq!(
+            title("<html><head><title>  My\n  Page </title></head><body>x</body></html>"),
+            Some("My Page".to_string())
+        );
+        assert_eq!(
+            title("<title>Implied head</title><p>x</p>"),
+            Some("Implied head".to_string())
+        );
+        assert_eq!(title("<title> </title><p>x</p>"), None);
+        assert_eq!(title("<p>no title</p>"), None);
+    }
+
+    #[test]
+    fn test_queries_skip_template_contents() {
+        let root = HTMLParser::new(
+            "<template><title>Inert</title><base href=/t/><a href=/inert id=inert>x</a></template><a href=/live>y</a>"
+                .to_string(),
+        )
+        .parse()
+        .unwrap();
+
+        assert_eq!(get_hrefs(root.clone()), vec!["/live".to_string()]);
+        assert_eq!(get_focusable(root.clone()).len(), 1);
+        assert!(get_anchors(root.clone()).is_empty());
+        assert_eq!(document_title(root.clone()), None);
+        assert_eq!(get_base_href(root.clone()), None);
+        // the template itself is still visited
+        let template = walk_tree(root).find(|node| match &node.borrow().data {
+            HTMLNodeData::Element(e) => e.tag == TEMPLATE,
+            HTMLNodeData::Text(_) => false,
+        });
+        assert!(template.is_some_and(|template| !template.borrow().children.is_empty()));
+    }
+
+    #[test]
+    fn test_ancestors_go_up_to_the_root() {
+        let root = HTMLParser::new("<div><p><b>x</b></p></div>".to_string())
+            .parse()
+            .unwrap();
+        let text = walk_tree(root.clone()).last().unwrap();
+
+        let tags: Vec<String> = ancestors(text.clone())
+            .skip(1)
+            .filter_map(|node| match &node.borrow().data {
+                HTMLNodeData::Element(e) => Some(e.tag.clone()),
+                HTMLNodeData::Text(_) => None,
+            })
+            .collect();
+        assert_eq!(tags, vec!["b", "p", "div", "body", "html"]);
+        assert!(Rc::ptr_eq(&ancestors(text).last().unwrap(), &root));
+    }
+}
@@ -1,5 +1,6 @@
 pub mod css_parser;
 pub mod html_node;
+pub mod html_parse;
 pub mod html_parser;
 pub mod style;
 pub mod selector;
@@ -1,6 +1,9 @@
+use crate::constant::html::{ANCHOR, ATTRIBUTE_KEY_CLASS, ATTRIBUTE_KEY_HREF};
 use crate::parser::css_parser::CSSRule;
 use crate::parser::html_node::{HTMLNodeData, HTMLNodeRef};
+use std::collections::HashSet;
 use std::fmt::{Display, Formatter, Result};
+use std::rc::Rc;
 
 pub type Priority = usize;
 
@@ -37,6 +40,85 @@ impl Display for TagSelector {
     }
 }
 
+/// `.name`, matching elements with `name` among their classes.
+#[derive(Debug, Clone)]
+pub struct ClassSelector {
+    class: String,
+    priority: Priority,
+}
+
+impl ClassSelector {
+    pub fn new(class: String) -> Self {
+        Self {
+            class,
+            priority: 10,
+        }
+    }
+
+    pub fn matches(&self, node: HTMLNodeRef) -> bool {
+        match &node.borrow().data {
+            HTMLNodeData::Element(e) => e
+                .attributes
+                .get(ATTRIBUTE_KEY_CLASS)
+                .is_some_and(|classes| classes.split_whitespace().any(|c| c == self.class)),
+            HTMLNodeData::Text(_) => false,
+        }
+    }
+
+    fn get_priority(&self) -> Priority {
+        self.priority
+    }
+}
+
+impl Display for ClassSelector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "ClassSelector(class={}, priority={})",
+            self.class, self.priority
+        )
+    }
+}
+
+/// Simple selectors written together, e.g. `p.intro`, that must all match the same node.
+#[derive(Debug, Clone)]
+pub struct CompoundSelector {
+    selectors: Vec<Selector>,
+    priority: Priority,
+}
+
+impl CompoundSelector {
+    pub fn new(selectors: Vec<Selector>) -> Self {
+        let priority = selectors.iter().map(|s| s.get_priority()).sum();
+        Self {
+            selectors,
+            priority,
+        }
+    }
+
+    pub fn matches(&self, node: HTMLNodeRef, visited: &HashSet<String>) -> bool {
+        self.selectors
+            .iter()
+            .all(|selector| selector.matches(node.clone(), visited))
+    }
+
+    fn get_priority(&self) -> Priority {
+        self.priority
+    }
+}
+
+impl Display for CompoundSelector {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let selectors: Vec<String> = self.selectors.iter().map(|s| s.to_string()).collect();
+        write!(
+            f,
+            "CompoundSelector(selectors=[{}], priority={})",
+            selectors.join(", "),
+            self.priority
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DescendantSelector {
     ancestor: Box<Selector>,
@@ -54,8 +136,8 @@ impl DescendantSelector {
         }
     }
 
-    pub fn matches(&self, node: HTMLNodeRef) -> bool {
-        if !self.descendant.matches(node.clone()) {
+    pub fn matches(&self, node: HTMLNodeRef, visited: &HashSet<String>) -> bool {
+        if !self.descendant.matches(node.clone(), visited) {
             return false;
         }
 
@@ -70,7 +152,7 @@ impl DescendantSelector {
             };
 
             if let Some(parent_rc) = parent_rc {
-                if self.ancestor.matches(parent_rc.clone()) {
+                if self.ancestor.matches(parent_rc.clone(), visited) {
                     return true;
                 }
 
@@ -98,10 +180,258 @@ impl Display for DescendantSelector {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum PseudoClass {
+    FirstChild,
+    LastChild,
+    // matches the 1-based positions a*n+b for some n >= 0
+    NthChild(i32, i32),
+    // `a[href]` elements not visited and visited yet
+    Link,
+    Visited,
+}
+
+impl PseudoClass {
+    /// `argument` is the text inside the parentheses, for functional pseudo-classes.
+    pub fn parse(name: &str, argument: Option<&str>) -> Option<Self> {
+        match (name, argument) {
+            ("first-child", None) => Some(Self::FirstChild),
+            ("last-child", None) => Some(Self::LastChild),
+            ("link", None) => Some(Self::Link),
+            ("visited", None) => Some(Self::Visited),
+            ("nth-child", Some(argument)) => {
+                let (a, b) = parse_an_plus_b(argument)?;
+                Some(Self::NthChild(a, b))
+            }
+            _ => None,
+        }
+    }
+
+    fn matches(&self, index: usize, count: usize) -> bool {
+        let index = index as i32;
+        match *self {
+            Self::FirstChild => index == 1,
+            Self::LastChild => index == count as i32,
+            Self::NthChild(0, b) => index == b,
+            Self::NthChild(a, b) => (index - b) % a == 0 && (index - b) / a >= 0,
+            Self::Link | Self::Visited => false,
+        }
+    }
+}
+
+/// Parse `odd`, `even`, an integer, or the `an+b` form into `(a, b)`.
+fn parse_an_plus_b(argument: &str) -> Option<(i32, i32)> {
+    let argument: String = argument
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+
+    match argument.as_str() {
+        "odd" => return Some((2, 1)),
+        "even" => return Some((2, 0)),
+        _ => {}
+    }
+
+    let Some((a, b)) = argument.split_once('n') else {
+        return Some((0, argument.parse().ok()?));
+    };
+
+    let a = match a {
+        "" | "+" => 1,
+        "-" => -1,
+        _ => a.parse().ok()?,
+    };
+    let b = if b.is_empty() { 0 } else { b.parse().ok()? };
+
+    Some((a, b))
+}
+
+/// A simple selector with a structural or link pseudo-class, e.g. `li:first-child`.
+#[derive(Debug, Clone)]
+pub struct PseudoClassSelector {
+    base: Box<Selector>,
+    pseudo_class: PseudoClass,
+    priority: Priority,
+}
+
+impl PseudoClassSelector {
+    pub fn new(base: Selector, pseudo_class: PseudoClass) -> Self {
+        // a pseudo-class weighs as much as a class
+        let priority = base.get_priority() + 10;
+        Self {
+            base: Box::new(base),
+            pseudo_class,
+            priority,
+        }
+    }
+
+    /// `visited` holds the `href`s, as written in the page, of the links already visited.
+    pub fn matches(&self, node: HTMLNodeRef, visited: &HashSet<String>) -> bool {
+        if !self.base.matches(node.clone(), visited) {
+            return false;
+        }
+
+        if let PseudoClass::Link | PseudoClass::Visited = self.pseudo_class {
+            let href = match &node.borrow().data {
+                HTMLNodeData::Element(e) if e.tag == ANCHOR => {
+                    e.attributes.get(ATTRIBUTE_KEY_HREF).cloned()
+                }
+                _ => None,
+            };
+            return href.is_some_and(|href| {
+                visited.contains(&href) == (self.pseudo_class == PseudoClass::Visited)
+            });
+        }
+
+        let parent_rc = match &node.borrow().parent {
+            Some(parent_weak) => parent_weak.upgrade(),
+            None => None,
+        };
+        let Some(parent_rc) = parent_rc else {
+            return false;
+        };
+
+        let parent = parent_rc.borrow();
+        // text between elements doesn't count as a child here
+        let elements: Vec<&HTMLNodeRef> = parent
+            .children
+            .iter()
+            .filter(|child| matches!(child.borrow().data, HTMLNodeData::Element(_)))
+            .collect();
+
+        let Some(position) = elements.iter().position(|child| Rc::ptr_eq(child, &node)) else {
+            return false;
+        };
+
+        self.pseudo_class.matches(position + 1, elements.len())
+    }
+
+    fn get_priority(&self) -> Priority {
+        self.priority
+    }
+}
+
+impl Display for PseudoClassSelector {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(
+            f,
+            "PseudoClassSelector(base={}, pseudo_class={:?}, priority={})",
+            self.base, self.pseudo_class, self.priority
+        )
+    }
+}
+
+/// `:not(inner)`, matching the elements `inner` doesn't. It adds the weight of `inner`.
+#[derive(Debug, Clone)]
+pub struct NotSelector {
+    inner: Box<Selector>,
+    priority: Priority,
+}
+
+impl NotSelector {
+    pub fn new(inner: Selector) -> Self {
+        let priority = inner.get_priority();
+        Self {
+            inner: Box::new(inner),
+            priority,
+        }
+    }
+
+    pub fn matches(&self, node: HTMLNodeRef, visited: &HashSet<String>) -> bool {
+        let is_element = matches!(node.borrow().data, HTMLNodeData::Element(_));
+        is_element && !self.inner.matches(node, visited)
+    }
+
+    fn get_priority(&self) -> Priority {
+        self.priority
+    }
+}
+
+impl Display for NotSelector {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(
+            f,
+            "NotSelector(inner={}, priority={})",
+            self.inner, self.priority
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PseudoElement {
+    Before,
+    After,
+}
+
+impl PseudoElement {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "before" => Some(Self::Before),
+            "after" => Some(Self::After),
+            _ => None,
+        }
+    }
+}
+
+/// Generated content around the elements `base` matches, e.g. `p::before`. It never matches
+/// an element itself, the style pass looks for it separately.
+#[derive(Debug, Clone)]
+pub struct PseudoElementSelector {
+    base: Box<Selector>,
+    pseudo_element: PseudoElement,
+    priority: Priority,
+}
+
+impl PseudoElementSelector {
+    pub fn new(base: Selector, pseudo_element: PseudoElement) -> Self {
+        // a pseudo-element weighs as much as a tag
+        let priority = base.get_priority() + 1;
+        Self {
+            base: Box::new(base),
+            pseudo_element,
+            priority,
+        }
+    }
+
+    /// Whether the element generates this pseudo-element.
+    pub fn matches(&self, node: HTMLNodeRef, visited: &HashSet<String>) -> bool {
+        self.base.matches(node, visited)
+    }
+
+    pub fn pseudo_element(&self) -> PseudoElement {
+        self.pseudo_element
+    }
+
+    /// The element selector and the pseudo-element it generates.
+    pub fn into_parts(self) -> (Selector, PseudoElement) {
+        (*self.base, self.pseudo_element)
+    }
+
+    fn get_priority(&self) -> Priority {
+        self.priority
+    }
+}
+
+impl Display for PseudoElementSelector {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(
+            f,
+            "PseudoElementSelector(base={}, pseudo_element={:?}, priority={})",
+            self.base, self.pseudo_element, self.priority
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Selector {
     Tag(TagSelector),
+    Class(ClassSelector),
+    Compound(CompoundSelector),
     Descendant(DescendantSelector),
+    PseudoClass(PseudoClassSelector),
+    Not(NotSelector),
+    PseudoElement(PseudoElementSelector),
 }
 
 impl Selector {
@@ -109,21 +439,63 @@ impl Selector {
         Self::Tag(TagSelector::new(tag))
     }
 
+    pub fn new_class(class: String) -> Self {
+        Self::Class(ClassSelector::new(class))
+    }
+
+    pub fn new_compound(selectors: Vec<Selector>) -> Self {
+        Self::Compound(CompoundSelector::new(selectors))
+    }
+
     pub fn new_descendant(ancestor: Selector, descendant: Selector) -> Self {
         Self::Descendant(DescendantSelector::new(ancestor, descendant))
     }
 
-    pub fn matches(&self, node: HTMLNodeRef) -> bool {
+    pub fn new_pseudo_class(base: Selector, pseudo_class: PseudoClass) -> Self {
+        Self::PseudoClass(PseudoClassSelector::new(base, pseudo_class))
+    }
+
+    pub fn new_not(inner: Selector) -> Self {
+        Self::Not(NotSelector::new(inner))
+    }
+
+    pub fn new_pseudo_element(base: Selector, pseudo_element: PseudoElement) -> Self {
+        Self::PseudoElement(PseudoElementSelector::new(base, pseudo_element))
+    }
+
+    pub fn matches(&self, node: HTMLNodeRef, visited: &HashSet<String>) -> bool {
         match self {
             Selector::Tag(selector) => selector.matches(node),
-            Selector::Descendant(selector) => selector.matches(node),
+            Selector::Class(selector) => selector.matches(node),
+            Selector::Compound(selector) => selector.matches(node, visited),
+            Selector::Descendant(selector) => selector.matches(node, visited),
+            Selector::PseudoClass(selector) => selector.matches(node, visited),
+            Selector::Not(selector) => selector.matches(node, visited),
+            Selector::PseudoElement(_) => false,
+        }
+    }
+
+    /// The tag the selected element must have, `None` when it can be any.
+    pub fn subject_tag(&self) -> Option<&str> {
+        match self {
+            Self::Tag(tag) => Some(&tag.tag),
+            Self::Compound(compound) => compound.selectors.iter().find_map(|s| s.subject_tag()),
+            Self::Descendant(descendant) => descendant.descendant.subject_tag(),
+            Self::PseudoClass(pseudo_class) => pseudo_class.base.subject_tag(),
+            Self::PseudoElement(pseudo_element) => pseudo_element.base.subject_tag(),
+            Self::Class(_) | Self::Not(_) => None,
         }
     }
 
     pub fn get_priority(&self) -> Priority {
         match self {
             Selector::Tag(selector) => selector.get_priority(),
+            Selector::Class(selector) => selector.get_priority(),
+            Selector::Compound(selector) => selector.get_priority(),
             Selector::Descendant(selector) => selector.get_priority(),
+            Selector::PseudoClass(selector) => selector.get_priority(),
+            Selector::Not(selector) => selector.get_priority(),
+            Selector::PseudoElement(selector) => selector.get_priority(),
         }
     }
 }
@@ -134,9 +506,24 @@ impl Display for Selector {
             Selector::Tag(t) => {
                 write!(f, "{}", t)
             }
+            Selector::Class(c) => {
+                write!(f, "{}", c)
+            }
+            Selector::Compound(c) => {
+                write!(f, "{}", c)
+            }
             Selector::Descendant(d) => {
                 write!(f, "{}", d)
             }
+            Selector::PseudoClass(p) => {
+                write!(f, "{}", p)
+            }
+            Selector::Not(n) => {
+                write!(f, "{}", n)
+            }
+            Selector::PseudoElement(p) => {
+                write!(f, "{}", p)
+            }
         }
     }
 }
@@ -144,3 +531,151 @@ impl Display for Selector {
 pub fn cascade_priority(rule: &CSSRule) -> Priority {
     rule.0.get_priority()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constant::style::{STYLE_KEY_BACKGROUND_COLOR, STYLE_KEY_COLOR};
+    use crate::parser::css_parser::CSSParser;
+    use crate::parser::html_parser::{HTMLParser, walk_tree};
+    use crate::parser::style::style;
+
+    fn list_item_colors(css: &str) -> Vec<Option<String>> {
+        let root = HTMLParser::new("<ul><li>a</li> <li>b</li> <li>c</li></ul>".to_string())
+            .parse()
+            .unwrap();
+        let mut rules = CSSParser::new(css).parse().unwrap();
+        rules.sort_by_key(cascade_priority);
+        style(root.clone(), &rules, &HashSet::new());
+
+        let nodes: Vec<_> = walk_tree(root).collect();
+        nodes
+            .iter()
+            .filter(|node| matches!(&node.borrow().data, HTMLNodeData::Element(e) if e.tag == "li"))
+            .map(|node| node.borrow().style.get(STYLE_KEY_COLOR).cloned())
+            .collect()
+    }
+
+    #[test]
+    fn test_an_plus_b() {
+        assert_eq!(parse_an_plus_b("odd"), Some((2, 1)));
+        assert_eq!(parse_an_plus_b("even"), Some((2, 0)));
+        assert_eq!(parse_an_plus_b("3"), Some((0, 3)));
+        assert_eq!(parse_an_plus_b("2n + 1"), Some((2, 1)));
+        assert_eq!(parse_an_plus_b("-n+3"), Some((-1, 3)));
+        assert_eq!(parse_an_plus_b("n"), Some((1, 0)));
+        assert_eq!(parse_an_plus_b("3n-2"), Some((3, -2)));
+        assert_eq!(parse_an_plus_b("x"), None);
+
+        let first_three = PseudoClass::NthChild(-1, 3);
+        let matched: Vec<usize> = (1..=5).filter(|i| first_three.matches(*i, 5)).collect();
+        assert_eq!(matched, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_nth_child_even_rows() {
+        let root = HTMLParser::new(
+            "<table><tr><td>1</td></tr><tr><td>2</td></tr><tr><td>3</td></tr><tr><td>4</td></tr></table>"
+                .to_string(),
+        )
+        .parse()
+        .unwrap();
+        let mut rules = CSSParser::new("tr:nth-child(even) { background-color: gray }")
+            .parse()
+            .unwrap();
+        rules.sort_by_key(cascade_priority);
+        style(root.clone(), &rules, &HashSet::new());
+
+        let nodes: Vec<_> = walk_tree(root).collect();
+        let striped: Vec<bool> = nodes
+            .iter()
+            .filter(|node| matches!(&node.borrow().data, HTMLNodeData::Element(e) if e.tag == "tr"))
+            .map(|node| {
+                node.borrow()
+                    .style
+                    .get(STYLE_KEY_BACKGROUND_COLOR)
+                    .is_some_and(|c| c == "gray")
+            })
+            .collect();
+        assert_eq!(striped, vec![false, true, false, true]);
+    }
+
+    #[test]
+    fn test_compound_tag_and_class() {
+        let root = HTMLParser::new(
+            "<p class=\"intro\">a</p><p>b</p><div class=\"intro\">c</div>".to_string(),
+        )
+        .parse()
+        .unwrap();
+        let mut rules = CSSParser::new("p.intro { color: red } .intro { background-color: gray }")
+            .parse()
+            .unwrap();
+        rules.sort_by_key(cascade_priority);
+        assert_eq!(rules[1].0.get_priority(), 11);
+        style(root.clone(), &rules, &HashSet::new());
+
+        let nodes: Vec<_> = walk_tree(root).collect();
+        let styled: Vec<(Option<String>, Option<String>)> = nodes
+            .iter()
+            .filter(|node| match &node.borrow().data {
+                HTMLNodeData::Element(e) => e.tag == "p" || e.tag == "div",
+                HTMLNodeData::Text(_) => false,
+            })
+            .map(|node| {
+                let node = node.borrow();
+                (
+                    node.style.get(STYLE_KEY_COLOR).cloned(),
+                    node.style.get(STYLE_KEY_BACKGROUND_COLOR).cloned(),
+                )
+            })
+            .collect();
+
+        assert_eq!(styled[0].0, Some("red".to_string()));
+        assert_ne!(styled[1].0, Some("red".to_string()));
+        assert_ne!(styled[2].0, Some("red".to_string()));
+        // the class alone still matches both
+        assert_eq!(styled[0].1, Some("gray".to_string()));
+        assert_ne!(styled[1].1, Some("gray".to_string()));
+        assert_eq!(styled[2].1, Some("gray".to_string()));
+    }
+
+    #[test]
+    fn test_not_class() {
+        let root = HTMLParser::new(
+            "<p>a</p><p class=\"special\">b</p><p class=\"other\">c</p>".to_string(),
+        )
+        .parse()
+        .unwrap();
+        let rules = CSSParser::new("p:not(.special) { color: red }")
+            .parse()
+            .unwrap();
+        assert_eq!(rules[0].0.get_priority(), 11);
+        style(root.clone(), &rules, &HashSet::new());
+
+        let nodes: Vec<_> = walk_tree(root).collect();
+        let colors: Vec<Option<String>> = nodes
+            .iter()
+            .filter(|node| matches!(&node.borrow().data, HTMLNodeData::Element(e) if e.tag == "p"))
+            .map(|node| node.borrow().style.get(STYLE_KEY_COLOR).cloned())
+            .collect();
+
+        assert_eq!(colors[0], Some("red".to_string()));
+        assert_ne!(colors[1], Some("red".to_string()));
+        assert_eq!(colors[2], Some("red".to_string()));
+    }
+
+    #[test]
+    fn test_first_child() {
+        let colors = list_item_colors("li:first-child { color: red }");
+        assert_eq!(colors[0], Some("red".to_string()));
+        assert_ne!(colors[1], Some("red".to_string()));
+        assert_ne!(colors[2], Some("red".to_string()));
+    }
+
+    #[test]
+    fn test_last_child_beats_tag() {
+        let colors = list_item_colors("li:last-child { color: blue } li { color: green }");
+        assert_eq!(colors[0], Some("green".to_string()));
+        assert_eq!(colors[2], Some("blue".to_string()));
+    }
+}
@@ -1,10 +1,19 @@
-use crate::constant::common::PERCENT;
+use crate::constant::common::{
+    CLOSING_PARENTHESIS, DOUBLE_QUOTE, OPENING_PARENTHESIS, SINGLE_QUOTE,
+};
 use crate::constant::html::ATTRIBUTE_KEY_STYLE;
 use crate::constant::style::{
-    DEFAULT_FONT_SIZE, STYLE_KEY_FONT_SIZE, UNIT_PIXEL, get_inherited_properties,
+    BACKGROUND_COLOR_DEFAULT_VALUE, CLOSE_QUOTE_MARK, CONTENT_CLOSE_QUOTE, CONTENT_OPEN_QUOTE,
+    DEFAULT_COLOR_STR, DEFAULT_FONT_SIZE, DEFAULT_FONT_SIZE_NUM, FONT_SIZE_KEYWORDS,
+    KEYWORD_CURRENT_COLOR, KEYWORD_INHERIT, KEYWORD_INITIAL, OPEN_QUOTE_MARK, STYLE_KEY_BACKGROUND,
+    STYLE_KEY_BACKGROUND_COLOR, STYLE_KEY_COLOR, STYLE_KEY_CONTENT, STYLE_KEY_FONT_SIZE,
+    UNIT_PIXEL, get_inherited_properties,
 };
+use crate::layout::length::{LengthContext, parse_length};
 use crate::parser::css_parser::{CSSParser, CSSRuleBody, CSSRules};
 use crate::parser::html_node::{HTMLNodeData, HTMLNodeRef, HTMLNodeWeakRef};
+use crate::parser::selector::{PseudoElement, Selector};
+use std::collections::{HashMap, HashSet};
 
 fn inherited_style(node_rc: HTMLNodeRef) {
     let node = &mut *node_rc.borrow_mut();
@@ -22,26 +31,116 @@ fn inherited_style(node_rc: HTMLNodeRef) {
     }
 }
 
-fn matched_rules(node_rc: HTMLNodeRef, rules: &CSSRules) -> Vec<&CSSRuleBody> {
+fn matched_rules<'a>(
+    node_rc: HTMLNodeRef,
+    rules: &'a CSSRules,
+    visited: &HashSet<String>,
+) -> Vec<&'a CSSRuleBody> {
     let mut matched_rules = Vec::new();
     for (selector, body) in rules.iter() {
-        if selector.matches(node_rc.clone()) {
+        if selector.matches(node_rc.clone(), visited) {
             matched_rules.push(body);
         }
     }
     matched_rules
 }
 
-fn external_style(node_rc: HTMLNodeRef, rules: &CSSRules) {
-    let matched_rules = matched_rules(node_rc.clone(), rules);
+fn external_style(node_rc: HTMLNodeRef, rules: &CSSRules, visited: &HashSet<String>) {
+    let matched_rules = matched_rules(node_rc.clone(), rules, visited);
 
     let node = &mut *node_rc.borrow_mut();
 
     for body in matched_rules.iter() {
-        for (property, value) in body.iter() {
-            node.style.insert(property.to_string(), value.to_string());
+        apply_declarations(&mut node.style, body);
+    }
+}
+
+/// Copy a rule body into the style, expanding shorthands first so the longhands written
+/// beside them win.
+fn apply_declarations(style: &mut HashMap<String, String>, body: &CSSRuleBody) {
+    if let Some(value) = body.get(STYLE_KEY_BACKGROUND) {
+        style.insert(
+            STYLE_KEY_BACKGROUND_COLOR.to_string(),
+            background_color(value).to_string(),
+        );
+    }
+
+    for (property, value) in body.iter() {
+        if property != STYLE_KEY_BACKGROUND {
+            style.insert(property.to_string(), value.to_string());
+        }
+    }
+}
+
+/// The color in a `background` shorthand, skipping the image, position and repeat parts.
+/// The shorthand resets the color, so without one the background is transparent.
+fn background_color(value: &str) -> &str {
+    let mut depth = 0;
+    let mut start = 0;
+    let mut tokens = Vec::new();
+    for (idx, c) in value.char_indices() {
+        match c {
+            OPENING_PARENTHESIS => depth += 1,
+            CLOSING_PARENTHESIS => depth -= 1,
+            c if c.is_whitespace() && depth == 0 => {
+                tokens.push(&value[start..idx]);
+                start = idx + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    tokens.push(&value[start..]);
+
+    tokens
+        .into_iter()
+        .find(|token| !token.is_empty() && csscolorparser::parse(token).is_ok())
+        .unwrap_or(BACKGROUND_COLOR_DEFAULT_VALUE)
+}
+
+/// Record the `content` of the `::before` and `::after` rules for the element, the last
+/// matching rule of each winning.
+fn generated_content(node_rc: HTMLNodeRef, rules: &CSSRules, visited: &HashSet<String>) {
+    let mut before = None;
+    let mut after = None;
+
+    if let HTMLNodeData::Element(_) = node_rc.borrow().data {
+        for (selector, body) in rules.iter() {
+            let Selector::PseudoElement(selector) = selector else {
+                continue;
+            };
+            if !selector.matches(node_rc.clone(), visited) {
+                continue;
+            }
+            let Some(content) = body.get(STYLE_KEY_CONTENT) else {
+                continue;
+            };
+
+            match selector.pseudo_element() {
+                PseudoElement::Before => before = content_string(content),
+                PseudoElement::After => after = content_string(content),
+            }
         }
     }
+
+    let node = &mut *node_rc.borrow_mut();
+    node.before = before;
+    node.after = after;
+}
+
+/// The text of a quoted `content` value or a quote keyword, `None` for anything else.
+fn content_string(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case(CONTENT_OPEN_QUOTE) {
+        return Some(OPEN_QUOTE_MARK.to_string());
+    }
+    if value.eq_ignore_ascii_case(CONTENT_CLOSE_QUOTE) {
+        return Some(CLOSE_QUOTE_MARK.to_string());
+    }
+
+    [DOUBLE_QUOTE, SINGLE_QUOTE].into_iter().find_map(|quote| {
+        let inner = value.strip_prefix(quote)?.strip_suffix(quote)?;
+        Some(inner.to_string())
+    })
 }
 
 fn inline_style(node_rc: HTMLNodeRef) {
@@ -50,63 +149,127 @@ fn inline_style(node_rc: HTMLNodeRef) {
         && let Some(style) = e.attributes.get(ATTRIBUTE_KEY_STYLE)
     {
         if let Ok(pairs) = CSSParser::new(style).body() {
-            node.style.extend(pairs);
+            apply_declarations(&mut node.style, &pairs);
         }
     }
 }
 
-fn percentage_font_size(node_rc: HTMLNodeRef) {
+/// Replace `inherit`, `initial` and `currentColor` with the values they stand for. `color`
+/// goes first, as `currentColor` anywhere else is the element's own color.
+fn css_wide_keywords(node_rc: HTMLNodeRef) {
     let node = &mut *node_rc.borrow_mut();
+    let parent_rc = node.parent.as_ref().and_then(|parent| parent.upgrade());
+    let parent_value = |property: &str| {
+        parent_rc
+            .as_ref()
+            .and_then(|parent| parent.borrow().style.get(property).cloned())
+    };
 
-    let current_font_size = node.style.get(STYLE_KEY_FONT_SIZE).cloned();
+    let mut properties: Vec<String> = node.style.keys().cloned().collect();
+    properties.sort_by_key(|property| property != STYLE_KEY_COLOR);
 
-    if let Some(current_val) = current_font_size
-        && current_val.ends_with(PERCENT)
-    {
-        let parent_font_size = get_parent_font_size(node.parent.clone());
+    for property in properties {
+        let value = node.style[&property].trim().to_lowercase();
+        let resolved = match value.as_str() {
+            KEYWORD_INHERIT => parent_value(&property),
+            // in `color` itself it means the inherited color
+            KEYWORD_CURRENT_COLOR if property == STYLE_KEY_COLOR => parent_value(&property),
+            KEYWORD_CURRENT_COLOR => Some(
+                node.style
+                    .get(STYLE_KEY_COLOR)
+                    .map_or(DEFAULT_COLOR_STR.to_string(), |color| color.to_string()),
+            ),
+            KEYWORD_INITIAL => None,
+            _ => continue,
+        };
 
-        let node_pct = current_val
-            .trim_end_matches(PERCENT)
-            .parse::<f32>()
-            .unwrap_or(0.0)
-            / 100.0;
+        // without a value the property falls back to its default, for the inherited ones
+        // that's the one they start from
+        let resolved = resolved.or_else(|| {
+            get_inherited_properties()
+                .get(property.as_str())
+                .map(|default_value| default_value.to_string())
+        });
+        match resolved {
+            Some(resolved) => node.style.insert(property, resolved),
+            None => node.style.remove(&property),
+        };
+    }
+}
 
-        let parent_px = parent_font_size
-            .trim_end_matches(UNIT_PIXEL)
-            .parse::<f32>()
-            .unwrap_or(16.0);
+/// Resolve `%`, `em` and keyword font sizes to px, so layout only has to parse px.
+fn computed_font_size(node_rc: HTMLNodeRef) {
+    let node = &mut *node_rc.borrow_mut();
 
-        let new_size = node_pct * parent_px;
+    let Some(current_val) = node.style.get(STYLE_KEY_FONT_SIZE).cloned() else {
+        return;
+    };
+    let current_val = current_val.trim().to_lowercase();
 
-        node.style.insert(
-            STYLE_KEY_FONT_SIZE.to_string(),
-            format!("{}{}", new_size, UNIT_PIXEL),
-        );
-    }
-}
+    let parent_px = parse_length(
+        &get_parent_font_size(node.parent.clone()),
+        &LengthContext::default(),
+    )
+    .unwrap_or(DEFAULT_FONT_SIZE_NUM as f32);
+    let context = LengthContext {
+        font_size: parent_px,
+        percent_base: Some(parent_px),
+    };
 
-pub fn style(node_rc: HTMLNodeRef, rules: &CSSRules) {
+    let new_size = if let Some((_, scale)) = FONT_SIZE_KEYWORDS
+        .iter()
+        .find(|(keyword, _)| *keyword == current_val)
     {
+        scale * DEFAULT_FONT_SIZE_NUM as f32
+    } else if let Some(size) = parse_length(&current_val, &context).filter(|size| *size >= 0.0) {
+        size
+    } else {
+        return;
+    };
+
+    node.style.insert(
+        STYLE_KEY_FONT_SIZE.to_string(),
+        format!("{}{}", new_size, UNIT_PIXEL),
+    );
+}
+
+/// Compute the style of the node and its descendants, marking dirty for layout the nodes whose
+/// style changed and those containing one. `visited` holds the `href`s of the visited links,
+/// for `:link` and `:visited`.
+pub fn style(node_rc: HTMLNodeRef, rules: &CSSRules, visited: &HashSet<String>) {
+    let (previous, previous_content) = {
         let node = &mut *node_rc.borrow_mut();
-        node.style.clear();
-    }
+        let content = (node.before.take(), node.after.take());
+        (std::mem::take(&mut node.style), content)
+    };
 
     // Inherited style
     inherited_style(node_rc.clone());
 
     // External style
-    external_style(node_rc.clone(), rules);
+    external_style(node_rc.clone(), rules, visited);
 
     // Inline style
     inline_style(node_rc.clone());
 
-    // Calculate percentage font size
-    percentage_font_size(node_rc.clone());
+    // `inherit`, `initial` and `currentColor`
+    css_wide_keywords(node_rc.clone());
 
-    let children = &node_rc.borrow().children;
-    for child in children {
-        style(child.clone(), rules);
+    // Text of `::before` and `::after`
+    generated_content(node_rc.clone(), rules, visited);
+
+    // Resolve relative and keyword font sizes
+    computed_font_size(node_rc.clone());
+
+    let mut dirty = {
+        let node = node_rc.borrow();
+        node.style != previous || (node.before.clone(), node.after.clone()) != previous_content
+    };
+    for child in &node_rc.borrow().children {
+        style(child.clone(), rules, visited);
+        dirty |= child.borrow().dirty;
     }
+    node_rc.borrow_mut().dirty |= dirty;
 }
 
 fn get_parent_font_size(parent_weak: Option<HTMLNodeWeakRef>) -> String {
@@ -124,3 +287,107 @@ fn get_parent_font_size(parent_weak: Option<HTMLNodeWeakRef>) -> String {
 
     DEFAULT_FONT_SIZE.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::font_manager::parse_font_size;
+    use crate::parser::html_parser::HTMLParser;
+
+    fn font_sizes(html: &str) -> Vec<i32> {
+        let root = HTMLParser::new(html.to_string()).parse().unwrap();
+        style(root.clone(), &Vec::new(), &HashSet::new());
+
+        // html > body > the test's elements
+        let body = root.borrow().children[0].clone();
+        body.borrow()
+            .children
+            .iter()
+            .map(|child| parse_font_size(child.borrow().style.get(STYLE_KEY_FONT_SIZE)))
+            .collect()
+    }
+
+    #[test]
+    fn test_keyword_font_sizes() {
+        let sizes = font_sizes(
+            "<p style=\"font-size:small\">a</p><p style=\"font-size:medium\">b</p><p style=\"font-size:large\">c</p><p style=\"font-size:XX-LARGE\">d</p>",
+        );
+        assert!(sizes[0] < sizes[1]);
+        assert_eq!(sizes[1], DEFAULT_FONT_SIZE_NUM);
+        assert!(sizes[2] > sizes[1]);
+        assert_eq!(sizes[3], 2 * DEFAULT_FONT_SIZE_NUM);
+    }
+
+    #[test]
+    fn test_css_wide_keywords() {
+        let root = HTMLParser::new(
+            "<div style=\"color:red;background-color:blue;font-weight:bold\"><p style=\"color:currentColor;background-color:INHERIT;border-color:currentColor\">a</p><p style=\"color:initial;font-weight:initial;background-color:initial\">b</p></div>".to_string(),
+        )
+        .parse()
+        .unwrap();
+        style(root.clone(), &Vec::new(), &HashSet::new());
+        let div = root.borrow().children[0].borrow().children[0].clone();
+        let value = |child: usize, property: &str| {
+            div.borrow().children[child]
+                .borrow()
+                .style
+                .get(property)
+                .cloned()
+        };
+
+        assert_eq!(value(0, STYLE_KEY_COLOR).as_deref(), Some("red"));
+        assert_eq!(
+            value(0, STYLE_KEY_BACKGROUND_COLOR).as_deref(),
+            Some("blue")
+        );
+        assert_eq!(value(0, "border-color").as_deref(), Some("red"));
+
+        assert_eq!(
+            value(1, STYLE_KEY_COLOR).as_deref(),
+            Some(DEFAULT_COLOR_STR)
+        );
+        assert_eq!(value(1, "font-weight").as_deref(), Some("normal"));
+        assert_eq!(value(1, STYLE_KEY_BACKGROUND_COLOR), None);
+    }
+
+    #[test]
+    fn test_generated_content_after_a_combinator() {
+        let root = HTMLParser::new("<div><p>a</p></div><p>b</p>".to_string())
+            .parse()
+            .unwrap();
+        let rules = CSSParser::new("div p::before { content: \"x\"; }")
+            .parse()
+            .unwrap();
+        style(root.clone(), &rules, &HashSet::new());
+
+        let body = root.borrow().children[0].clone();
+        let div = body.borrow().children[0].clone();
+        let inside = div.borrow().children[0].clone();
+        let outside = body.borrow().children[1].clone();
+        assert_eq!(inside.borrow().before.as_deref(), Some("x"));
+        assert_eq!(outside.borrow().before, None);
+    }
+
+    #[test]
+    fn test_em_font_size() {
+        assert_eq!(
+            font_sizes("<p style=\"font-size:50%\">a</p>"),
+            vec![DEFAULT_FONT_SIZE_NUM / 2]
+        );
+
+        // relative to the parent's computed size
+
+        let root = HTMLParser::new(
+            "<div style=\"font-size:20px\"><p style=\"font-size:1.5em\">a</p></div>".to_string(),
+        )
+        .parse()
+        .unwrap();
+        style(root.clone(), &Vec::new(), &HashSet::new());
+        let div = root.borrow().children[0].borrow().children[0].clone();
+        let p = div.borrow().children[0].clone();
+        assert_eq!(
+            parse_font_size(p.borrow().style.get(STYLE_KEY_FONT_SIZE)),
+            30
+        );
+    }
+}